@@ -58,7 +58,7 @@ open class RustBuffer : Structure() {
     companion object {
         internal fun alloc(size: ULong = 0UL) = uniffiRustCall() { status ->
             // Note: need to convert the size to a `Long` value to make this work with JVM.
-            UniffiLib.INSTANCE.ffi_myapp_core_rustbuffer_alloc(size.toLong(), status)
+            UniffiLib.INSTANCE.ffi_myapp_rustbuffer_alloc(size.toLong(), status)
         }.also {
             if(it.data == null) {
                throw RuntimeException("RustBuffer.alloc() returned null data pointer (size=${size})")
@@ -74,7 +74,7 @@ open class RustBuffer : Structure() {
         }
 
         internal fun free(buf: RustBuffer.ByValue) = uniffiRustCall() { status ->
-            UniffiLib.INSTANCE.ffi_myapp_core_rustbuffer_free(buf, status)
+            UniffiLib.INSTANCE.ffi_myapp_rustbuffer_free(buf, status)
         }
     }
 
@@ -374,7 +374,7 @@ private fun findLibraryName(componentName: String): String {
     if (libOverride != null) {
         return libOverride
     }
-    return "uniffi_myapp"
+    return "myapp"
 }
 
 private inline fun <reified Lib : Library> loadIndirect(
@@ -708,6 +708,34 @@ internal interface UniffiForeignFutureCompleteVoid : com.sun.jna.Callback {
 
 
 
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
 
 
 
@@ -744,155 +772,211 @@ internal interface UniffiLib : Library {
         
     }
 
-    fun uniffi_myapp_core_fn_func_clear_auth(uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_myapp_fn_func_build_refresh_request(uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_build_revoke_request(`token`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_build_token_exchange_request(`code`: RustBuffer.ByValue,`redirectUri`: RustBuffer.ByValue,`codeVerifier`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_clear_auth(uniffi_out_err: UniffiRustCallStatus, 
     ): Unit
-    fun uniffi_myapp_core_fn_func_get_access_token(uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_myapp_fn_func_decode_id_token(`idToken`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_export_auth_state(uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_generate_state(uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_get_access_token(uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_get_api_url(uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_get_auth_url(`redirectUri`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
-    fun uniffi_myapp_core_fn_func_get_api_url(uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_myapp_fn_func_get_auth_url_with_state(`redirectUri`: RustBuffer.ByValue,`state`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
-    fun uniffi_myapp_core_fn_func_get_auth_url(`redirectUri`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_myapp_fn_func_get_current_user(uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
-    fun uniffi_myapp_core_fn_func_get_current_user(uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_myapp_fn_func_get_logout_url(`logoutUri`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
-    fun uniffi_myapp_core_fn_func_get_token_endpoint(uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_myapp_fn_func_get_revoke_endpoint(uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
-    fun uniffi_myapp_core_fn_func_initialize(`config`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_myapp_fn_func_get_token_endpoint(uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_import_auth_state(`blob`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    ): Unit
+    fun uniffi_myapp_fn_func_initialize(`config`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
     ): Unit
-    fun uniffi_myapp_core_fn_func_is_authenticated(uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_myapp_fn_func_is_authenticated(uniffi_out_err: UniffiRustCallStatus, 
     ): Byte
-    fun uniffi_myapp_core_fn_func_set_auth_tokens(`tokens`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_myapp_fn_func_set_auth_tokens(`tokens`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
     ): Unit
-    fun ffi_myapp_core_rustbuffer_alloc(`size`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_myapp_fn_func_token_expiry_info(uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_tokens_from_initiate_auth(`json`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_myapp_fn_func_verify_id_token_signature(`idToken`: RustBuffer.ByValue,`jwksJson`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    ): Byte
+    fun uniffi_myapp_fn_func_verify_state(`expected`: RustBuffer.ByValue,`received`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    ): Byte
+    fun ffi_myapp_rustbuffer_alloc(`size`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
-    fun ffi_myapp_core_rustbuffer_from_bytes(`bytes`: ForeignBytes.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rustbuffer_from_bytes(`bytes`: ForeignBytes.ByValue,uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
-    fun ffi_myapp_core_rustbuffer_free(`buf`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rustbuffer_free(`buf`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
     ): Unit
-    fun ffi_myapp_core_rustbuffer_reserve(`buf`: RustBuffer.ByValue,`additional`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rustbuffer_reserve(`buf`: RustBuffer.ByValue,`additional`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
-    fun ffi_myapp_core_rust_future_poll_u8(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_u8(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_u8(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_u8(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_u8(`handle`: Long,
+    fun ffi_myapp_rust_future_free_u8(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_u8(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_u8(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Byte
-    fun ffi_myapp_core_rust_future_poll_i8(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_i8(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_i8(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_i8(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_i8(`handle`: Long,
+    fun ffi_myapp_rust_future_free_i8(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_i8(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_i8(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Byte
-    fun ffi_myapp_core_rust_future_poll_u16(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_u16(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_u16(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_u16(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_u16(`handle`: Long,
+    fun ffi_myapp_rust_future_free_u16(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_u16(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_u16(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Short
-    fun ffi_myapp_core_rust_future_poll_i16(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_i16(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_i16(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_i16(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_i16(`handle`: Long,
+    fun ffi_myapp_rust_future_free_i16(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_i16(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_i16(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Short
-    fun ffi_myapp_core_rust_future_poll_u32(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_u32(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_u32(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_u32(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_u32(`handle`: Long,
+    fun ffi_myapp_rust_future_free_u32(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_u32(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_u32(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Int
-    fun ffi_myapp_core_rust_future_poll_i32(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_i32(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_i32(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_i32(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_i32(`handle`: Long,
+    fun ffi_myapp_rust_future_free_i32(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_i32(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_i32(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Int
-    fun ffi_myapp_core_rust_future_poll_u64(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_u64(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_u64(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_u64(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_u64(`handle`: Long,
+    fun ffi_myapp_rust_future_free_u64(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_u64(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_u64(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Long
-    fun ffi_myapp_core_rust_future_poll_i64(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_i64(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_i64(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_i64(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_i64(`handle`: Long,
+    fun ffi_myapp_rust_future_free_i64(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_i64(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_i64(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Long
-    fun ffi_myapp_core_rust_future_poll_f32(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_f32(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_f32(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_f32(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_f32(`handle`: Long,
+    fun ffi_myapp_rust_future_free_f32(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_f32(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_f32(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Float
-    fun ffi_myapp_core_rust_future_poll_f64(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_f64(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_f64(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_f64(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_f64(`handle`: Long,
+    fun ffi_myapp_rust_future_free_f64(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_f64(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_f64(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Double
-    fun ffi_myapp_core_rust_future_poll_pointer(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_pointer(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_pointer(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_pointer(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_pointer(`handle`: Long,
+    fun ffi_myapp_rust_future_free_pointer(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_pointer(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_pointer(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Pointer
-    fun ffi_myapp_core_rust_future_poll_rust_buffer(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_rust_buffer(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_rust_buffer(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_rust_buffer(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_rust_buffer(`handle`: Long,
+    fun ffi_myapp_rust_future_free_rust_buffer(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_rust_buffer(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_rust_buffer(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
-    fun ffi_myapp_core_rust_future_poll_void(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+    fun ffi_myapp_rust_future_poll_void(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_cancel_void(`handle`: Long,
+    fun ffi_myapp_rust_future_cancel_void(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_free_void(`handle`: Long,
+    fun ffi_myapp_rust_future_free_void(`handle`: Long,
     ): Unit
-    fun ffi_myapp_core_rust_future_complete_void(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+    fun ffi_myapp_rust_future_complete_void(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): Unit
-    fun uniffi_myapp_core_checksum_func_clear_auth(
+    fun uniffi_myapp_checksum_func_build_refresh_request(
+    ): Short
+    fun uniffi_myapp_checksum_func_build_revoke_request(
+    ): Short
+    fun uniffi_myapp_checksum_func_build_token_exchange_request(
+    ): Short
+    fun uniffi_myapp_checksum_func_clear_auth(
+    ): Short
+    fun uniffi_myapp_checksum_func_decode_id_token(
+    ): Short
+    fun uniffi_myapp_checksum_func_export_auth_state(
+    ): Short
+    fun uniffi_myapp_checksum_func_generate_state(
     ): Short
-    fun uniffi_myapp_core_checksum_func_get_access_token(
+    fun uniffi_myapp_checksum_func_get_access_token(
     ): Short
-    fun uniffi_myapp_core_checksum_func_get_api_url(
+    fun uniffi_myapp_checksum_func_get_api_url(
     ): Short
-    fun uniffi_myapp_core_checksum_func_get_auth_url(
+    fun uniffi_myapp_checksum_func_get_auth_url(
     ): Short
-    fun uniffi_myapp_core_checksum_func_get_current_user(
+    fun uniffi_myapp_checksum_func_get_auth_url_with_state(
     ): Short
-    fun uniffi_myapp_core_checksum_func_get_token_endpoint(
+    fun uniffi_myapp_checksum_func_get_current_user(
     ): Short
-    fun uniffi_myapp_core_checksum_func_initialize(
+    fun uniffi_myapp_checksum_func_get_logout_url(
     ): Short
-    fun uniffi_myapp_core_checksum_func_is_authenticated(
+    fun uniffi_myapp_checksum_func_get_revoke_endpoint(
     ): Short
-    fun uniffi_myapp_core_checksum_func_set_auth_tokens(
+    fun uniffi_myapp_checksum_func_get_token_endpoint(
     ): Short
-    fun ffi_myapp_core_uniffi_contract_version(
+    fun uniffi_myapp_checksum_func_import_auth_state(
+    ): Short
+    fun uniffi_myapp_checksum_func_initialize(
+    ): Short
+    fun uniffi_myapp_checksum_func_is_authenticated(
+    ): Short
+    fun uniffi_myapp_checksum_func_set_auth_tokens(
+    ): Short
+    fun uniffi_myapp_checksum_func_token_expiry_info(
+    ): Short
+    fun uniffi_myapp_checksum_func_tokens_from_initiate_auth(
+    ): Short
+    fun uniffi_myapp_checksum_func_verify_id_token_signature(
+    ): Short
+    fun uniffi_myapp_checksum_func_verify_state(
+    ): Short
+    fun ffi_myapp_uniffi_contract_version(
     ): Int
     
 }
@@ -901,7 +985,7 @@ private fun uniffiCheckContractApiVersion(lib: UniffiLib) {
     // Get the bindings contract version from our ComponentInterface
     val bindings_contract_version = 26
     // Get the scaffolding contract version by calling the into the dylib
-    val scaffolding_contract_version = lib.ffi_myapp_core_uniffi_contract_version()
+    val scaffolding_contract_version = lib.ffi_myapp_uniffi_contract_version()
     if (bindings_contract_version != scaffolding_contract_version) {
         throw RuntimeException("UniFFI contract version mismatch: try cleaning and rebuilding your project")
     }
@@ -909,31 +993,73 @@ private fun uniffiCheckContractApiVersion(lib: UniffiLib) {
 
 @Suppress("UNUSED_PARAMETER")
 private fun uniffiCheckApiChecksums(lib: UniffiLib) {
-    if (lib.uniffi_myapp_core_checksum_func_clear_auth() != 42182.toShort()) {
+    if (lib.uniffi_myapp_checksum_func_build_refresh_request() != 39197.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_build_revoke_request() != 49744.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_build_token_exchange_request() != 23088.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_clear_auth() != 25548.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_decode_id_token() != 50204.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_export_auth_state() != 12022.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_generate_state() != 23135.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_get_access_token() != 6857.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_get_api_url() != 20315.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
-    if (lib.uniffi_myapp_core_checksum_func_get_access_token() != 7629.toShort()) {
+    if (lib.uniffi_myapp_checksum_func_get_auth_url() != 10118.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
-    if (lib.uniffi_myapp_core_checksum_func_get_api_url() != 27096.toShort()) {
+    if (lib.uniffi_myapp_checksum_func_get_auth_url_with_state() != 37392.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
-    if (lib.uniffi_myapp_core_checksum_func_get_auth_url() != 22326.toShort()) {
+    if (lib.uniffi_myapp_checksum_func_get_current_user() != 12356.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
-    if (lib.uniffi_myapp_core_checksum_func_get_current_user() != 49038.toShort()) {
+    if (lib.uniffi_myapp_checksum_func_get_logout_url() != 30337.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
-    if (lib.uniffi_myapp_core_checksum_func_get_token_endpoint() != 22326.toShort()) {
+    if (lib.uniffi_myapp_checksum_func_get_revoke_endpoint() != 65113.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
-    if (lib.uniffi_myapp_core_checksum_func_initialize() != 9564.toShort()) {
+    if (lib.uniffi_myapp_checksum_func_get_token_endpoint() != 38259.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
-    if (lib.uniffi_myapp_core_checksum_func_is_authenticated() != 19214.toShort()) {
+    if (lib.uniffi_myapp_checksum_func_import_auth_state() != 37477.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
-    if (lib.uniffi_myapp_core_checksum_func_set_auth_tokens() != 54306.toShort()) {
+    if (lib.uniffi_myapp_checksum_func_initialize() != 17840.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_is_authenticated() != 51294.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_set_auth_tokens() != 25622.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_token_expiry_info() != 60909.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_tokens_from_initiate_auth() != 20299.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_verify_id_token_signature() != 36087.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_myapp_checksum_func_verify_state() != 27504.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
 }
@@ -1006,6 +1132,29 @@ public object FfiConverterULong: FfiConverter<ULong, Long> {
     }
 }
 
+/**
+ * @suppress
+ */
+public object FfiConverterLong: FfiConverter<Long, Long> {
+    override fun lift(value: Long): Long {
+        return value
+    }
+
+    override fun read(buf: ByteBuffer): Long {
+        return buf.getLong()
+    }
+
+    override fun lower(value: Long): Long {
+        return value
+    }
+
+    override fun allocationSize(value: Long) = 8UL
+
+    override fun write(value: Long, buf: ByteBuffer) {
+        buf.putLong(value)
+    }
+}
+
 /**
  * @suppress
  */
@@ -1088,10 +1237,18 @@ public object FfiConverterString: FfiConverter<String, RustBuffer.ByValue> {
 
 
 
+/**
+ * API configuration
+ */
 data class ApiConfig (
     var `apiUrl`: kotlin.String, 
     var `cognitoDomain`: kotlin.String, 
-    var `cognitoClientId`: kotlin.String
+    var `cognitoClientId`: kotlin.String, 
+    /**
+     * Redirect URIs `get_auth_url` and `get_logout_url` accept; empty
+     * allows any
+     */
+    var `allowedRedirectUris`: List<kotlin.String> = listOf()
 ) {
     
     companion object
@@ -1106,24 +1263,30 @@ public object FfiConverterTypeApiConfig: FfiConverterRustBuffer<ApiConfig> {
             FfiConverterString.read(buf),
             FfiConverterString.read(buf),
             FfiConverterString.read(buf),
+            FfiConverterSequenceString.read(buf),
         )
     }
 
     override fun allocationSize(value: ApiConfig) = (
             FfiConverterString.allocationSize(value.`apiUrl`) +
             FfiConverterString.allocationSize(value.`cognitoDomain`) +
-            FfiConverterString.allocationSize(value.`cognitoClientId`)
+            FfiConverterString.allocationSize(value.`cognitoClientId`) +
+            FfiConverterSequenceString.allocationSize(value.`allowedRedirectUris`)
     )
 
     override fun write(value: ApiConfig, buf: ByteBuffer) {
             FfiConverterString.write(value.`apiUrl`, buf)
             FfiConverterString.write(value.`cognitoDomain`, buf)
             FfiConverterString.write(value.`cognitoClientId`, buf)
+            FfiConverterSequenceString.write(value.`allowedRedirectUris`, buf)
     }
 }
 
 
 
+/**
+ * Authentication state
+ */
 data class AuthTokens (
     var `accessToken`: kotlin.String, 
     var `idToken`: kotlin.String, 
@@ -1164,10 +1327,191 @@ public object FfiConverterTypeAuthTokens: FfiConverterRustBuffer<AuthTokens> {
 
 
 
+/**
+ * When the stored tokens expire, relative to now
+ */
+data class ExpiryInfo (
+    var `expiresAt`: kotlin.ULong, 
+    /**
+     * Negative once the tokens have expired
+     */
+    var `secondsRemaining`: kotlin.Long, 
+    var `isExpired`: kotlin.Boolean
+) {
+    
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypeExpiryInfo: FfiConverterRustBuffer<ExpiryInfo> {
+    override fun read(buf: ByteBuffer): ExpiryInfo {
+        return ExpiryInfo(
+            FfiConverterULong.read(buf),
+            FfiConverterLong.read(buf),
+            FfiConverterBoolean.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: ExpiryInfo) = (
+            FfiConverterULong.allocationSize(value.`expiresAt`) +
+            FfiConverterLong.allocationSize(value.`secondsRemaining`) +
+            FfiConverterBoolean.allocationSize(value.`isExpired`)
+    )
+
+    override fun write(value: ExpiryInfo, buf: ByteBuffer) {
+            FfiConverterULong.write(value.`expiresAt`, buf)
+            FfiConverterLong.write(value.`secondsRemaining`, buf)
+            FfiConverterBoolean.write(value.`isExpired`, buf)
+    }
+}
+
+
+
+/**
+ * HTTP request the host app should send to refresh the stored tokens
+ */
+data class RefreshRequest (
+    var `url`: kotlin.String, 
+    var `body`: kotlin.String, 
+    var `headers`: Map<kotlin.String, kotlin.String>
+) {
+    
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypeRefreshRequest: FfiConverterRustBuffer<RefreshRequest> {
+    override fun read(buf: ByteBuffer): RefreshRequest {
+        return RefreshRequest(
+            FfiConverterString.read(buf),
+            FfiConverterString.read(buf),
+            FfiConverterMapStringString.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: RefreshRequest) = (
+            FfiConverterString.allocationSize(value.`url`) +
+            FfiConverterString.allocationSize(value.`body`) +
+            FfiConverterMapStringString.allocationSize(value.`headers`)
+    )
+
+    override fun write(value: RefreshRequest, buf: ByteBuffer) {
+            FfiConverterString.write(value.`url`, buf)
+            FfiConverterString.write(value.`body`, buf)
+            FfiConverterMapStringString.write(value.`headers`, buf)
+    }
+}
+
+
+
+/**
+ * HTTP request the host app should send to revoke a refresh token
+ */
+data class RevokeRequest (
+    var `url`: kotlin.String, 
+    var `body`: kotlin.String, 
+    var `headers`: Map<kotlin.String, kotlin.String>
+) {
+    
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypeRevokeRequest: FfiConverterRustBuffer<RevokeRequest> {
+    override fun read(buf: ByteBuffer): RevokeRequest {
+        return RevokeRequest(
+            FfiConverterString.read(buf),
+            FfiConverterString.read(buf),
+            FfiConverterMapStringString.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: RevokeRequest) = (
+            FfiConverterString.allocationSize(value.`url`) +
+            FfiConverterString.allocationSize(value.`body`) +
+            FfiConverterMapStringString.allocationSize(value.`headers`)
+    )
+
+    override fun write(value: RevokeRequest, buf: ByteBuffer) {
+            FfiConverterString.write(value.`url`, buf)
+            FfiConverterString.write(value.`body`, buf)
+            FfiConverterMapStringString.write(value.`headers`, buf)
+    }
+}
+
+
+
+/**
+ * HTTP request the host app should send to exchange an authorization code for tokens
+ */
+data class TokenExchangeRequest (
+    var `url`: kotlin.String, 
+    var `body`: kotlin.String, 
+    var `headers`: Map<kotlin.String, kotlin.String>
+) {
+    
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypeTokenExchangeRequest: FfiConverterRustBuffer<TokenExchangeRequest> {
+    override fun read(buf: ByteBuffer): TokenExchangeRequest {
+        return TokenExchangeRequest(
+            FfiConverterString.read(buf),
+            FfiConverterString.read(buf),
+            FfiConverterMapStringString.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: TokenExchangeRequest) = (
+            FfiConverterString.allocationSize(value.`url`) +
+            FfiConverterString.allocationSize(value.`body`) +
+            FfiConverterMapStringString.allocationSize(value.`headers`)
+    )
+
+    override fun write(value: TokenExchangeRequest, buf: ByteBuffer) {
+            FfiConverterString.write(value.`url`, buf)
+            FfiConverterString.write(value.`body`, buf)
+            FfiConverterMapStringString.write(value.`headers`, buf)
+    }
+}
+
+
+
+/**
+ * User info from token
+ */
 data class User (
     var `id`: kotlin.String, 
+    /**
+     * Lowercased, since providers differ in the casing they return
+     */
     var `email`: kotlin.String?, 
-    var `name`: kotlin.String?
+    /**
+     * Whether the provider verified `email`; false when the claim is absent
+     */
+    var `emailVerified`: kotlin.Boolean, 
+    var `name`: kotlin.String?, 
+    /**
+     * E.164, e.g. `+15555550100`
+     */
+    var `phoneNumber`: kotlin.String?, 
+    /**
+     * BCP 47 language tag, e.g. `en-GB`
+     */
+    var `locale`: kotlin.String?, 
+    /**
+     * Profile picture URL
+     */
+    var `picture`: kotlin.String?
 ) {
     
     companion object
@@ -1181,6 +1525,10 @@ public object FfiConverterTypeUser: FfiConverterRustBuffer<User> {
         return User(
             FfiConverterString.read(buf),
             FfiConverterOptionalString.read(buf),
+            FfiConverterBoolean.read(buf),
+            FfiConverterOptionalString.read(buf),
+            FfiConverterOptionalString.read(buf),
+            FfiConverterOptionalString.read(buf),
             FfiConverterOptionalString.read(buf),
         )
     }
@@ -1188,13 +1536,21 @@ public object FfiConverterTypeUser: FfiConverterRustBuffer<User> {
     override fun allocationSize(value: User) = (
             FfiConverterString.allocationSize(value.`id`) +
             FfiConverterOptionalString.allocationSize(value.`email`) +
-            FfiConverterOptionalString.allocationSize(value.`name`)
+            FfiConverterBoolean.allocationSize(value.`emailVerified`) +
+            FfiConverterOptionalString.allocationSize(value.`name`) +
+            FfiConverterOptionalString.allocationSize(value.`phoneNumber`) +
+            FfiConverterOptionalString.allocationSize(value.`locale`) +
+            FfiConverterOptionalString.allocationSize(value.`picture`)
     )
 
     override fun write(value: User, buf: ByteBuffer) {
             FfiConverterString.write(value.`id`, buf)
             FfiConverterOptionalString.write(value.`email`, buf)
+            FfiConverterBoolean.write(value.`emailVerified`, buf)
             FfiConverterOptionalString.write(value.`name`, buf)
+            FfiConverterOptionalString.write(value.`phoneNumber`, buf)
+            FfiConverterOptionalString.write(value.`locale`, buf)
+            FfiConverterOptionalString.write(value.`picture`, buf)
     }
 }
 
@@ -1202,20 +1558,65 @@ public object FfiConverterTypeUser: FfiConverterRustBuffer<User> {
 
 
 
-sealed class CoreException(message: String): kotlin.Exception(message) {
-        
-        class NotAuthenticated(message: String) : CoreException(message)
-        
-        class TokenExpired(message: String) : CoreException(message)
+/**
+ * Errors that can occur
+ */
+sealed class CoreException: kotlin.Exception() {
+    
+    class NotAuthenticated(
+        ) : CoreException() {
+        override val message
+            get() = ""
+    }
+    
+    class TokenExpired(
+        ) : CoreException() {
+        override val message
+            get() = ""
+    }
+    
+    class NotInitialized(
+        ) : CoreException() {
+        override val message
+            get() = ""
+    }
+    
+    class Network(
         
-        class Network(message: String) : CoreException(message)
+        val `msg`: kotlin.String
+        ) : CoreException() {
+        override val message
+            get() = "msg=${ `msg` }"
+    }
+    
+    class InvalidResponse(
         
-        class InvalidResponse(message: String) : CoreException(message)
+        val `msg`: kotlin.String
+        ) : CoreException() {
+        override val message
+            get() = "msg=${ `msg` }"
+    }
+    
+    class NoRefreshNeeded(
+        ) : CoreException() {
+        override val message
+            get() = ""
+    }
+    
+    class RedirectNotAllowed(
         
+        val `uri`: kotlin.String
+        ) : CoreException() {
+        override val message
+            get() = "uri=${ `uri` }"
+    }
+    
 
     companion object ErrorHandler : UniffiRustCallStatusErrorHandler<CoreException> {
         override fun lift(error_buf: RustBuffer.ByValue): CoreException = FfiConverterTypeCoreError.lift(error_buf)
     }
+
+    
 }
 
 /**
@@ -1224,18 +1625,59 @@ sealed class CoreException(message: String): kotlin.Exception(message) {
 public object FfiConverterTypeCoreError : FfiConverterRustBuffer<CoreException> {
     override fun read(buf: ByteBuffer): CoreException {
         
-            return when(buf.getInt()) {
-            1 -> CoreException.NotAuthenticated(FfiConverterString.read(buf))
-            2 -> CoreException.TokenExpired(FfiConverterString.read(buf))
-            3 -> CoreException.Network(FfiConverterString.read(buf))
-            4 -> CoreException.InvalidResponse(FfiConverterString.read(buf))
+
+        return when(buf.getInt()) {
+            1 -> CoreException.NotAuthenticated()
+            2 -> CoreException.TokenExpired()
+            3 -> CoreException.NotInitialized()
+            4 -> CoreException.Network(
+                FfiConverterString.read(buf),
+                )
+            5 -> CoreException.InvalidResponse(
+                FfiConverterString.read(buf),
+                )
+            6 -> CoreException.NoRefreshNeeded()
+            7 -> CoreException.RedirectNotAllowed(
+                FfiConverterString.read(buf),
+                )
             else -> throw RuntimeException("invalid error enum value, something is very wrong!!")
         }
-        
     }
 
     override fun allocationSize(value: CoreException): ULong {
-        return 4UL
+        return when(value) {
+            is CoreException.NotAuthenticated -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+            )
+            is CoreException.TokenExpired -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+            )
+            is CoreException.NotInitialized -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+            )
+            is CoreException.Network -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+                + FfiConverterString.allocationSize(value.`msg`)
+            )
+            is CoreException.InvalidResponse -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+                + FfiConverterString.allocationSize(value.`msg`)
+            )
+            is CoreException.NoRefreshNeeded -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+            )
+            is CoreException.RedirectNotAllowed -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+                + FfiConverterString.allocationSize(value.`uri`)
+            )
+        }
     }
 
     override fun write(value: CoreException, buf: ByteBuffer) {
@@ -1248,12 +1690,27 @@ public object FfiConverterTypeCoreError : FfiConverterRustBuffer<CoreException>
                 buf.putInt(2)
                 Unit
             }
-            is CoreException.Network -> {
+            is CoreException.NotInitialized -> {
                 buf.putInt(3)
                 Unit
             }
-            is CoreException.InvalidResponse -> {
+            is CoreException.Network -> {
                 buf.putInt(4)
+                FfiConverterString.write(value.`msg`, buf)
+                Unit
+            }
+            is CoreException.InvalidResponse -> {
+                buf.putInt(5)
+                FfiConverterString.write(value.`msg`, buf)
+                Unit
+            }
+            is CoreException.NoRefreshNeeded -> {
+                buf.putInt(6)
+                Unit
+            }
+            is CoreException.RedirectNotAllowed -> {
+                buf.putInt(7)
+                FfiConverterString.write(value.`uri`, buf)
                 Unit
             }
         }.let { /* this makes the `when` an expression, which ensures it is exhaustive */ }
@@ -1291,88 +1748,370 @@ public object FfiConverterOptionalString: FfiConverterRustBuffer<kotlin.String?>
             FfiConverterString.write(value, buf)
         }
     }
-} fun `clearAuth`()
+}
+
+
+
+
+/**
+ * @suppress
+ */
+public object FfiConverterSequenceString: FfiConverterRustBuffer<List<kotlin.String>> {
+    override fun read(buf: ByteBuffer): List<kotlin.String> {
+        val len = buf.getInt()
+        return List<kotlin.String>(len) {
+            FfiConverterString.read(buf)
+        }
+    }
+
+    override fun allocationSize(value: List<kotlin.String>): ULong {
+        val sizeForLength = 4UL
+        val sizeForItems = value.map { FfiConverterString.allocationSize(it) }.sum()
+        return sizeForLength + sizeForItems
+    }
+
+    override fun write(value: List<kotlin.String>, buf: ByteBuffer) {
+        buf.putInt(value.size)
+        value.iterator().forEach {
+            FfiConverterString.write(it, buf)
+        }
+    }
+}
+
+
+
+
+/**
+ * @suppress
+ */
+public object FfiConverterMapStringString: FfiConverterRustBuffer<Map<kotlin.String, kotlin.String>> {
+    override fun read(buf: ByteBuffer): Map<kotlin.String, kotlin.String> {
+        val len = buf.getInt()
+        return buildMap<kotlin.String, kotlin.String>(len) {
+            repeat(len) {
+                val k = FfiConverterString.read(buf)
+                val v = FfiConverterString.read(buf)
+                this[k] = v
+            }
+        }
+    }
+
+    override fun allocationSize(value: Map<kotlin.String, kotlin.String>): ULong {
+        val spaceForMapSize = 4UL
+        val spaceForChildren = value.map { (k, v) ->
+            FfiConverterString.allocationSize(k) +
+            FfiConverterString.allocationSize(v)
+        }.sum()
+        return spaceForMapSize + spaceForChildren
+    }
+
+    override fun write(value: Map<kotlin.String, kotlin.String>, buf: ByteBuffer) {
+        buf.putInt(value.size)
+        // The parens on `(k, v)` here ensure we're calling the right method,
+        // which is important for compatibility with older android devices.
+        // Ref https://blog.danlew.net/2017/03/16/kotlin-puzzler-whose-line-is-it-anyways/
+        value.forEach { (k, v) ->
+            FfiConverterString.write(k, buf)
+            FfiConverterString.write(v, buf)
+        }
+    }
+}
+        /**
+         * Build the `refresh_token` grant request for the stored tokens. Errors with
+         * `NoRefreshNeeded` until the access token is within `REFRESH_LEAD_SECS` of
+         * expiring, and `NotAuthenticated` when there is no refresh token to use.
+         */
+    @Throws(CoreException::class) fun `buildRefreshRequest`(): RefreshRequest {
+            return FfiConverterTypeRefreshRequest.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_build_refresh_request(
+        _status)
+}
+    )
+    }
+    
+
+        /**
+         * Build the request that revokes a refresh token at Cognito.
+         * The host POSTs it on logout and then calls `clear_auth`.
+         */
+    @Throws(CoreException::class) fun `buildRevokeRequest`(`token`: kotlin.String): RevokeRequest {
+            return FfiConverterTypeRevokeRequest.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_build_revoke_request(
+        FfiConverterString.lower(`token`),_status)
+}
+    )
+    }
+    
+
+        /**
+         * Build the `authorization_code` grant request for a code from the redirect.
+         * `redirect_uri` must match the one used for the authorize URL; pass the
+         * PKCE `code_verifier` when the flow used a code challenge.
+         */
+    @Throws(CoreException::class) fun `buildTokenExchangeRequest`(`code`: kotlin.String, `redirectUri`: kotlin.String, `codeVerifier`: kotlin.String?): TokenExchangeRequest {
+            return FfiConverterTypeTokenExchangeRequest.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_build_token_exchange_request(
+        FfiConverterString.lower(`code`),FfiConverterString.lower(`redirectUri`),FfiConverterOptionalString.lower(`codeVerifier`),_status)
+}
+    )
+    }
+    
+
+        /**
+         * Clear authentication (logout)
+         */ fun `clearAuth`()
         = 
     uniffiRustCall() { _status ->
-    UniffiLib.INSTANCE.uniffi_myapp_core_fn_func_clear_auth(
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_clear_auth(
         _status)
 }
     
     
 
+        /**
+         * Decode user info from an arbitrary ID token (does not verify the signature)
+         */
+    @Throws(CoreException::class) fun `decodeIdToken`(`idToken`: kotlin.String): User {
+            return FfiConverterTypeUser.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_decode_id_token(
+        FfiConverterString.lower(`idToken`),_status)
+}
+    )
+    }
+    
+
+        /**
+         * Serialize the current tokens so the host app can persist them (e.g. in the keystore)
+         */ fun `exportAuthState`(): kotlin.String? {
+            return FfiConverterOptionalString.lift(
+    uniffiRustCall() { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_export_auth_state(
+        _status)
+}
+    )
+    }
+    
+
+        /**
+         * Generate a random OAuth `state` value; the host stores it and checks the
+         * callback's `state` with `verify_state`
+         */ fun `generateState`(): kotlin.String {
+            return FfiConverterString.lift(
+    uniffiRustCall() { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_generate_state(
+        _status)
+}
+    )
+    }
+    
+
+        /**
+         * Get current access token for API calls
+         */
     @Throws(CoreException::class) fun `getAccessToken`(): kotlin.String {
             return FfiConverterString.lift(
     uniffiRustCallWithError(CoreException) { _status ->
-    UniffiLib.INSTANCE.uniffi_myapp_core_fn_func_get_access_token(
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_get_access_token(
         _status)
 }
     )
     }
     
 
+        /**
+         * Get configured API URL
+         */
     @Throws(CoreException::class) fun `getApiUrl`(): kotlin.String {
             return FfiConverterString.lift(
     uniffiRustCallWithError(CoreException) { _status ->
-    UniffiLib.INSTANCE.uniffi_myapp_core_fn_func_get_api_url(
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_get_api_url(
         _status)
 }
     )
     }
     
 
+        /**
+         * Get the OAuth authorization URL
+         */
     @Throws(CoreException::class) fun `getAuthUrl`(`redirectUri`: kotlin.String): kotlin.String {
             return FfiConverterString.lift(
     uniffiRustCallWithError(CoreException) { _status ->
-    UniffiLib.INSTANCE.uniffi_myapp_core_fn_func_get_auth_url(
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_get_auth_url(
         FfiConverterString.lower(`redirectUri`),_status)
 }
     )
     }
     
 
+        /**
+         * Get the OAuth authorization URL including a CSRF `state` value
+         * (see `generate_state`)
+         */
+    @Throws(CoreException::class) fun `getAuthUrlWithState`(`redirectUri`: kotlin.String, `state`: kotlin.String): kotlin.String {
+            return FfiConverterString.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_get_auth_url_with_state(
+        FfiConverterString.lower(`redirectUri`),FfiConverterString.lower(`state`),_status)
+}
+    )
+    }
+    
+
+        /**
+         * Get current user info (parsed from ID token)
+         */
     @Throws(CoreException::class) fun `getCurrentUser`(): User {
             return FfiConverterTypeUser.lift(
     uniffiRustCallWithError(CoreException) { _status ->
-    UniffiLib.INSTANCE.uniffi_myapp_core_fn_func_get_current_user(
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_get_current_user(
+        _status)
+}
+    )
+    }
+    
+
+        /**
+         * Get the Cognito logout URL, which ends the hosted UI session and then
+         * sends the browser to `logout_uri`
+         */
+    @Throws(CoreException::class) fun `getLogoutUrl`(`logoutUri`: kotlin.String): kotlin.String {
+            return FfiConverterString.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_get_logout_url(
+        FfiConverterString.lower(`logoutUri`),_status)
+}
+    )
+    }
+    
+
+        /**
+         * Get the token revocation endpoint URL
+         */
+    @Throws(CoreException::class) fun `getRevokeEndpoint`(): kotlin.String {
+            return FfiConverterString.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_get_revoke_endpoint(
         _status)
 }
     )
     }
     
 
+        /**
+         * Get the token endpoint URL
+         */
     @Throws(CoreException::class) fun `getTokenEndpoint`(): kotlin.String {
             return FfiConverterString.lift(
     uniffiRustCallWithError(CoreException) { _status ->
-    UniffiLib.INSTANCE.uniffi_myapp_core_fn_func_get_token_endpoint(
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_get_token_endpoint(
         _status)
 }
     )
     }
     
- fun `initialize`(`config`: ApiConfig)
+
+        /**
+         * Restore tokens previously returned by `export_auth_state`
+         */
+    @Throws(CoreException::class) fun `importAuthState`(`blob`: kotlin.String)
+        = 
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_import_auth_state(
+        FfiConverterString.lower(`blob`),_status)
+}
+    
+    
+
+        /**
+         * Initialize the SDK with configuration
+         */ fun `initialize`(`config`: ApiConfig)
         = 
     uniffiRustCall() { _status ->
-    UniffiLib.INSTANCE.uniffi_myapp_core_fn_func_initialize(
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_initialize(
         FfiConverterTypeApiConfig.lower(`config`),_status)
 }
     
     
- fun `isAuthenticated`(): kotlin.Boolean {
+
+        /**
+         * Check if user is authenticated
+         */ fun `isAuthenticated`(): kotlin.Boolean {
             return FfiConverterBoolean.lift(
     uniffiRustCall() { _status ->
-    UniffiLib.INSTANCE.uniffi_myapp_core_fn_func_is_authenticated(
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_is_authenticated(
         _status)
 }
     )
     }
     
- fun `setAuthTokens`(`tokens`: AuthTokens)
+
+        /**
+         * Store authentication tokens after login
+         */ fun `setAuthTokens`(`tokens`: AuthTokens)
         = 
     uniffiRustCall() { _status ->
-    UniffiLib.INSTANCE.uniffi_myapp_core_fn_func_set_auth_tokens(
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_set_auth_tokens(
         FfiConverterTypeAuthTokens.lower(`tokens`),_status)
 }
     
     
 
+        /**
+         * Expiry of the stored tokens, for "session expires in ..." UI
+         */
+    @Throws(CoreException::class) fun `tokenExpiryInfo`(): ExpiryInfo {
+            return FfiConverterTypeExpiryInfo.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_token_expiry_info(
+        _status)
+}
+    )
+    }
+    
+
+        /**
+         * Build tokens from the JSON returned by the AWS SDK's `InitiateAuth` (SRP flow)
+         */
+    @Throws(CoreException::class) fun `tokensFromInitiateAuth`(`json`: kotlin.String): AuthTokens {
+            return FfiConverterTypeAuthTokens.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_tokens_from_initiate_auth(
+        FfiConverterString.lower(`json`),_status)
+}
+    )
+    }
+    
+
+        /**
+         * Check an ID token's RS256 signature against a JWKS document the host app
+         * fetched (the Cognito issuer's `/.well-known/jwks.json`). Returns `false`
+         * for a tampered token; claims such as `exp` are not checked.
+         */
+    @Throws(CoreException::class) fun `verifyIdTokenSignature`(`idToken`: kotlin.String, `jwksJson`: kotlin.String): kotlin.Boolean {
+            return FfiConverterBoolean.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_verify_id_token_signature(
+        FfiConverterString.lower(`idToken`),FfiConverterString.lower(`jwksJson`),_status)
+}
+    )
+    }
+    
+
+        /**
+         * Compare the stored and returned `state` values in constant time
+         */ fun `verifyState`(`expected`: kotlin.String, `received`: kotlin.String): kotlin.Boolean {
+            return FfiConverterBoolean.lift(
+    uniffiRustCall() { _status ->
+    UniffiLib.INSTANCE.uniffi_myapp_fn_func_verify_state(
+        FfiConverterString.lower(`expected`),FfiConverterString.lower(`received`),_status)
+}
+    )
+    }
+    
+
 
@@ -7,12 +7,20 @@ interface Item {
   description?: string
   created_at: string
   updated_at: string
+  created_by: string
+  expires_at?: number
+  slug?: string
+  deleted_at?: string
+  metadata?: Record<string, string>
+  public: boolean
 }
 
 interface ApiResponse<T> {
   success: boolean
   data?: T
   error?: string
+  request_id?: string
+  fields?: { field: string; message: string }[]
 }
 
 function App() {
@@ -39,7 +47,7 @@ function App() {
     try {
       setLoading(true)
       const res = await api('/items')
-      const data: ApiResponse<{ items: Item[]; count: number }> = await res.json()
+      const data: ApiResponse<{ items: Item[]; page_count: number; has_more: boolean }> = await res.json()
       if (data.success && data.data) setItems(data.data.items)
       else setError(data.error || 'Failed to fetch items')
     } catch (err) {
@@ -17,6 +17,12 @@ resource "aws_lambda_function" "api" {
       TABLE_NAME     = aws_dynamodb_table.main.name
       STORAGE_BUCKET = aws_s3_bucket.storage.bucket
       ALLOWED_ORIGIN = "https://${aws_cloudfront_distribution.frontend.domain_name}"
+      CORS_MAX_AGE   = 3600
+      READ_ONLY      = var.read_only
+      PUBLIC_READS   = var.public_reads
+      TTL_ATTRIBUTE  = local.ttl_attribute
+      # Leave a few seconds for a clean 504 before the Lambda itself times out
+      HANDLER_TIMEOUT_MS = (var.lambda_timeout - 3) * 1000
       COGNITO_ISSUER    = "https://cognito-idp.${var.aws_region}.amazonaws.com/${aws_cognito_user_pool.main.id}"
       COGNITO_CLIENT_ID = aws_cognito_user_pool_client.frontend.id
     }
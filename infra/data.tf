@@ -15,6 +15,7 @@ resource "aws_dynamodb_table" "main" {
     type = "S"
   }
 
+  # gsi1: items by creation time (ITEM / created_at)
   attribute {
     name = "gsi1pk"
     type = "S"
@@ -25,6 +26,42 @@ resource "aws_dynamodb_table" "main" {
     type = "S"
   }
 
+  # gsi2: items per owner, sorted by lowercased name (USER#{id} / NAME#{name})
+  attribute {
+    name = "gsi2pk"
+    type = "S"
+  }
+
+  attribute {
+    name = "gsi2sk"
+    type = "S"
+  }
+
+  # gsi3: items by last update (ITEM / updated_at), for modified_since.
+  # Items written before it existed need scripts/backfill-index-keys.sh
+  attribute {
+    name = "gsi3pk"
+    type = "S"
+  }
+
+  attribute {
+    name = "gsi3sk"
+    type = "S"
+  }
+
+  # gsi4: live items by visibility (PUBLIC or PRIVATE#<owner> / sk), so
+  # lists read only what the caller may see. Items written before it existed
+  # need scripts/backfill-visibility-keys.sh
+  attribute {
+    name = "gsi4pk"
+    type = "S"
+  }
+
+  attribute {
+    name = "gsi4sk"
+    type = "S"
+  }
+
   global_secondary_index {
     name            = "gsi1"
     hash_key        = "gsi1pk"
@@ -32,12 +69,33 @@ resource "aws_dynamodb_table" "main" {
     projection_type = "ALL"
   }
 
+  global_secondary_index {
+    name            = "gsi2"
+    hash_key        = "gsi2pk"
+    range_key       = "gsi2sk"
+    projection_type = "ALL"
+  }
+
+  global_secondary_index {
+    name            = "gsi3"
+    hash_key        = "gsi3pk"
+    range_key       = "gsi3sk"
+    projection_type = "ALL"
+  }
+
+  global_secondary_index {
+    name            = "gsi4"
+    hash_key        = "gsi4pk"
+    range_key       = "gsi4sk"
+    projection_type = "ALL"
+  }
+
   point_in_time_recovery {
     enabled = var.environment == "prod"
   }
 
   ttl {
-    attribute_name = "ttl"
+    attribute_name = local.ttl_attribute
     enabled        = true
   }
 
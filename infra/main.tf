@@ -29,4 +29,6 @@ provider "aws" {
 
 locals {
   prefix = "${var.project_name}-${var.environment}"
+  # The table's TTL column; the Lambda writes item expiry under the same name
+  ttl_attribute = "ttl"
 }
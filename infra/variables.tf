@@ -22,6 +22,18 @@ variable "lambda_memory_size" {
   default     = 256
 }
 
+variable "read_only" {
+  description = "Put the API in maintenance mode: writes return 503, reads keep working"
+  type        = bool
+  default     = false
+}
+
+variable "public_reads" {
+  description = "Let callers without a token list and fetch items marked public; writes still need one"
+  type        = bool
+  default     = false
+}
+
 variable "lambda_timeout" {
   description = "Timeout for Lambda functions in seconds"
   type        = number
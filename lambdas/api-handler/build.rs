@@ -0,0 +1,23 @@
+//! Embeds deployment metadata reported by the health endpoint
+
+fn main() {
+    // CI sets GITHUB_SHA; GIT_SHA allows overriding it for other pipelines
+    let git_sha = std::env::var("GIT_SHA").or_else(|_| std::env::var("GITHUB_SHA"));
+    if let Ok(sha) = git_sha {
+        println!("cargo:rustc-env=BUILD_GIT_SHA={sha}");
+    }
+
+    if let Ok(time) = std::env::var("BUILD_TIME") {
+        println!("cargo:rustc-env=BUILD_TIME={time}");
+    }
+
+    // TARGET is always provided to build scripts
+    if let Ok(target) = std::env::var("TARGET") {
+        println!("cargo:rustc-env=BUILD_TARGET={target}");
+    }
+
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-env-changed=GITHUB_SHA");
+    println!("cargo:rerun-if-env-changed=BUILD_TIME");
+    println!("cargo:rerun-if-changed=build.rs");
+}
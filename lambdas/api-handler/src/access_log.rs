@@ -0,0 +1,149 @@
+use aws_lambda_events::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
+use aws_lambda_events::encodings::Body;
+use std::cell::RefCell;
+use std::future::Future;
+use std::time::Duration;
+use tracing::info;
+
+tokio::task_local! {
+    /// Id of the caller the auth middleware accepted for the current request
+    static CALLER: RefCell<Option<String>>;
+}
+
+/// Note the caller the auth middleware accepted, so the access log can name
+/// them without validating the token a second time. Does nothing outside
+/// `capture_caller`.
+pub fn record_caller(user_id: &str) {
+    let _ = CALLER.try_with(|caller| *caller.borrow_mut() = Some(user_id.to_string()));
+}
+
+/// Run `handler`, returning its output and the caller it recorded, if any
+pub async fn capture_caller<F: Future>(handler: F) -> (F::Output, Option<String>) {
+    CALLER
+        .scope(RefCell::new(None), async {
+            let output = handler.await;
+            (output, CALLER.with(|caller| caller.take()))
+        })
+        .await
+}
+
+/// One line per request for log ingestion, emitted after the handler finishes
+#[derive(Debug, PartialEq)]
+pub struct AccessLog {
+    pub method: String,
+    pub path: String,
+    /// API Gateway request id, also returned to clients in error bodies
+    pub request_id: Option<String>,
+    pub status: i64,
+    pub user_id: Option<String>,
+    pub bytes_out: usize,
+    pub duration_ms: u64,
+}
+
+impl AccessLog {
+    /// A missing response (the handler returned an error) is logged as a 500
+    pub fn new(
+        request: &ApiGatewayV2httpRequest,
+        response: Option<&ApiGatewayV2httpResponse>,
+        user_id: Option<String>,
+        duration: Duration,
+    ) -> Self {
+        let bytes_out = match response.and_then(|r| r.body.as_ref()) {
+            Some(Body::Text(text)) => text.len(),
+            Some(Body::Binary(bytes)) => bytes.len(),
+            _ => 0,
+        };
+
+        Self {
+            method: request.request_context.http.method.to_string(),
+            path: request.raw_path.clone().unwrap_or_else(|| "/".to_string()),
+            request_id: request.request_context.request_id.clone(),
+            status: response.map_or(500, |r| r.status_code),
+            user_id,
+            bytes_out,
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+
+    pub fn emit(&self) {
+        info!(
+            target: "access_log",
+            method = %self.method,
+            path = %self.path,
+            request_id = self.request_id.as_deref(),
+            status = self.status,
+            user_id = self.user_id.as_deref(),
+            bytes_out = self.bytes_out,
+            duration_ms = self.duration_ms,
+            "Access"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> ApiGatewayV2httpRequest {
+        let mut request = ApiGatewayV2httpRequest::default();
+        request.request_context.http.method = "POST".parse().unwrap();
+        request.raw_path = Some("/items".to_string());
+        request.request_context.request_id = Some("req-123".to_string());
+        request
+    }
+
+    #[test]
+    fn test_access_log_from_request_and_response() {
+        let response = ApiGatewayV2httpResponse {
+            status_code: 201,
+            body: Some(Body::Text(r#"{"success":true}"#.to_string())),
+            ..Default::default()
+        };
+
+        let log = AccessLog::new(
+            &request(),
+            Some(&response),
+            Some("user-1".to_string()),
+            Duration::from_micros(12_500),
+        );
+
+        assert_eq!(
+            log,
+            AccessLog {
+                method: "POST".to_string(),
+                path: "/items".to_string(),
+                request_id: Some("req-123".to_string()),
+                status: 201,
+                user_id: Some("user-1".to_string()),
+                bytes_out: 16,
+                duration_ms: 12,
+            }
+        );
+    }
+
+    #[test]
+    fn test_access_log_without_response_or_user() {
+        let log = AccessLog::new(&request(), None, None, Duration::ZERO);
+
+        assert_eq!(log.status, 500);
+        assert_eq!(log.bytes_out, 0);
+        assert!(log.user_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_capture_caller_returns_the_recorded_caller() {
+        let (output, caller) = capture_caller(async {
+            record_caller("user-1");
+            201
+        })
+        .await;
+        assert_eq!(output, 201);
+        assert_eq!(caller.as_deref(), Some("user-1"));
+
+        let (_, caller) = capture_caller(async {}).await;
+        assert_eq!(caller, None);
+
+        // Outside a capture there is nowhere to record to
+        record_caller("user-2");
+    }
+}
@@ -1,21 +1,139 @@
 #![allow(dead_code)]
 
 use aws_lambda_events::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
+use jsonwebtoken::errors::ErrorKind;
 use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, TokenData, Validation};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 
+use crate::clock::{Clock, SystemClock};
+use crate::response::ResponseBuilder;
 use crate::{json_response, ApiResponse};
 
-/// Cached JWKS (JSON Web Key Set) from Cognito
-static JWKS_CACHE: RwLock<Option<JwksCache>> = RwLock::new(None);
+/// Scope required to list and fetch items
+pub const SCOPE_ITEMS_READ: &str = "items:read";
 
-#[derive(Clone)]
+/// Scope required to create, update and delete items
+pub const SCOPE_ITEMS_WRITE: &str = "items:write";
+
+/// Scope required for the `/admin` routes
+pub const SCOPE_ADMIN: &str = "admin";
+
+/// Cached JWKS (JSON Web Key Set) per issuer
+static JWKS_CACHE: RwLock<Option<JwksCache>> = RwLock::const_new(None);
+
+/// How long fetched keys are trusted before the JWKS is fetched again
+const JWKS_TTL: Duration = Duration::from_secs(3600);
+
+/// How far past `JWKS_TTL` cached keys are still used while the JWKS
+/// endpoint is failing
+const JWKS_STALE_GRACE: Duration = Duration::from_secs(6 * 3600);
+
+/// Minimum gap between fetch attempts after one fails, so an outage doesn't
+/// add a slow failing fetch to every request
+const JWKS_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Shared HTTP client, reused across warm invocations
+static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Key ID from a token header, naming one key in the issuer's JWKS
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Kid(String);
+
+impl Kid {
+    fn new(value: impl Into<String>) -> Result<Self, &'static str> {
+        let value = value.into();
+        if value.is_empty() {
+            return Err("Token missing key ID");
+        }
+        Ok(Self(value))
+    }
+
+    fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Token issuer (the Cognito user pool URL). Checked once to be an https
+/// URL, since the JWKS location is derived from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Issuer(String);
+
+impl Issuer {
+    fn new(value: impl Into<String>) -> Result<Self, &'static str> {
+        let value = value.into();
+        let host = value
+            .strip_prefix("https://")
+            .and_then(|rest| rest.split('/').next())
+            .unwrap_or("");
+        if host.is_empty() || value.chars().any(char::is_whitespace) {
+            return Err("Token issuer must be an https URL");
+        }
+        Ok(Self(value))
+    }
+
+    fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Keys by kid for each issuer, each set with its own fetch time so tokens
+/// from different user pools don't evict one another
+#[derive(Clone, Default)]
 struct JwksCache {
-    keys: HashMap<String, DecodingKey>,
-    fetched_at: std::time::Instant,
+    issuers: HashMap<String, (HashMap<String, DecodingKey>, Instant)>,
+    /// When each issuer's last fetch failed, cleared by a successful fetch
+    failed_at: HashMap<String, Instant>,
+}
+
+impl JwksCache {
+    /// The issuer's key for `kid`, if its key set is younger than `JWKS_TTL`
+    fn get(&self, issuer: &Issuer, kid: &Kid, now: Instant) -> Option<DecodingKey> {
+        self.get_within(issuer, kid, now, JWKS_TTL)
+    }
+
+    /// A key past its TTL but inside the grace window, for use while fetches fail
+    fn get_stale(&self, issuer: &Issuer, kid: &Kid, now: Instant) -> Option<DecodingKey> {
+        self.get_within(issuer, kid, now, JWKS_TTL + JWKS_STALE_GRACE)
+    }
+
+    fn get_within(
+        &self,
+        issuer: &Issuer,
+        kid: &Kid,
+        now: Instant,
+        max_age: Duration,
+    ) -> Option<DecodingKey> {
+        let (keys, fetched_at) = self.issuers.get(issuer.as_str())?;
+        if now.duration_since(*fetched_at) >= max_age {
+            return None;
+        }
+        keys.get(kid.as_str()).cloned()
+    }
+
+    /// Replace the issuer's key set, leaving other issuers untouched
+    fn insert(&mut self, issuer: &Issuer, keys: HashMap<String, DecodingKey>, now: Instant) {
+        self.issuers
+            .insert(issuer.as_str().to_string(), (keys, now));
+        self.failed_at.remove(issuer.as_str());
+    }
+
+    fn record_failure(&mut self, issuer: &Issuer, now: Instant) {
+        self.failed_at.insert(issuer.as_str().to_string(), now);
+    }
+
+    /// Whether the issuer's last fetch failed within `JWKS_RETRY_INTERVAL`
+    fn recently_failed(&self, issuer: &Issuer, now: Instant) -> bool {
+        self.failed_at
+            .get(issuer.as_str())
+            .is_some_and(|failed_at| now.duration_since(*failed_at) < JWKS_RETRY_INTERVAL)
+    }
 }
 
 /// JWKS response from Cognito
@@ -45,6 +163,13 @@ pub struct Claims {
     pub token_use: String,
     pub exp: usize,
     pub iat: usize,
+    #[serde(rename = "cognito:groups", default)]
+    pub groups: Vec<String>,
+    /// Space-separated OAuth scopes (access tokens only)
+    pub scope: Option<String>,
+    /// Claims without a field above, e.g. `custom:tenant_id`
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
 }
 
 /// Authenticated user info extracted from token
@@ -53,23 +178,144 @@ pub struct AuthUser {
     pub id: String,
     pub email: Option<String>,
     pub name: Option<String>,
+    pub groups: Vec<String>,
+    pub scopes: Vec<String>,
+    /// Every claim in the token, for custom ones the fields above don't cover
+    pub claims: Map<String, Value>,
+}
+
+impl AuthUser {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    pub fn in_group(&self, group: &str) -> bool {
+        self.groups.iter().any(|g| g == group)
+    }
 }
 
 impl From<Claims> for AuthUser {
     fn from(claims: Claims) -> Self {
+        // Absent optional claims serialize as null; leave them out
+        let all_claims = match serde_json::to_value(&claims) {
+            Ok(Value::Object(map)) => map.into_iter().filter(|(_, v)| !v.is_null()).collect(),
+            _ => Map::new(),
+        };
+        let scopes = claims
+            .scope
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+
         Self {
             id: claims.sub,
             email: claims.email,
             name: claims.name,
+            groups: claims.groups,
+            scopes,
+            claims: all_claims,
         }
     }
 }
 
+/// Cognito `token_use` claim values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenUse {
+    Id,
+    Access,
+}
+
+impl TokenUse {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "id" => Some(TokenUse::Id),
+            "access" => Some(TokenUse::Access),
+            _ => None,
+        }
+    }
+
+    /// Token type required on protected API routes (REQUIRED_TOKEN_USE, default "access")
+    pub fn required_for_api() -> Self {
+        std::env::var("REQUIRED_TOKEN_USE")
+            .ok()
+            .and_then(|v| Self::parse(&v))
+            .unwrap_or(TokenUse::Access)
+    }
+}
+
+/// Check the token_use claim; unknown values are always rejected
+fn check_token_use(token_use: &str, required: Option<TokenUse>) -> Result<(), &'static str> {
+    let token_use = TokenUse::parse(token_use).ok_or("Invalid token type")?;
+    match required {
+        Some(TokenUse::Access) if token_use != TokenUse::Access => Err("Access token required"),
+        Some(TokenUse::Id) if token_use != TokenUse::Id => Err("ID token required"),
+        _ => Ok(()),
+    }
+}
+
+/// Stable tag for an authentication failure, for CloudWatch metric filters
+fn failure_reason(message: &str) -> &'static str {
+    match message {
+        "Missing authorization header" => "missing_header",
+        "Invalid token format" | "Token missing key ID" | "Invalid token" => "bad_format",
+        "Token expired" => "expired",
+        "Invalid token issuer" => "wrong_issuer",
+        "Key ID not found in JWKS" => "unknown_kid",
+        "Invalid token signature" => "signature",
+        "Invalid token type" | "Access token required" | "ID token required" => "wrong_token_use",
+        "Failed to fetch JWKS" | "Failed to parse JWKS" | "No valid RSA keys in JWKS" => "upstream",
+        "COGNITO_ISSUER not configured" | "Token issuer must be an https URL" => "config",
+        _ => "other",
+    }
+}
+
+/// Response for a failed `validate_token`: 401 when the token is at fault,
+/// 503 when the JWKS endpoint is, and 500 when our own configuration is
+fn auth_failure(message: &str) -> ApiGatewayV2httpResponse {
+    match failure_reason(message) {
+        "upstream" => {
+            error!(message = message, "Authentication unavailable");
+            ResponseBuilder::new(503)
+                .json(&ApiResponse::<()>::error(
+                    "Authentication is temporarily unavailable",
+                ))
+                .header("retry-after", JWKS_RETRY_INTERVAL.as_secs())
+                .build()
+        }
+        "config" => {
+            error!(message = message, "Authentication misconfigured");
+            json_response(500, &ApiResponse::<()>::error("Internal server error"))
+        }
+        _ => unauthorized(message),
+    }
+}
+
 fn unauthorized(message: &str) -> ApiGatewayV2httpResponse {
-    warn!(message = message, "Authentication failed");
+    warn!(
+        reason = failure_reason(message),
+        message = message,
+        "Authentication failed"
+    );
     json_response(401, &ApiResponse::<()>::error(message))
 }
 
+fn forbidden(message: &str) -> ApiGatewayV2httpResponse {
+    warn!(message = message, "Authorization failed");
+    json_response(403, &ApiResponse::<()>::error(message))
+}
+
+/// Pass the user through if the token carries the scope, otherwise 403
+#[allow(clippy::result_large_err)]
+fn check_scope(user: AuthUser, scope: &str) -> Result<AuthUser, ApiGatewayV2httpResponse> {
+    if user.has_scope(scope) {
+        Ok(user)
+    } else {
+        Err(forbidden("Insufficient scope"))
+    }
+}
+
 fn extract_token(request: &ApiGatewayV2httpRequest) -> Option<&str> {
     request
         .headers
@@ -79,17 +325,31 @@ fn extract_token(request: &ApiGatewayV2httpRequest) -> Option<&str> {
         .and_then(|h| h.strip_prefix("Bearer "))
 }
 
-/// Fetch JWKS from Cognito and cache it
-fn fetch_jwks(issuer: &str) -> Result<HashMap<String, DecodingKey>, &'static str> {
-    let jwks_url = format!("{}/.well-known/jwks.json", issuer);
+/// JWKS location: `override_url` verbatim when set, otherwise derived from the issuer
+fn jwks_url(issuer: &Issuer, override_url: Option<String>) -> String {
+    override_url
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| format!("{}/.well-known/jwks.json", issuer.as_str()))
+}
 
-    // Use blocking HTTP client (ureq is lightweight and works in Lambda)
-    let response = ureq::get(&jwks_url).call().map_err(|e| {
-        error!(error = %e, url = %jwks_url, "Failed to fetch JWKS");
-        "Failed to fetch JWKS"
-    })?;
+/// Fetch JWKS from Cognito (or JWKS_URL if set) and cache it
+async fn fetch_jwks(issuer: &Issuer) -> Result<HashMap<String, DecodingKey>, &'static str> {
+    fetch_jwks_from(&jwks_url(issuer, std::env::var("JWKS_URL").ok())).await
+}
 
-    let jwks: JwksResponse = response.into_json().map_err(|e| {
+async fn fetch_jwks_from(jwks_url: &str) -> Result<HashMap<String, DecodingKey>, &'static str> {
+    let client = HTTP_CLIENT.get_or_init(reqwest::Client::new);
+    let response = client
+        .get(jwks_url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| {
+            error!(error = %e, url = %jwks_url, "Failed to fetch JWKS");
+            "Failed to fetch JWKS"
+        })?;
+
+    let jwks: JwksResponse = response.json().await.map_err(|e| {
         error!(error = %e, "Failed to parse JWKS response");
         "Failed to parse JWKS"
     })?;
@@ -117,40 +377,75 @@ fn fetch_jwks(issuer: &str) -> Result<HashMap<String, DecodingKey>, &'static str
 }
 
 /// Get decoding key for the given key ID, fetching JWKS if needed
-fn get_decoding_key(kid: &str, issuer: &str) -> Result<DecodingKey, &'static str> {
+async fn get_decoding_key(kid: &Kid, issuer: &Issuer) -> Result<DecodingKey, &'static str> {
+    resolve_key(&JWKS_CACHE, issuer, kid, &SystemClock, || {
+        fetch_jwks(issuer)
+    })
+    .await
+}
+
+/// Serve `kid` from `cache`, fetching the issuer's JWKS once it is stale.
+/// While fetches fail, keys within `JWKS_STALE_GRACE` of expiring keep
+/// working, and a new fetch is only tried every `JWKS_RETRY_INTERVAL`.
+async fn resolve_key<F, Fut>(
+    cache: &RwLock<Option<JwksCache>>,
+    issuer: &Issuer,
+    kid: &Kid,
+    clock: &dyn Clock,
+    fetch: F,
+) -> Result<DecodingKey, &'static str>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<HashMap<String, DecodingKey>, &'static str>>,
+{
     // Check cache first
-    {
-        let cache = JWKS_CACHE.read().unwrap();
-        if let Some(ref cached) = *cache {
-            // Refresh cache if older than 1 hour
-            if cached.fetched_at.elapsed() < std::time::Duration::from_secs(3600) {
-                if let Some(key) = cached.keys.get(kid) {
-                    return Ok(key.clone());
+    let now = clock.now();
+    let stale = {
+        let cache = cache.read().await;
+        let cache = cache.as_ref();
+        if let Some(key) = cache.and_then(|cache| cache.get(issuer, kid, now)) {
+            return Ok(key);
+        }
+        let stale = cache.and_then(|cache| cache.get_stale(issuer, kid, now));
+        let recently_failed = cache.is_some_and(|cache| cache.recently_failed(issuer, now));
+        if let (Some(key), true) = (&stale, recently_failed) {
+            return Ok(key.clone());
+        }
+        stale
+    };
+
+    // Fetch fresh JWKS, dating the result from when it arrived
+    let fetched = fetch().await;
+    let now = clock.now();
+    let mut cache = cache.write().await;
+    let cache = cache.get_or_insert_with(JwksCache::default);
+    match fetched {
+        Ok(keys) => {
+            let key = keys.get(kid.as_str()).cloned();
+            cache.insert(issuer, keys, now);
+            key.ok_or("Key ID not found in JWKS")
+        }
+        Err(e) => {
+            cache.record_failure(issuer, now);
+            match stale {
+                Some(key) => {
+                    warn!(issuer = %issuer.as_str(), error = %e, "JWKS fetch failed, using stale cached keys");
+                    Ok(key)
                 }
+                None => Err(e),
             }
         }
     }
-
-    // Fetch fresh JWKS
-    let keys = fetch_jwks(issuer)?;
-    let key = keys.get(kid).cloned().ok_or("Key ID not found in JWKS")?;
-
-    // Update cache
-    {
-        let mut cache = JWKS_CACHE.write().unwrap();
-        *cache = Some(JwksCache {
-            keys,
-            fetched_at: std::time::Instant::now(),
-        });
-    }
-
-    Ok(key)
 }
 
-/// Validate JWT token and extract claims
-pub fn validate_token(token: &str) -> Result<Claims, &'static str> {
+/// Validate JWT token and extract claims, optionally requiring a specific token_use
+pub async fn validate_token(
+    token: &str,
+    required_use: Option<TokenUse>,
+) -> Result<Claims, &'static str> {
     let cognito_issuer =
         std::env::var("COGNITO_ISSUER").map_err(|_| "COGNITO_ISSUER not configured")?;
+    let cognito_issuer = Issuer::new(cognito_issuer)?;
 
     // Decode header to get the key ID
     let header = decode_header(token).map_err(|e| {
@@ -158,62 +453,527 @@ pub fn validate_token(token: &str) -> Result<Claims, &'static str> {
         "Invalid token format"
     })?;
 
-    let kid = header.kid.ok_or("Token missing key ID")?;
+    let kid = Kid::new(header.kid.unwrap_or_default())?;
 
     // Get the decoding key (fetches JWKS if needed)
-    let decoding_key = get_decoding_key(&kid, &cognito_issuer)?;
+    let decoding_key = get_decoding_key(&kid, &cognito_issuer).await?;
 
     // Set up validation
     let mut validation = Validation::new(Algorithm::RS256);
     validation.validate_exp = true;
-    validation.set_issuer(&[&cognito_issuer]);
+    validation.set_issuer(&[cognito_issuer.as_str()]);
 
     // Cognito access tokens don't have 'aud' claim
     validation.validate_aud = false;
 
     let token_data: TokenData<Claims> = decode(token, &decoding_key, &validation).map_err(|e| {
         error!(error = %e, "Failed to validate token");
-        "Invalid token"
+        match e.kind() {
+            ErrorKind::ExpiredSignature => "Token expired",
+            ErrorKind::InvalidSignature => "Invalid token signature",
+            ErrorKind::InvalidIssuer => "Invalid token issuer",
+            _ => "Invalid token",
+        }
     })?;
 
     let claims = token_data.claims;
 
     // Verify issuer matches
-    if claims.iss != cognito_issuer {
+    if claims.iss != cognito_issuer.as_str() {
         return Err("Invalid token issuer");
     }
 
     // Verify token type
-    if claims.token_use != "id" && claims.token_use != "access" {
-        return Err("Invalid token type");
-    }
+    check_token_use(&claims.token_use, required_use)?;
 
     Ok(claims)
 }
 
 #[allow(clippy::result_large_err)]
-pub fn require_auth(
+pub async fn require_auth(
     request: &ApiGatewayV2httpRequest,
 ) -> Result<AuthUser, ApiGatewayV2httpResponse> {
     let token =
         extract_token(request).ok_or_else(|| unauthorized("Missing authorization header"))?;
 
-    let claims = validate_token(token).map_err(unauthorized)?;
+    let claims = validate_token(token, Some(TokenUse::required_for_api()))
+        .await
+        .map_err(auth_failure)?;
 
     Ok(AuthUser::from(claims))
 }
 
-/// Optional authentication - returns Some(user) if valid token, None otherwise
-pub fn optional_auth(request: &ApiGatewayV2httpRequest) -> Option<AuthUser> {
-    let token = extract_token(request)?;
-    let claims = validate_token(token).ok()?;
-    Some(AuthUser::from(claims))
+/// Require a valid token (401 otherwise) whose `scope` claim includes `scope` (403 otherwise)
+#[allow(clippy::result_large_err)]
+pub async fn require_scope(
+    request: &ApiGatewayV2httpRequest,
+    scope: &str,
+) -> Result<AuthUser, ApiGatewayV2httpResponse> {
+    let user = require_auth(request).await?;
+    check_scope(user, scope)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::testing::FakeClock;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const JWKS_FIXTURE: &str = r#"{"keys":[
+        {"kid":"key-1","kty":"RSA","alg":"RS256","use":"sig","e":"AQAB",
+         "n":"u1SU1LfVLPHCozMxH2Mo4lgOEePzNm0tRgeLezV6ffAt0gunVTLw7onLRnrq0_IzW7yWR7QkrmBL7jTKEn5u-qKhbwKfBstIs-bMY2Zkp18gnTxKLxoS2tFczGkPLPgizskuemMghRniWaoLcyehkd3qqGElvW_VDL5AaWTg0nLVkjRo9z-40RQzuVaE8AkAFmxZzow3x-VJYKdjykkJ0iT9wCS0DRTXu269V264Vf_3jvredZiKRkgwlL9xNAwxXFg0x_XFw005UWVRIkdgcKWTjpBP2dPwVZ4WWC-9aGVd-Gyn1o0CLelf4rEjGoXbAAEgAqeGUxrcIlbjXfbcmw"},
+        {"kid":"ec-key","kty":"EC","e":"","n":""}
+    ]}"#;
+
+    /// Serve a single HTTP response on a local port and return its base URL
+    async fn serve_once(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        format!("http://{addr}")
+    }
+
     #[test]
     fn test_extract_token() {
-        // Basic compile test
+        let mut request = ApiGatewayV2httpRequest::default();
+        assert_eq!(extract_token(&request), None);
+
+        request
+            .headers
+            .insert("authorization", "Bearer abc.def.ghi".parse().unwrap());
+        assert_eq!(extract_token(&request), Some("abc.def.ghi"));
+    }
+
+    #[test]
+    fn test_access_token_passes_when_access_required() {
+        assert!(check_token_use("access", Some(TokenUse::Access)).is_ok());
+        assert!(check_token_use("access", None).is_ok());
+    }
+
+    #[test]
+    fn test_id_token_rejected_when_access_required() {
+        assert_eq!(
+            check_token_use("id", Some(TokenUse::Access)),
+            Err("Access token required")
+        );
+        assert!(check_token_use("id", None).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_token_use_always_rejected() {
+        assert!(check_token_use("refresh", None).is_err());
+        assert!(check_token_use("refresh", Some(TokenUse::Access)).is_err());
+        assert!(check_token_use("", Some(TokenUse::Id)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_jwks_parses_fixture() {
+        let issuer = serve_once(JWKS_FIXTURE).await;
+
+        let keys = fetch_jwks_from(&issuer).await.unwrap();
+
+        assert_eq!(keys.len(), 1);
+        assert!(keys.contains_key("key-1"));
+    }
+
+    fn claims_from_json(json: &str) -> Claims {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_auth_user_scopes_and_groups() {
+        let claims = claims_from_json(
+            r#"{"sub":"user-1","iss":"issuer","token_use":"access","exp":0,"iat":0,
+                "scope":"items:read  items:write openid","cognito:groups":["admins","editors"]}"#,
+        );
+        let user = AuthUser::from(claims);
+
+        assert_eq!(user.scopes, vec!["items:read", "items:write", "openid"]);
+        assert!(user.has_scope("items:write"));
+        assert!(!user.has_scope("items"));
+        assert!(user.in_group("admins"));
+        assert!(!user.in_group("viewers"));
+    }
+
+    #[test]
+    fn test_auth_user_without_scopes_or_groups() {
+        let claims =
+            claims_from_json(r#"{"sub":"user-1","iss":"issuer","token_use":"id","exp":0,"iat":0}"#);
+        let user = AuthUser::from(claims);
+
+        assert!(user.scopes.is_empty());
+        assert!(user.groups.is_empty());
+        assert!(!user.has_scope("items:read"));
+    }
+
+    fn user_with_scopes(scope: &str) -> AuthUser {
+        AuthUser::from(claims_from_json(&format!(
+            r#"{{"sub":"user-1","iss":"issuer","token_use":"access","exp":0,"iat":0,"scope":"{scope}"}}"#
+        )))
+    }
+
+    #[test]
+    fn test_write_scope_allows_write() {
+        let user = user_with_scopes("items:read items:write");
+        let user = check_scope(user, SCOPE_ITEMS_WRITE).unwrap();
+        assert_eq!(user.id, "user-1");
+    }
+
+    #[test]
+    fn test_read_only_scope_forbidden_for_write() {
+        let user = user_with_scopes("items:read");
+        assert!(check_scope(user.clone(), SCOPE_ITEMS_READ).is_ok());
+
+        let response = check_scope(user, SCOPE_ITEMS_WRITE).unwrap_err();
+        assert_eq!(response.status_code, 403);
+    }
+
+    #[tokio::test]
+    async fn test_require_scope_without_token_is_unauthorized() {
+        let request = ApiGatewayV2httpRequest::default();
+
+        let response = require_scope(&request, SCOPE_ITEMS_WRITE)
+            .await
+            .unwrap_err();
+
+        assert_eq!(response.status_code, 401);
+    }
+
+    #[tokio::test]
+    async fn test_jwks_outage_is_503_but_bad_token_is_401() {
+        // Nothing listens on port 1, so the fetch fails like an outage would
+        let error = fetch_jwks_from("http://127.0.0.1:1/.well-known/jwks.json")
+            .await
+            .err()
+            .unwrap();
+        let response = auth_failure(error);
+        assert_eq!(response.status_code, 503);
+        assert_eq!(response.headers["retry-after"], "30");
+
+        for message in ["Invalid token signature", "Token expired", "Invalid token"] {
+            assert_eq!(auth_failure(message).status_code, 401, "{message}");
+        }
+        assert_eq!(
+            auth_failure("COGNITO_ISSUER not configured").status_code,
+            500
+        );
+    }
+
+    #[test]
+    fn test_failure_reason_tags() {
+        let cases = [
+            ("Missing authorization header", "missing_header"),
+            ("Invalid token format", "bad_format"),
+            ("Token missing key ID", "bad_format"),
+            ("Invalid token", "bad_format"),
+            ("Token expired", "expired"),
+            ("Invalid token issuer", "wrong_issuer"),
+            ("Key ID not found in JWKS", "unknown_kid"),
+            ("Invalid token signature", "signature"),
+            ("Access token required", "wrong_token_use"),
+            ("Failed to fetch JWKS", "upstream"),
+            ("COGNITO_ISSUER not configured", "config"),
+            ("Something else", "other"),
+        ];
+
+        for (message, reason) in cases {
+            assert_eq!(failure_reason(message), reason, "{message}");
+        }
+    }
+
+    fn key_set(kid: &str) -> HashMap<String, DecodingKey> {
+        HashMap::from([(kid.to_string(), DecodingKey::from_secret(b"secret"))])
+    }
+
+    fn issuer(url: &str) -> Issuer {
+        Issuer::new(url).unwrap()
+    }
+
+    fn kid(id: &str) -> Kid {
+        Kid::new(id).unwrap()
+    }
+
+    #[test]
+    fn test_jwks_cache_keeps_issuers_apart() {
+        let now = Instant::now();
+        let mut cache = JwksCache::default();
+
+        cache.insert(&issuer("https://pool-a"), key_set("a-1"), now);
+        cache.insert(&issuer("https://pool-b"), key_set("b-1"), now);
+
+        assert!(cache
+            .get(&issuer("https://pool-a"), &kid("a-1"), now)
+            .is_some());
+        assert!(cache
+            .get(&issuer("https://pool-b"), &kid("b-1"), now)
+            .is_some());
+        // A kid is only trusted for the issuer that published it
+        assert!(cache
+            .get(&issuer("https://pool-a"), &kid("b-1"), now)
+            .is_none());
+        assert!(cache
+            .get(&issuer("https://pool-c"), &kid("a-1"), now)
+            .is_none());
+
+        // Refreshing one issuer leaves the other's keys alone
+        cache.insert(&issuer("https://pool-a"), key_set("a-2"), now);
+        assert!(cache
+            .get(&issuer("https://pool-a"), &kid("a-1"), now)
+            .is_none());
+        assert!(cache
+            .get(&issuer("https://pool-a"), &kid("a-2"), now)
+            .is_some());
+        assert!(cache
+            .get(&issuer("https://pool-b"), &kid("b-1"), now)
+            .is_some());
+    }
+
+    #[test]
+    fn test_jwks_cache_entries_expire_independently() {
+        let start = Instant::now();
+        let mut cache = JwksCache::default();
+        cache.insert(&issuer("https://pool-a"), key_set("a-1"), start);
+        cache.insert(
+            &issuer("https://pool-b"),
+            key_set("b-1"),
+            start + JWKS_TTL / 2,
+        );
+
+        let later = start + JWKS_TTL;
+        assert!(cache
+            .get(&issuer("https://pool-a"), &kid("a-1"), later)
+            .is_none());
+        assert!(cache
+            .get(&issuer("https://pool-b"), &kid("b-1"), later)
+            .is_some());
+
+        let much_later = start + JWKS_TTL + JWKS_TTL / 2;
+        assert!(cache
+            .get(&issuer("https://pool-b"), &kid("b-1"), much_later)
+            .is_none());
+    }
+
+    #[test]
+    fn test_jwks_url_derived_from_issuer() {
+        let issuer =
+            Issuer::new("https://cognito-idp.us-east-1.amazonaws.com/us-east-1_abc").unwrap();
+
+        assert_eq!(
+            jwks_url(&issuer, None),
+            "https://cognito-idp.us-east-1.amazonaws.com/us-east-1_abc/.well-known/jwks.json"
+        );
+        assert_eq!(
+            jwks_url(&issuer, Some(String::new())),
+            format!("{}/.well-known/jwks.json", issuer.as_str())
+        );
+    }
+
+    #[test]
+    fn test_jwks_url_override_takes_precedence() {
+        let url = jwks_url(
+            &issuer("https://cognito-idp.us-east-1.amazonaws.com/us-east-1_abc"),
+            Some("http://localhost:9999/keys.json".to_string()),
+        );
+
+        assert_eq!(url, "http://localhost:9999/keys.json");
+    }
+
+    fn cache_with(
+        url: &str,
+        keys: HashMap<String, DecodingKey>,
+        at: Instant,
+    ) -> RwLock<Option<JwksCache>> {
+        let mut cache = JwksCache::default();
+        cache.insert(&issuer(url), keys, at);
+        RwLock::new(Some(cache))
+    }
+
+    #[tokio::test]
+    async fn test_resolve_key_falls_back_to_stale_keys_when_fetch_fails() {
+        let start = Instant::now();
+        let cache = cache_with("https://pool-a", key_set("a-1"), start);
+        let clock = FakeClock::at(start + JWKS_TTL + Duration::from_secs(60));
+        let fetches = AtomicUsize::new(0);
+        let failing_fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            Err("Failed to fetch JWKS")
+        };
+
+        let key = resolve_key(
+            &cache,
+            &issuer("https://pool-a"),
+            &kid("a-1"),
+            &clock,
+            failing_fetch,
+        )
+        .await;
+        assert!(key.is_ok());
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // Within the retry interval the stale key is served without refetching
+        clock.advance(JWKS_RETRY_INTERVAL / 2);
+        let key = resolve_key(
+            &cache,
+            &issuer("https://pool-a"),
+            &kid("a-1"),
+            &clock,
+            failing_fetch,
+        )
+        .await;
+        assert!(key.is_ok());
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // After it, the next request tries again
+        clock.advance(JWKS_RETRY_INTERVAL / 2);
+        let key = resolve_key(
+            &cache,
+            &issuer("https://pool-a"),
+            &kid("a-1"),
+            &clock,
+            failing_fetch,
+        )
+        .await;
+        assert!(key.is_ok());
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+        // A successful fetch replaces the stale set
+        clock.advance(JWKS_RETRY_INTERVAL);
+        let key = resolve_key(
+            &cache,
+            &issuer("https://pool-a"),
+            &kid("a-2"),
+            &clock,
+            || async { Ok(key_set("a-2")) },
+        )
+        .await;
+        assert!(key.is_ok());
+        let cache = cache.read().await;
+        let cache = cache.as_ref().unwrap();
+        assert!(cache
+            .get(&issuer("https://pool-a"), &kid("a-2"), clock.now())
+            .is_some());
+        assert!(!cache.recently_failed(&issuer("https://pool-a"), clock.now()));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_key_refetches_once_ttl_passes() {
+        let empty = RwLock::new(None);
+        let clock = FakeClock::at(Instant::now());
+        let fetches = AtomicUsize::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            // A slow fetch: the keys are dated from when they arrive
+            clock.advance(Duration::from_secs(5));
+            Ok(key_set("a-1"))
+        };
+        let (issuer, kid) = (issuer("https://pool-a"), kid("a-1"));
+        let resolve = || resolve_key(&empty, &issuer, &kid, &clock, fetch);
+
+        assert!(resolve().await.is_ok());
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        clock.advance(JWKS_TTL - Duration::from_secs(1));
+        assert!(resolve().await.is_ok());
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        clock.advance(Duration::from_secs(1));
+        assert!(resolve().await.is_ok());
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_key_fails_without_usable_cached_key() {
+        let now = Instant::now();
+        let failing_fetch = || async { Err("Failed to fetch JWKS") };
+
+        let empty = RwLock::new(None);
+        let result = resolve_key(
+            &empty,
+            &issuer("https://pool-a"),
+            &kid("a-1"),
+            &FakeClock::at(now),
+            failing_fetch,
+        )
+        .await;
+        assert_eq!(result.err(), Some("Failed to fetch JWKS"));
+
+        // A different kid, or keys past the grace window, don't count
+        let cache = cache_with("https://pool-a", key_set("a-1"), now);
+        let expired = now + JWKS_TTL;
+        let result = resolve_key(
+            &cache,
+            &issuer("https://pool-a"),
+            &kid("a-9"),
+            &FakeClock::at(expired),
+            failing_fetch,
+        )
+        .await;
+        assert_eq!(result.err(), Some("Failed to fetch JWKS"));
+
+        let cache = cache_with("https://pool-a", key_set("a-1"), now);
+        let too_old = now + JWKS_TTL + JWKS_STALE_GRACE;
+        let result = resolve_key(
+            &cache,
+            &issuer("https://pool-a"),
+            &kid("a-1"),
+            &FakeClock::at(too_old),
+            failing_fetch,
+        )
+        .await;
+        assert_eq!(result.err(), Some("Failed to fetch JWKS"));
+    }
+
+    #[test]
+    fn test_kid_must_not_be_empty() {
+        assert_eq!(Kid::new("").err(), Some("Token missing key ID"));
+        assert_eq!(Kid::new("key-1").unwrap().as_str(), "key-1");
+    }
+
+    #[test]
+    fn test_issuer_must_be_an_https_url() {
+        for value in [
+            "",
+            "cognito-idp.us-east-1.amazonaws.com/us-east-1_abc",
+            "http://cognito-idp.us-east-1.amazonaws.com/us-east-1_abc",
+            "https://",
+            "https:///us-east-1_abc",
+            "https://cognito idp.example/pool",
+        ] {
+            assert!(Issuer::new(value).is_err(), "{value:?} should be rejected");
+        }
+
+        let issuer = Issuer::new("https://cognito-idp.us-east-1.amazonaws.com/us-east-1_abc");
+        assert_eq!(
+            issuer.unwrap().as_str(),
+            "https://cognito-idp.us-east-1.amazonaws.com/us-east-1_abc"
+        );
+    }
+
+    #[test]
+    fn test_auth_user_exposes_custom_claims() {
+        let claims = claims_from_json(
+            r#"{"sub":"user-1","iss":"issuer","token_use":"access","exp":0,"iat":0,
+                "custom:tenant_id":"tenant-7","custom:beta":true}"#,
+        );
+        let user = AuthUser::from(claims);
+
+        assert_eq!(user.claims["custom:tenant_id"], "tenant-7");
+        assert_eq!(user.claims["custom:beta"], true);
+        // Typed claims are in the map too; absent optional ones are not
+        assert_eq!(user.claims["sub"], "user-1");
+        assert_eq!(user.claims["token_use"], "access");
+        assert!(!user.claims.contains_key("email"));
     }
 }
@@ -0,0 +1,16 @@
+use std::time::Instant;
+
+/// Where time-dependent code reads the current time, so tests can move a
+/// fake clock across a deadline instead of sleeping
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The real clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
@@ -0,0 +1,112 @@
+use aws_lambda_events::http::HeaderMap;
+use shared::config::AppConfig;
+
+const ALLOW_METHODS: &str = "GET, POST, PUT, PATCH, DELETE, OPTIONS";
+const ALLOW_HEADERS: &str =
+    "Content-Type, Authorization, If-None-Match, If-Match, If-Unmodified-Since";
+const EXPOSE_HEADERS: &str = "ETag, Location, Retry-After, X-Export-Truncated";
+
+/// Add the CORS headers for a request from `origin` to a response. A
+/// preflight response that already lists its path's methods keeps them.
+pub fn apply(headers: &mut HeaderMap, config: &AppConfig, origin: Option<&str>) {
+    let allow_origin = allowed_origin(&config.allowed_origins, origin);
+    if allow_origin != "*" {
+        // The header varies with the request's Origin, so caches must key on it
+        headers.insert("vary", "Origin".parse().unwrap());
+    }
+    if let Ok(value) = allow_origin.parse() {
+        headers.insert("access-control-allow-origin", value);
+    }
+    headers
+        .entry("access-control-allow-methods")
+        .or_insert(ALLOW_METHODS.parse().unwrap());
+    headers.insert(
+        "access-control-allow-headers",
+        ALLOW_HEADERS.parse().unwrap(),
+    );
+    headers.insert(
+        "access-control-expose-headers",
+        EXPOSE_HEADERS.parse().unwrap(),
+    );
+    headers.insert("access-control-max-age", config.cors_max_age.into());
+    if config.cors_allow_credentials {
+        headers.insert("access-control-allow-credentials", "true".parse().unwrap());
+    }
+}
+
+/// `*` when any origin is allowed, the request's origin when it is on the
+/// allowlist, otherwise the first allowed origin (which the browser will refuse)
+fn allowed_origin<'a>(allowed: &'a [String], origin: Option<&'a str>) -> &'a str {
+    if allowed.iter().any(|o| o == "*") {
+        return "*";
+    }
+    match origin {
+        Some(origin) if allowed.iter().any(|o| o == origin) => origin,
+        _ => allowed.first().map(String::as_str).unwrap_or("*"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(origins: &[&str], allow_credentials: bool) -> AppConfig {
+        AppConfig {
+            allowed_origins: origins.iter().map(|o| o.to_string()).collect(),
+            cors_max_age: 3600,
+            cors_allow_credentials: allow_credentials,
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_wildcard_origin() {
+        let mut headers = HeaderMap::new();
+        apply(
+            &mut headers,
+            &config(&["*"], false),
+            Some("https://any.example"),
+        );
+
+        assert_eq!(headers["access-control-allow-origin"], "*");
+        assert_eq!(headers["access-control-max-age"], "3600");
+        assert!(!headers.contains_key("access-control-allow-credentials"));
+        assert!(!headers.contains_key("vary"));
+    }
+
+    #[test]
+    fn test_allowlisted_origin_echoed_with_credentials() {
+        let config = config(&["https://a.example", "https://b.example"], true);
+        let mut headers = HeaderMap::new();
+        apply(&mut headers, &config, Some("https://b.example"));
+
+        assert_eq!(headers["access-control-allow-origin"], "https://b.example");
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+        assert_eq!(headers["vary"], "Origin");
+    }
+
+    #[test]
+    fn test_unlisted_origin_not_echoed() {
+        let config = config(&["https://a.example"], false);
+        assert_eq!(
+            allowed_origin(&config.allowed_origins, Some("https://evil.example")),
+            "https://a.example"
+        );
+    }
+
+    #[test]
+    fn test_preflight_methods_kept() {
+        let config = config(&["*"], false);
+        let mut headers = HeaderMap::new();
+        apply(&mut headers, &config, None);
+        assert_eq!(headers["access-control-allow-methods"], ALLOW_METHODS);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "access-control-allow-methods",
+            "GET, OPTIONS".parse().unwrap(),
+        );
+        apply(&mut headers, &config, None);
+        assert_eq!(headers["access-control-allow-methods"], "GET, OPTIONS");
+    }
+}
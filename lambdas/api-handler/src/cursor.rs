@@ -0,0 +1,135 @@
+use aws_sdk_dynamodb::types::AttributeValue;
+use base64::prelude::{Engine, BASE64_URL_SAFE_NO_PAD};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+/// Key attributes a cursor may carry: the table key and the index keys
+/// DynamoDB adds to a `last_evaluated_key` when querying an index
+const KEY_ATTRIBUTES: &[&str] = &[
+    "pk", "sk", "gsi1pk", "gsi1sk", "gsi2pk", "gsi2sk", "gsi3pk", "gsi3sk", "gsi4pk", "gsi4sk",
+];
+
+/// Position name of a list served by a single query
+pub const SINGLE: &str = "next";
+
+/// Where each query behind a list stopped: the start key of its next page,
+/// or `None` while it has yet to return anything. A query missing from the
+/// map has no more pages.
+pub type Positions = BTreeMap<String, Option<HashMap<String, AttributeValue>>>;
+
+#[derive(Debug, Error, PartialEq)]
+#[error("cursor must be a next_cursor returned by a previous page")]
+pub struct InvalidCursor;
+
+/// Opaque `next_cursor` for `positions`: each query's key attributes as
+/// base64url-encoded JSON. `None` once every query has run out.
+pub fn encode(positions: &Positions) -> Option<String> {
+    if positions.is_empty() {
+        return None;
+    }
+    let json: BTreeMap<&str, BTreeMap<&str, &str>> = positions
+        .iter()
+        .map(|(name, key)| {
+            let attributes = key
+                .iter()
+                .flatten()
+                .filter_map(|(name, value)| Some((name.as_str(), value.as_s().ok()?.as_str())))
+                .collect();
+            (name.as_str(), attributes)
+        })
+        .collect();
+    Some(BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&json).unwrap_or_default()))
+}
+
+/// `next_cursor` for a single query's `last_evaluated_key`. Every key
+/// attribute is a string.
+pub fn encode_key(key: &HashMap<String, AttributeValue>) -> String {
+    let positions = Positions::from([(SINGLE.to_string(), Some(key.clone()))]);
+    encode(&positions).unwrap_or_default()
+}
+
+/// The positions a cursor from `encode` stands for. Anything that isn't a
+/// non-empty object of key attribute objects is rejected rather than sent
+/// to DynamoDB.
+pub fn decode(cursor: &str) -> Result<Positions, InvalidCursor> {
+    let json = BASE64_URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .map_err(|_| InvalidCursor)?;
+    let positions: BTreeMap<String, HashMap<String, String>> =
+        serde_json::from_slice(&json).map_err(|_| InvalidCursor)?;
+    if positions.is_empty() {
+        return Err(InvalidCursor);
+    }
+    positions
+        .into_iter()
+        .map(|(name, attributes)| {
+            if attributes
+                .keys()
+                .any(|attribute| !KEY_ATTRIBUTES.contains(&attribute.as_str()))
+            {
+                return Err(InvalidCursor);
+            }
+            let key = (!attributes.is_empty()).then(|| {
+                attributes
+                    .into_iter()
+                    .map(|(attribute, value)| (attribute, AttributeValue::S(value)))
+                    .collect()
+            });
+            Ok((name, key))
+        })
+        .collect()
+}
+
+/// Start key for a list served by a single query, from its decoded cursor
+pub fn start_key(positions: Positions) -> Result<HashMap<String, AttributeValue>, InvalidCursor> {
+    match positions.into_iter().collect::<Vec<_>>().as_slice() {
+        [(name, Some(key))] if name == SINGLE => Ok(key.clone()),
+        _ => Err(InvalidCursor),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(sk: &str) -> HashMap<String, AttributeValue> {
+        HashMap::from([
+            ("pk".to_string(), AttributeValue::S("ITEM".to_string())),
+            ("sk".to_string(), AttributeValue::S(sk.to_string())),
+        ])
+    }
+
+    #[test]
+    fn test_cursor_round_trips_a_last_evaluated_key() {
+        let cursor = encode_key(&key("ITEM#abc"));
+        assert!(cursor
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(decode(&cursor).and_then(start_key), Ok(key("ITEM#abc")));
+    }
+
+    #[test]
+    fn test_cursor_round_trips_positions_of_several_queries() {
+        let positions = Positions::from([
+            ("own".to_string(), None),
+            ("public".to_string(), Some(key("ITEM#abc"))),
+        ]);
+        let cursor = encode(&positions).unwrap();
+        assert_eq!(decode(&cursor), Ok(positions.clone()));
+        assert_eq!(start_key(positions), Err(InvalidCursor));
+        assert_eq!(encode(&Positions::new()), None);
+    }
+
+    #[test]
+    fn test_decode_rejects_anything_encode_could_not_have_made() {
+        let encoded = |json: &str| BASE64_URL_SAFE_NO_PAD.encode(json);
+        assert_eq!(decode("not a cursor!"), Err(InvalidCursor));
+        assert_eq!(decode(&encoded("[]")), Err(InvalidCursor));
+        assert_eq!(decode(&encoded("{}")), Err(InvalidCursor));
+        assert_eq!(decode(&encoded(r#"{"next":{"pk":1}}"#)), Err(InvalidCursor));
+        assert_eq!(
+            decode(&encoded(r#"{"next":{"pk":"ITEM","name":"x"}}"#)),
+            Err(InvalidCursor)
+        );
+    }
+}
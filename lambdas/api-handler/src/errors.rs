@@ -0,0 +1,97 @@
+use aws_lambda_events::apigw::ApiGatewayV2httpResponse;
+use shared::repository::RepositoryError;
+use tracing::{error, warn};
+
+use crate::{json_response, ApiResponse};
+
+/// Service codes DynamoDB uses when the table or account is over capacity
+const THROTTLING_CODES: &[&str] = &[
+    "ProvisionedThroughputExceededException",
+    "ThrottlingException",
+    "RequestLimitExceeded",
+    "ServiceUnavailable",
+];
+
+/// Status and client-facing error for a failed DynamoDB operation.
+/// `fallback` is the message for errors the client can't act on (500s).
+pub fn map_dynamo_error(err: &RepositoryError, fallback: &str) -> (i64, ApiResponse<()>) {
+    let (status, message) = match err {
+        RepositoryError::ConditionFailed => (409, "Conflicting write; fetch the item and retry"),
+        RepositoryError::ItemTooLarge => (413, "Item is too large to store"),
+        RepositoryError::Dynamo {
+            code: Some(code), ..
+        } if THROTTLING_CODES.contains(&code.as_str()) => (503, "Service is busy; retry shortly"),
+        RepositoryError::Dynamo {
+            code: Some(code), ..
+        } if code == "ValidationException" => (400, "Invalid request"),
+        _ => (500, fallback),
+    };
+    (status, ApiResponse::<()>::error(message))
+}
+
+/// Log a failed DynamoDB operation and build its response via `map_dynamo_error`
+pub fn dynamo_error_response(err: &RepositoryError, fallback: &str) -> ApiGatewayV2httpResponse {
+    let (status, body) = map_dynamo_error(err, fallback);
+    if status >= 500 && status != 503 {
+        error!(error = %err, status, "{fallback}");
+    } else {
+        warn!(error = %err, status, "{fallback}");
+    }
+    json_response(status, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dynamo(code: &str) -> RepositoryError {
+        RepositoryError::Dynamo {
+            code: Some(code.to_string()),
+            message: "failed".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_throttling_maps_to_503() {
+        for code in [
+            "ProvisionedThroughputExceededException",
+            "ThrottlingException",
+            "RequestLimitExceeded",
+        ] {
+            assert_eq!(map_dynamo_error(&dynamo(code), "Failed").0, 503, "{code}");
+        }
+    }
+
+    #[test]
+    fn test_conditional_check_failure_maps_to_409() {
+        let (status, body) = map_dynamo_error(&RepositoryError::ConditionFailed, "Failed");
+
+        assert_eq!(status, 409);
+        assert!(!body.success);
+    }
+
+    #[test]
+    fn test_validation_maps_to_400() {
+        assert_eq!(
+            map_dynamo_error(&dynamo("ValidationException"), "Failed").0,
+            400
+        );
+    }
+
+    #[test]
+    fn test_unknown_error_maps_to_500_with_fallback() {
+        let err = RepositoryError::Dynamo {
+            code: None,
+            message: "dispatch failure".to_string(),
+        };
+
+        let (status, body) = map_dynamo_error(&err, "Failed to list items");
+
+        assert_eq!(status, 500);
+        assert_eq!(body.error.as_deref(), Some("Failed to list items"));
+        assert_eq!(
+            map_dynamo_error(&dynamo("InternalServerError"), "Failed").0,
+            500
+        );
+    }
+}
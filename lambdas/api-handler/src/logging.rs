@@ -0,0 +1,60 @@
+use tracing_subscriber::EnvFilter;
+
+/// How log lines are rendered, chosen with `LOG_FORMAT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One JSON object per line without timestamps (CloudWatch adds its own)
+    Json,
+    /// Human-readable multi-line output with timestamps, for local development
+    Pretty,
+}
+
+impl LogFormat {
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var("LOG_FORMAT").ok().as_deref())
+    }
+
+    /// `pretty` (case-insensitive) selects pretty output; anything else,
+    /// including unset, keeps the Lambda default of JSON
+    fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some(value) if value.trim().eq_ignore_ascii_case("pretty") => LogFormat::Pretty,
+            _ => LogFormat::Json,
+        }
+    }
+}
+
+/// Install the global subscriber. `RUST_LOG` controls verbosity, defaulting to `info`.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
+    match LogFormat::from_env() {
+        LogFormat::Json => tracing_subscriber::fmt()
+            .json()
+            .with_env_filter(filter)
+            .without_time()
+            .init(),
+        LogFormat::Pretty => tracing_subscriber::fmt()
+            .pretty()
+            .with_env_filter(filter)
+            .init(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_format_defaults_to_json() {
+        assert_eq!(LogFormat::parse(None), LogFormat::Json);
+        assert_eq!(LogFormat::parse(Some("json")), LogFormat::Json);
+        assert_eq!(LogFormat::parse(Some("verbose")), LogFormat::Json);
+    }
+
+    #[test]
+    fn test_log_format_pretty() {
+        assert_eq!(LogFormat::parse(Some("pretty")), LogFormat::Pretty);
+        assert_eq!(LogFormat::parse(Some(" Pretty ")), LogFormat::Pretty);
+    }
+}
@@ -1,20 +1,89 @@
+use access_log::{capture_caller, record_caller, AccessLog};
+use auth::{require_scope, AuthUser, SCOPE_ADMIN, SCOPE_ITEMS_READ, SCOPE_ITEMS_WRITE};
 use aws_lambda_events::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
 use aws_lambda_events::encodings::Body;
-use aws_lambda_events::http::HeaderMap;
 use aws_sdk_dynamodb::Client as DynamoClient;
+use aws_sdk_s3::config::SharedCredentialsProvider;
 use aws_sdk_s3::Client as S3Client;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use futures::FutureExt;
 use lambda_runtime::{service_fn, Error, LambdaEvent};
+use percent_encoding::percent_decode_str;
+use rate_limit::RateLimiter;
+use response::ResponseBuilder;
 use serde::{Deserialize, Serialize};
 use shared::config::AppConfig;
-use tracing::{info, instrument};
+use shared::models::{Item, SlugClaim};
+use shared::repository::{EntityStore, Repository};
+use std::any::Any;
+use std::borrow::Cow;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, field, info, info_span, instrument, warn, Instrument};
 
+mod access_log;
 mod auth;
+mod clock;
+mod cors;
+mod cursor;
+mod errors;
+mod logging;
+mod query;
+mod rate_limit;
+mod response;
 mod routes;
+#[cfg(test)]
+mod testing;
+mod validation;
 
 pub struct AppState {
     pub dynamo: DynamoClient,
     pub s3: S3Client,
+    /// Credentials the clients sign with, for signing S3 POST policies
+    /// ourselves; None when the environment provides none
+    pub credentials: Option<SharedCredentialsProvider>,
     pub config: AppConfig,
+    pub items: Box<dyn EntityStore<Item>>,
+    pub slugs: Box<dyn EntityStore<SlugClaim>>,
+    pub rate_limiter: RateLimiter,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// State with unconfigured AWS clients and an in-memory item store;
+    /// any call that reaches AWS directly fails. S3 gets static credentials
+    /// so presigning, which is purely local, works
+    pub(crate) fn for_tests() -> Self {
+        let dynamo = DynamoClient::from_conf(
+            aws_sdk_dynamodb::Config::builder()
+                .behavior_version(aws_config::BehaviorVersion::latest())
+                .build(),
+        );
+        let credentials = SharedCredentialsProvider::new(aws_sdk_s3::config::Credentials::new(
+            "test", "test", None, None, "tests",
+        ));
+        let s3 = S3Client::from_conf(
+            aws_sdk_s3::Config::builder()
+                .behavior_version(aws_config::BehaviorVersion::latest())
+                .region(aws_sdk_s3::config::Region::new("us-east-1"))
+                .credentials_provider(credentials.clone())
+                .build(),
+        );
+        let config = AppConfig::default();
+        let items = Box::new(testing::InMemoryStore::new(config.table_settings()));
+        let slugs = Box::new(testing::InMemoryStore::new(config.table_settings()));
+        let rate_limiter = RateLimiter::new(config.rate_limit_burst, config.rate_limit_per_second);
+        AppState {
+            dynamo,
+            s3,
+            credentials: Some(credentials),
+            config,
+            items,
+            slugs,
+            rate_limiter,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,6 +93,28 @@ pub struct ApiResponse<T> {
     pub data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Per-field validation failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
+    /// API Gateway request id on error responses, for quoting in bug reports;
+    /// the access log carries the same id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
 }
 
 impl<T: Serialize> ApiResponse<T> {
@@ -32,6 +123,8 @@ impl<T: Serialize> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            fields: None,
+            request_id: None,
         }
     }
 
@@ -40,6 +133,55 @@ impl<T: Serialize> ApiResponse<T> {
             success: false,
             data: None,
             error: Some(message.into()),
+            fields: None,
+            request_id: None,
+        }
+    }
+
+    /// Error that still carries data describing the failure
+    pub fn error_with(message: impl Into<String>, data: T) -> Self {
+        Self {
+            success: false,
+            data: Some(data),
+            error: Some(message.into()),
+            fields: None,
+            request_id: None,
+        }
+    }
+
+    pub fn validation_error(fields: Vec<FieldError>) -> ApiResponse<()> {
+        ApiResponse {
+            success: false,
+            data: None,
+            error: Some("Validation failed".to_string()),
+            fields: Some(fields),
+            request_id: None,
+        }
+    }
+}
+
+/// Cache-Control directive sent with a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheControl {
+    /// Never cache; the default for authenticated, mutable data
+    NoStore,
+    /// Browser-only caching for `max_age` seconds
+    Private { max_age: u32 },
+}
+
+impl CacheControl {
+    /// Directive for single-item GETs, per `item_cache_max_age`
+    pub fn for_item(config: &AppConfig) -> Self {
+        match config.item_cache_max_age {
+            0 => CacheControl::NoStore,
+            max_age => CacheControl::Private { max_age },
+        }
+    }
+
+    pub(crate) fn header_value(self) -> String {
+        match self {
+            CacheControl::NoStore => "no-store".to_string(),
+            CacheControl::Private { max_age } => format!("private, max-age={max_age}"),
         }
     }
 }
@@ -48,100 +190,2401 @@ pub fn json_response<T: Serialize>(
     status_code: i64,
     body: &ApiResponse<T>,
 ) -> ApiGatewayV2httpResponse {
-    let mut headers = HeaderMap::new();
-    headers.insert("content-type", "application/json".parse().unwrap());
-    let allowed_origin = std::env::var("ALLOWED_ORIGIN").unwrap_or_else(|_| "*".to_string());
-    headers.insert(
-        "access-control-allow-origin",
-        allowed_origin.parse().unwrap(),
-    );
-    headers.insert(
-        "access-control-allow-methods",
-        "GET, POST, PUT, DELETE, OPTIONS".parse().unwrap(),
-    );
-    headers.insert(
-        "access-control-allow-headers",
-        "Content-Type, Authorization".parse().unwrap(),
-    );
+    json_response_with_cache(status_code, body, CacheControl::NoStore)
+}
+
+pub fn json_response_with_cache<T: Serialize>(
+    status_code: i64,
+    body: &ApiResponse<T>,
+    cache: CacheControl,
+) -> ApiGatewayV2httpResponse {
+    ResponseBuilder::new(status_code)
+        .json(body)
+        .cache(cache)
+        .build()
+}
+
+/// Public base URL the request arrived on, e.g. `https://abc.execute-api.us-east-1.amazonaws.com/prod`.
+/// Named stages on the default execute-api domain are part of the path; custom
+/// domains (via API mappings) and the `$default` stage have no stage prefix.
+pub fn base_url(request: &ApiGatewayV2httpRequest) -> Option<String> {
+    let context = &request.request_context;
+    let domain = context.domain_name.as_deref().filter(|d| !d.is_empty())?;
+
+    let stage = context
+        .stage
+        .as_deref()
+        .filter(|stage| !stage.is_empty() && *stage != "$default")
+        .filter(|_| domain.ends_with(".amazonaws.com"));
+
+    Some(match stage {
+        Some(stage) => format!("https://{domain}/{stage}"),
+        None => format!("https://{domain}"),
+    })
+}
+
+/// The request body's bytes, base64-decoded if API Gateway flagged it as
+/// encoded; `None` when there is no body
+pub fn decoded_body(
+    request: &ApiGatewayV2httpRequest,
+) -> Result<Option<Cow<'_, [u8]>>, base64::DecodeError> {
+    let Some(body) = request.body.as_deref() else {
+        return Ok(None);
+    };
+    if !request.is_base64_encoded {
+        return Ok(Some(Cow::Borrowed(body.as_bytes())));
+    }
+    BASE64_STANDARD
+        .decode(body)
+        .map(|bytes| Some(Cow::Owned(bytes)))
+}
+
+/// Size of the request body after any base64 decoding
+fn decoded_body_len(request: &ApiGatewayV2httpRequest) -> usize {
+    let body = request.body.as_deref().unwrap_or("");
+    if !request.is_base64_encoded {
+        return body.len();
+    }
+    let padding = body.bytes().rev().take_while(|&b| b == b'=').count();
+    (body.len() / 4 * 3).saturating_sub(padding)
+}
+
+/// 413 response if the body exceeds the limit, checked before any JSON parsing
+fn reject_oversized_body(
+    request: &ApiGatewayV2httpRequest,
+    max_bytes: usize,
+) -> Option<ApiGatewayV2httpResponse> {
+    (decoded_body_len(request) > max_bytes)
+        .then(|| json_response(413, &ApiResponse::<()>::error("Request body too large")))
+}
+
+/// 415 response unless a request carrying a body declares `application/json`
+/// (a charset or other parameters are allowed)
+fn reject_non_json_body(request: &ApiGatewayV2httpRequest) -> Option<ApiGatewayV2httpResponse> {
+    let method = request.request_context.http.method.as_str();
+    if !matches!(method, "POST" | "PUT" | "PATCH") {
+        return None;
+    }
+
+    let is_json = request
+        .headers
+        .get("content-type")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"));
+
+    (!is_json).then(|| {
+        json_response(
+            415,
+            &ApiResponse::<()>::error("Content-Type must be application/json"),
+        )
+    })
+}
+
+/// Seconds clients are told to wait while the API is read-only
+const READ_ONLY_RETRY_AFTER_SECS: u32 = 300;
+
+/// POST routes that only read, and so keep working in read-only mode
+const READ_ONLY_POSTS: &[&str] = &["/items/search"];
+
+/// 503 for writes to a known route while `READ_ONLY` maintenance mode is on
+fn reject_write_when_read_only(
+    request: &ApiGatewayV2httpRequest,
+    config: &AppConfig,
+) -> Option<ApiGatewayV2httpResponse> {
+    let method = request.request_context.http.method.as_str();
+    let path = request.raw_path.as_deref().unwrap_or("/");
+    if !config.read_only
+        || !matches!(method, "POST" | "PUT" | "PATCH" | "DELETE")
+        || (method == "POST" && READ_ONLY_POSTS.contains(&path))
+        || allowed_methods(path).is_none()
+    {
+        return None;
+    }
+
+    Some(
+        ResponseBuilder::new(503)
+            .json(&ApiResponse::<()>::error(
+                "API is in read-only maintenance mode; try again later",
+            ))
+            .header("retry-after", READ_ONLY_RETRY_AFTER_SECS)
+            .build(),
+    )
+}
+
+/// A route handler that can be wrapped with cross-cutting middleware
+trait Handler {
+    async fn call(
+        &self,
+        state: &AppState,
+        request: &ApiGatewayV2httpRequest,
+    ) -> ApiGatewayV2httpResponse;
+}
+
+/// Route handler that doesn't need the caller's identity
+struct Public<H>(H);
+
+impl<H> Handler for Public<H>
+where
+    H: AsyncFn(&AppState, &ApiGatewayV2httpRequest) -> ApiGatewayV2httpResponse,
+{
+    async fn call(
+        &self,
+        state: &AppState,
+        request: &ApiGatewayV2httpRequest,
+    ) -> ApiGatewayV2httpResponse {
+        (self.0)(state, request).await
+    }
+}
+
+/// Rejects unauthenticated requests with 401, tokens lacking the scope with 403
+/// and callers over their rate limit with 429, then passes the caller to the
+/// handler (and records them for the access log)
+struct WithAuth<H> {
+    scope: &'static str,
+    handler: H,
+}
+
+impl<H> Handler for WithAuth<H>
+where
+    H: AsyncFn(&AppState, &ApiGatewayV2httpRequest, AuthUser) -> ApiGatewayV2httpResponse,
+{
+    async fn call(
+        &self,
+        state: &AppState,
+        request: &ApiGatewayV2httpRequest,
+    ) -> ApiGatewayV2httpResponse {
+        let user = match require_scope(request, self.scope).await {
+            Ok(user) => user,
+            Err(response) => return response,
+        };
+        record_caller(&user.id);
+        match state.rate_limiter.check(&user.id) {
+            Ok(()) => (self.handler)(state, request, user).await,
+            Err(retry_after) => {
+                warn!(user = %user.id, "Rate limit exceeded");
+                too_many_requests(retry_after)
+            }
+        }
+    }
+}
+
+/// `WithAuth` for requests carrying a token. With `PUBLIC_READS` on, a
+/// request without one reaches the handler anonymously instead, rate
+/// limited by source IP; with it off that is the usual 401.
+struct WithOptionalAuth<H> {
+    scope: &'static str,
+    handler: H,
+}
+
+impl<H> Handler for WithOptionalAuth<H>
+where
+    H: AsyncFn(&AppState, &ApiGatewayV2httpRequest, Option<AuthUser>) -> ApiGatewayV2httpResponse,
+{
+    async fn call(
+        &self,
+        state: &AppState,
+        request: &ApiGatewayV2httpRequest,
+    ) -> ApiGatewayV2httpResponse {
+        let anonymous = state.config.public_reads && !request.headers.contains_key("authorization");
+        let (user, rate_limit_key) = if anonymous {
+            let source_ip = request.request_context.http.source_ip.as_deref();
+            (None, format!("ip:{}", source_ip.unwrap_or("unknown")))
+        } else {
+            match require_scope(request, self.scope).await {
+                Ok(user) => {
+                    record_caller(&user.id);
+                    let key = user.id.clone();
+                    (Some(user), key)
+                }
+                Err(response) => return response,
+            }
+        };
+        match state.rate_limiter.check(&rate_limit_key) {
+            Ok(()) => (self.handler)(state, request, user).await,
+            Err(retry_after) => {
+                warn!(caller = %rate_limit_key, "Rate limit exceeded");
+                too_many_requests(retry_after)
+            }
+        }
+    }
+}
+
+/// Runs the handler inside a span carrying method, path and response status
+struct WithLogging<H>(H);
+
+impl<H: Handler> Handler for WithLogging<H> {
+    async fn call(
+        &self,
+        state: &AppState,
+        request: &ApiGatewayV2httpRequest,
+    ) -> ApiGatewayV2httpResponse {
+        let span = info_span!(
+            "handler",
+            method = %request.request_context.http.method,
+            path = %request.raw_path.as_deref().unwrap_or("/"),
+            status = field::Empty,
+        );
+        let response = self.0.call(state, request).instrument(span.clone()).await;
+        span.record("status", response.status_code);
+        span.in_scope(|| info!(status = response.status_code, "Request completed"));
+        response
+    }
+}
+
+/// 429 with a Retry-After header in whole seconds (rounded up)
+fn too_many_requests(retry_after: Duration) -> ApiGatewayV2httpResponse {
+    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    ResponseBuilder::new(429)
+        .json(&ApiResponse::<()>::error("Too many requests"))
+        .header("retry-after", seconds.max(1))
+        .build()
+}
+
+fn with_auth<H>(scope: &'static str, handler: H) -> WithAuth<H> {
+    WithAuth { scope, handler }
+}
+
+fn with_logging<H: Handler>(handler: H) -> WithLogging<H> {
+    WithLogging(handler)
+}
+
+/// Unauthenticated route with request logging
+fn public<H>(handler: H) -> WithLogging<Public<H>>
+where
+    Public<H>: Handler,
+{
+    with_logging(Public(handler))
+}
+
+/// Authenticated route requiring `scope`, with request logging
+fn protected<H>(scope: &'static str, handler: H) -> WithLogging<WithAuth<H>>
+where
+    WithAuth<H>: Handler,
+{
+    with_logging(with_auth(scope, handler))
+}
+
+/// Route requiring `scope` unless `PUBLIC_READS` admits anonymous callers,
+/// with request logging
+fn public_read<H>(scope: &'static str, handler: H) -> WithLogging<WithOptionalAuth<H>>
+where
+    WithOptionalAuth<H>: Handler,
+{
+    with_logging(WithOptionalAuth { scope, handler })
+}
+
+/// Readable message from a panic payload
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
 
-    ApiGatewayV2httpResponse {
-        status_code,
-        headers,
-        multi_value_headers: HeaderMap::new(),
-        body: Some(Body::Text(serde_json::to_string(body).unwrap_or_default())),
-        is_base64_encoded: false,
-        cookies: vec![],
+/// Convert a panicking handler into a 500 JSON response (with CORS headers)
+async fn catch_panics<F>(handler: F) -> Result<ApiGatewayV2httpResponse, Error>
+where
+    F: Future<Output = Result<ApiGatewayV2httpResponse, Error>>,
+{
+    match AssertUnwindSafe(handler).catch_unwind().await {
+        Ok(result) => result,
+        Err(panic) => {
+            error!(panic = %panic_message(panic.as_ref()), "Handler panicked");
+            Ok(json_response(
+                500,
+                &ApiResponse::<()>::error("Internal server error"),
+            ))
+        }
+    }
+}
+
+/// Abandon a handler that outlives `limit` with a 504 (CORS headers are
+/// added by `router`), rather than letting the Lambda itself time out
+async fn with_timeout<F>(handler: F, limit: Duration) -> Result<ApiGatewayV2httpResponse, Error>
+where
+    F: Future<Output = Result<ApiGatewayV2httpResponse, Error>>,
+{
+    match tokio::time::timeout(limit, handler).await {
+        Ok(result) => result,
+        Err(_) => {
+            error!(timeout_ms = limit.as_millis() as u64, "Handler timed out");
+            Ok(json_response(
+                504,
+                &ApiResponse::<()>::error("Request timed out"),
+            ))
+        }
     }
 }
 
-#[instrument(skip(state, event), fields(path = %event.payload.raw_path.as_deref().unwrap_or("/")))]
+#[instrument(
+    skip(state, event),
+    fields(
+        path = %event.payload.raw_path.as_deref().unwrap_or("/"),
+        request_id = event.payload.request_context.request_id.as_deref(),
+    )
+)]
 async fn router(
     state: &AppState,
     event: LambdaEvent<ApiGatewayV2httpRequest>,
 ) -> Result<ApiGatewayV2httpResponse, Error> {
+    if is_warmup(&event.payload) {
+        debug!("Warm-up ping");
+        return Ok(json_response(200, &ApiResponse::success(())));
+    }
+
+    let started = Instant::now();
     let request = event.payload;
+    let timeout = Duration::from_millis(state.config.handler_timeout_ms);
+    // Boxed so the whole route table doesn't sit on the caller's stack
+    let handled = Box::pin(catch_panics(with_timeout(
+        dispatch(state, &request),
+        timeout,
+    )));
+    let (result, user_id) = capture_caller(handled).await;
+    let result = result.map(|mut response| {
+        let origin = request.headers.get("origin").and_then(|o| o.to_str().ok());
+        cors::apply(&mut response.headers, &state.config, origin);
+        if let Some(request_id) = request.request_context.request_id.as_deref() {
+            attach_request_id(&mut response, request_id);
+        }
+        response
+    });
+
+    AccessLog::new(&request, result.as_ref().ok(), user_id, started.elapsed()).emit();
+
+    result
+}
+
+/// Stamp the request id into an error envelope so clients can quote it;
+/// success responses and non-envelope bodies are left as they are
+fn attach_request_id(response: &mut ApiGatewayV2httpResponse, request_id: &str) {
+    if response.status_code < 400 {
+        return;
+    }
+    let Some(Body::Text(text)) = &response.body else {
+        return;
+    };
+    let Ok(mut envelope) = serde_json::from_str::<ApiResponse<serde_json::Value>>(text) else {
+        return;
+    };
+    envelope.request_id = Some(request_id.to_string());
+    if let Ok(text) = serde_json::to_string(&envelope) {
+        response.body = Some(Body::Text(text));
+    }
+}
+
+/// `request` with its path in the canonical form routes match on;
+/// borrowed when it already is. A path that doesn't decode is a 400.
+#[allow(clippy::result_large_err)]
+fn normalized_request(
+    request: &ApiGatewayV2httpRequest,
+) -> Result<Cow<'_, ApiGatewayV2httpRequest>, ApiGatewayV2httpResponse> {
+    let raw = request.raw_path.as_deref().unwrap_or("/");
+    let path = normalize_path(raw)
+        .map_err(|message| json_response(400, &ApiResponse::<()>::error(message)))?;
+    if path == raw {
+        return Ok(Cow::Borrowed(request));
+    }
+    let mut request = request.clone();
+    request.raw_path = Some(path);
+    Ok(Cow::Owned(request))
+}
+
+/// Percent-decode the path once, collapse repeated slashes and drop a
+/// trailing slash (except for the root), so `/items/`, `/items//` and
+/// `/%69tems` all route as `/items`. Segments themselves, such as an item
+/// id, are kept as decoded.
+fn normalize_path(raw: &str) -> Result<String, &'static str> {
+    let decoded = percent_decode_str(raw)
+        .decode_utf8()
+        .map_err(|_| "Request path is not valid UTF-8")?;
+    let mut path = String::with_capacity(decoded.len());
+    for segment in decoded.split('/').filter(|segment| !segment.is_empty()) {
+        path.push('/');
+        path.push_str(segment);
+    }
+    if path.is_empty() {
+        path.push('/');
+    }
+    Ok(path)
+}
+
+/// Scheduled warm-up invocations hit `/_warmup` or send `x-warmup: true`
+fn is_warmup(request: &ApiGatewayV2httpRequest) -> bool {
+    request.raw_path.as_deref() == Some("/_warmup")
+        || request
+            .headers
+            .get("x-warmup")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+}
+
+/// 400 for an event that isn't an API Gateway HTTP request, such as one
+/// from another trigger or a hand-built test event, so it is logged and told
+/// apart from a route miss. API Gateway always sends a path; a missing or
+/// empty method can't be seen here, since `aws_lambda_events` reads an empty
+/// one as GET and rejects an absent one before the handler runs.
+fn reject_malformed_event(request: &ApiGatewayV2httpRequest) -> Option<ApiGatewayV2httpResponse> {
+    if request
+        .raw_path
+        .as_deref()
+        .is_some_and(|path| !path.is_empty())
+    {
+        return None;
+    }
+    warn!(
+        method = %request.request_context.http.method,
+        request_id = ?request.request_context.request_id,
+        "Malformed event: no request path"
+    );
+    Some(json_response(
+        400,
+        &ApiResponse::<()>::error("Malformed request: missing path"),
+    ))
+}
+
+async fn dispatch(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+) -> Result<ApiGatewayV2httpResponse, Error> {
+    if let Some(response) = reject_malformed_event(request) {
+        return Ok(response);
+    }
+    let request = match normalized_request(request) {
+        Ok(request) => request,
+        Err(response) => return Ok(response),
+    };
+    let request = request.as_ref();
     let method = request.request_context.http.method.as_str();
     let path = request.raw_path.as_deref().unwrap_or("/");
 
     info!(method = %method, path = %path, "Handling request");
 
+    if let Some(response) = reject_write_when_read_only(request, &state.config) {
+        return Ok(response);
+    }
+
+    if let Some(response) = reject_oversized_body(request, state.config.max_body_bytes) {
+        return Ok(response);
+    }
+
+    if let Some(response) = reject_non_json_body(request) {
+        return Ok(response);
+    }
+
     let response = match (method, path) {
-        ("OPTIONS", _) => {
-            let mut headers = HeaderMap::new();
-            let allowed_origin =
-                std::env::var("ALLOWED_ORIGIN").unwrap_or_else(|_| "*".to_string());
-            headers.insert(
-                "access-control-allow-origin",
-                allowed_origin.parse().unwrap(),
-            );
-            headers.insert(
-                "access-control-allow-methods",
-                "GET, POST, PUT, DELETE, OPTIONS".parse().unwrap(),
-            );
-            headers.insert(
-                "access-control-allow-headers",
-                "Content-Type, Authorization".parse().unwrap(),
-            );
-            ApiGatewayV2httpResponse {
-                status_code: 200,
-                headers,
-                multi_value_headers: HeaderMap::new(),
-                body: None,
-                is_base64_encoded: false,
-                cookies: vec![],
-            }
+        ("OPTIONS", p) => preflight(p),
+        ("GET", "/health") => public(routes::health::handle).call(state, request).await,
+        ("GET", "/openapi.json") => public(routes::openapi::handle).call(state, request).await,
+        ("GET", "/items") => {
+            public_read(SCOPE_ITEMS_READ, routes::items::list)
+                .call(state, request)
+                .await
+        }
+        ("POST", "/items") => {
+            protected(SCOPE_ITEMS_WRITE, routes::items::create)
+                .call(state, request)
+                .await
+        }
+        ("POST", "/items/batch") => {
+            protected(SCOPE_ITEMS_WRITE, routes::items::batch_create)
+                .call(state, request)
+                .await
+        }
+        ("POST", "/items/bulk-delete") => {
+            protected(SCOPE_ITEMS_WRITE, routes::items::bulk_delete)
+                .call(state, request)
+                .await
         }
-        ("GET", "/health") => routes::health::handle(state).await,
-        ("GET", "/items") => routes::items::list(state, &request).await,
-        ("POST", "/items") => routes::items::create(state, &request).await,
-        ("GET", p) if p.starts_with("/items/") => routes::items::get(state, &request).await,
-        ("DELETE", p) if p.starts_with("/items/") => routes::items::delete(state, &request).await,
-        _ => json_response(404, &ApiResponse::<()>::error("Not found")),
+        ("GET", "/items/count") => {
+            protected(SCOPE_ITEMS_READ, routes::items::count)
+                .call(state, request)
+                .await
+        }
+        ("GET", "/items/export") => {
+            protected(SCOPE_ITEMS_READ, routes::items::export)
+                .call(state, request)
+                .await
+        }
+        ("POST", "/items/search") => {
+            protected(SCOPE_ITEMS_READ, routes::items::search)
+                .call(state, request)
+                .await
+        }
+        ("GET", p) if p.starts_with("/items/") => {
+            public_read(SCOPE_ITEMS_READ, routes::items::get)
+                .call(state, request)
+                .await
+        }
+        ("PATCH", p) if p.starts_with("/items/") => {
+            protected(SCOPE_ITEMS_WRITE, routes::items::update)
+                .call(state, request)
+                .await
+        }
+        ("DELETE", p) if p.starts_with("/items/") => {
+            protected(SCOPE_ITEMS_WRITE, routes::items::delete)
+                .call(state, request)
+                .await
+        }
+        ("GET", "/admin/items") => {
+            protected(SCOPE_ADMIN, routes::items::admin_list)
+                .call(state, request)
+                .await
+        }
+        ("POST", "/uploads") => {
+            protected(SCOPE_ITEMS_WRITE, routes::uploads::upload)
+                .call(state, request)
+                .await
+        }
+        ("GET", p) if p.starts_with("/uploads/") => {
+            protected(SCOPE_ITEMS_READ, routes::uploads::download)
+                .call(state, request)
+                .await
+        }
+        (_, p) => unmatched_route(p),
     };
 
     Ok(response)
 }
 
+/// Methods supported on a known path, or None if the path doesn't exist
+fn allowed_methods(path: &str) -> Option<&'static str> {
+    match path {
+        "/health" | "/openapi.json" => Some("GET, OPTIONS"),
+        "/items" => Some("GET, POST, OPTIONS"),
+        "/items/batch" | "/items/bulk-delete" | "/items/search" => Some("POST, OPTIONS"),
+        "/items/count" | "/items/export" | "/admin/items" => Some("GET, OPTIONS"),
+        p if p.starts_with("/items/") => Some("GET, PATCH, DELETE, OPTIONS"),
+        "/uploads" => Some("POST, OPTIONS"),
+        p if p.starts_with("/uploads/") => Some("GET, OPTIONS"),
+        _ => None,
+    }
+}
+
+/// Preflight listing only the methods `path` supports; the remaining CORS
+/// headers are added in `router`. Unknown paths get the usual 404.
+fn preflight(path: &str) -> ApiGatewayV2httpResponse {
+    let Some(allow) = allowed_methods(path) else {
+        return unmatched_route(path);
+    };
+    ResponseBuilder::new(200)
+        .header("access-control-allow-methods", allow)
+        .build()
+}
+
+/// 405 with an Allow header for known paths, 404 otherwise
+fn unmatched_route(path: &str) -> ApiGatewayV2httpResponse {
+    match allowed_methods(path) {
+        Some(allow) => ResponseBuilder::new(405)
+            .json(&ApiResponse::<()>::error("Method not allowed"))
+            .header("allow", allow)
+            .build(),
+        None => json_response(404, &ApiResponse::<()>::error("Not found")),
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    tracing_subscriber::fmt()
-        .json()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("info".parse().unwrap()),
-        )
-        .without_time()
-        .init();
+    logging::init();
 
     let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
         .load()
         .await;
     let dynamo = DynamoClient::new(&aws_config);
     let s3 = S3Client::new(&aws_config);
-    let config = AppConfig::from_env();
+    let credentials = aws_config.credentials_provider();
+    // CONFIG_FILE supplies defaults (handy for local development); env vars win
+    let config_file = std::env::var("CONFIG_FILE").ok();
+    let config = AppConfig::load(config_file.as_deref().map(std::path::Path::new))?;
+    config.validate()?;
 
     info!(table = %config.table_name, bucket = %config.storage_bucket, "Starting Lambda");
 
-    let state = AppState { dynamo, s3, config };
+    let items = Box::new(Repository::new(
+        dynamo.clone(),
+        &config.table_name,
+        config.table_settings(),
+    ));
+    let slugs = Box::new(Repository::new(
+        dynamo.clone(),
+        &config.table_name,
+        config.table_settings(),
+    ));
+    let rate_limiter = RateLimiter::new(config.rate_limit_burst, config.rate_limit_per_second);
+    let state = AppState {
+        dynamo,
+        s3,
+        credentials,
+        config,
+        items,
+        slugs,
+        rate_limiter,
+    };
     lambda_runtime::run(service_fn(|event| router(&state, event))).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with_body(body: &str, is_base64_encoded: bool) -> ApiGatewayV2httpRequest {
+        ApiGatewayV2httpRequest {
+            body: Some(body.to_string()),
+            is_base64_encoded,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_over_limit_body_rejected() {
+        let request = request_with_body(&"x".repeat(65), false);
+        let response = reject_oversized_body(&request, 64).unwrap();
+        assert_eq!(response.status_code, 413);
+    }
+
+    #[test]
+    fn test_under_limit_body_proceeds() {
+        let request = request_with_body(r#"{"name":"ok"}"#, false);
+        assert!(reject_oversized_body(&request, 64).is_none());
+    }
+
+    #[test]
+    fn test_base64_body_uses_decoded_length() {
+        // "hello world" base64-encoded
+        let request = request_with_body("aGVsbG8gd29ybGQ=", true);
+        assert_eq!(decoded_body_len(&request), 11);
+    }
+
+    #[test]
+    fn test_decoded_body_handles_both_encodings() {
+        let encoded = request_with_body("aGVsbG8gd29ybGQ=", true);
+        let plain = request_with_body("hello world", false);
+
+        assert_eq!(
+            decoded_body(&encoded).unwrap().as_deref(),
+            Some(&b"hello world"[..])
+        );
+        assert_eq!(
+            decoded_body(&plain).unwrap().as_deref(),
+            Some(&b"hello world"[..])
+        );
+        assert!(decoded_body(&ApiGatewayV2httpRequest::default())
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_panicking_handler_returns_500_json() {
+        let response = catch_panics(async {
+            panic!("unexpected attribute");
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(response.status_code, 500);
+        let Some(Body::Text(body)) = response.body else {
+            panic!("expected a text body");
+        };
+        let body: ApiResponse<()> = serde_json::from_str(&body).unwrap();
+        assert!(!body.success);
+        assert_eq!(body.error.as_deref(), Some("Internal server error"));
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_is_cut_off_with_504() {
+        let started = Instant::now();
+        let response = with_timeout(
+            async {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                Ok(json_response(200, &ApiResponse::success(())))
+            },
+            Duration::from_millis(20),
+        )
+        .await
+        .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(response.status_code, 504);
+        let Some(Body::Text(body)) = response.body else {
+            panic!("expected a text body");
+        };
+        let body: ApiResponse<()> = serde_json::from_str(&body).unwrap();
+        assert_eq!(body.error.as_deref(), Some("Request timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_fast_handler_passes_through_timeout() {
+        let response = with_timeout(
+            async { Ok(json_response(201, &ApiResponse::success(()))) },
+            Duration::from_millis(20),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.status_code, 201);
+    }
+
+    #[tokio::test]
+    async fn test_warmup_short_circuits_routing() {
+        let state = AppState::for_tests();
+        // Oversized, non-JSON and unauthenticated: dispatch would reject it
+        let mut request = request_with_body(&"x".repeat(state.config.max_body_bytes + 1), false);
+        request.request_context.http.method = "POST".parse().unwrap();
+        request.raw_path = Some("/_warmup".to_string());
+
+        let event = LambdaEvent::new(request.clone(), lambda_runtime::Context::default());
+        let response = router(&state, event).await.unwrap();
+        assert_eq!(response.status_code, 200);
+
+        request.raw_path = Some("/items".to_string());
+        request.headers.insert("x-warmup", "true".parse().unwrap());
+        assert!(is_warmup(&request));
+
+        request.headers.clear();
+        assert!(!is_warmup(&request));
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        for (raw, path) in [
+            ("/items", "/items"),
+            ("/items/", "/items"),
+            ("/items//", "/items"),
+            ("//items///abc/", "/items/abc"),
+            ("/%69tems/abc", "/items/abc"),
+            ("/items/my%20id", "/items/my id"),
+            ("/", "/"),
+            ("", "/"),
+            ("//", "/"),
+        ] {
+            assert_eq!(normalize_path(raw).unwrap(), path, "{raw:?}");
+        }
+        assert!(normalize_path("/items/%ff").is_err());
+    }
+
+    #[test]
+    fn test_wrong_method_on_known_path_returns_405() {
+        let response = unmatched_route("/health");
+
+        assert_eq!(response.status_code, 405);
+        assert_eq!(response.headers.get("allow").unwrap(), "GET, OPTIONS");
+    }
+
+    #[tokio::test]
+    async fn test_count_route_is_matched_and_protected() {
+        let state = AppState::for_tests();
+        let mut request = ApiGatewayV2httpRequest::default();
+        request.request_context.http.method = "GET".parse().unwrap();
+        request.raw_path = Some("/items/count".to_string());
+
+        let response = dispatch(&state, &request).await.unwrap();
+
+        assert_eq!(response.status_code, 401);
+        assert_eq!(allowed_methods("/items/count"), Some("GET, OPTIONS"));
+    }
+
+    #[test]
+    fn test_too_many_requests_sets_retry_after() {
+        let response = too_many_requests(Duration::from_millis(1500));
+
+        assert_eq!(response.status_code, 429);
+        assert_eq!(response.headers.get("retry-after").unwrap(), "2");
+
+        let response = too_many_requests(Duration::from_millis(10));
+        assert_eq!(response.headers.get("retry-after").unwrap(), "1");
+    }
+
+    #[test]
+    fn test_responses_are_not_stored_by_default() {
+        let response = json_response(200, &ApiResponse::success("ok"));
+
+        assert_eq!(response.headers.get("cache-control").unwrap(), "no-store");
+    }
+
+    #[test]
+    fn test_item_cache_control_from_config() {
+        let mut config = AppConfig {
+            item_cache_max_age: 0,
+            ..AppConfig::default()
+        };
+        assert_eq!(CacheControl::for_item(&config), CacheControl::NoStore);
+
+        config.item_cache_max_age = 30;
+        let response = json_response_with_cache(
+            200,
+            &ApiResponse::success("ok"),
+            CacheControl::for_item(&config),
+        );
+        assert_eq!(
+            response.headers.get("cache-control").unwrap(),
+            "private, max-age=30"
+        );
+    }
+
+    fn request_on(domain: &str, stage: &str) -> ApiGatewayV2httpRequest {
+        let mut request = ApiGatewayV2httpRequest::default();
+        request.request_context.domain_name = Some(domain.to_string());
+        request.request_context.stage = Some(stage.to_string());
+        request
+    }
+
+    #[test]
+    fn test_base_url_on_execute_api_with_stage() {
+        let request = request_on("abc123.execute-api.us-east-1.amazonaws.com", "prod");
+        assert_eq!(
+            base_url(&request).as_deref(),
+            Some("https://abc123.execute-api.us-east-1.amazonaws.com/prod")
+        );
+
+        let request = request_on("abc123.execute-api.us-east-1.amazonaws.com", "$default");
+        assert_eq!(
+            base_url(&request).as_deref(),
+            Some("https://abc123.execute-api.us-east-1.amazonaws.com")
+        );
+    }
+
+    #[test]
+    fn test_base_url_on_custom_domain_has_no_stage() {
+        let request = request_on("api.example.com", "prod");
+        assert_eq!(
+            base_url(&request).as_deref(),
+            Some("https://api.example.com")
+        );
+
+        assert_eq!(base_url(&ApiGatewayV2httpRequest::default()), None);
+    }
+
+    #[test]
+    fn test_unknown_path_returns_404() {
+        let response = unmatched_route("/nope");
+
+        assert_eq!(response.status_code, 404);
+        assert!(response.headers.get("allow").is_none());
+    }
+
+    fn post_with_content_type(content_type: Option<&str>) -> ApiGatewayV2httpRequest {
+        let mut request = request_with_body(r#"{"name":"ok"}"#, false);
+        request.request_context.http.method = "POST".parse().unwrap();
+        if let Some(content_type) = content_type {
+            request
+                .headers
+                .insert("content-type", content_type.parse().unwrap());
+        }
+        request
+    }
+
+    #[test]
+    fn test_json_content_type_accepted() {
+        assert!(reject_non_json_body(&post_with_content_type(Some("application/json"))).is_none());
+        assert!(reject_non_json_body(&post_with_content_type(Some(
+            "Application/JSON; charset=utf-8"
+        )))
+        .is_none());
+    }
+
+    #[test]
+    fn test_missing_content_type_rejected() {
+        let response = reject_non_json_body(&post_with_content_type(None)).unwrap();
+        assert_eq!(response.status_code, 415);
+    }
+
+    #[test]
+    fn test_wrong_content_type_rejected() {
+        let response = reject_non_json_body(&post_with_content_type(Some(
+            "application/x-www-form-urlencoded",
+        )))
+        .unwrap();
+        assert_eq!(response.status_code, 415);
+
+        let mut get = post_with_content_type(Some("text/plain"));
+        get.request_context.http.method = "GET".parse().unwrap();
+        assert!(reject_non_json_body(&get).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_protected_handler_rejects_unauthenticated() {
+        let state = AppState::for_tests();
+        let request = ApiGatewayV2httpRequest::default();
+
+        let handler = protected(
+            SCOPE_ITEMS_WRITE,
+            async |_: &AppState, _: &ApiGatewayV2httpRequest, _: AuthUser| {
+                panic!("handler must not run without authentication")
+            },
+        );
+        let response = handler.call(&state, &request).await;
+
+        assert_eq!(response.status_code, 401);
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_records_the_caller_for_the_access_log() {
+        let state = AppState::for_tests();
+        let handler = protected(
+            SCOPE_ITEMS_READ,
+            async |_: &AppState, _: &ApiGatewayV2httpRequest, _: AuthUser| {
+                json_response(200, &ApiResponse::success(()))
+            },
+        );
+
+        let request = authed_request("GET", "/items", None);
+        let (response, caller) = capture_caller(handler.call(&state, &request)).await;
+        assert_eq!(response.status_code, 200);
+        assert_eq!(caller.as_deref(), Some("user-1"));
+
+        let (response, caller) =
+            capture_caller(handler.call(&state, &ApiGatewayV2httpRequest::default())).await;
+        assert_eq!(response.status_code, 401);
+        assert_eq!(caller, None);
+    }
+
+    #[derive(Clone, Default)]
+    struct SpanFields(std::sync::Arc<std::sync::Mutex<Vec<(String, String)>>>);
+
+    impl tracing::field::Visit for SpanFields {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((field.name().to_string(), format!("{value:?}")));
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanFields {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _id: &tracing::span::Id,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            attrs.record(&mut self.clone());
+        }
+
+        fn on_record(
+            &self,
+            _id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            values.record(&mut self.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_logging_wrapper_records_span_fields() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let fields = SpanFields::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(fields.clone()));
+
+        let state = AppState::for_tests();
+        let mut request = ApiGatewayV2httpRequest::default();
+        request.request_context.http.method = "GET".parse().unwrap();
+        request.raw_path = Some("/health".to_string());
+
+        let handler = public(async |_: &AppState, _: &ApiGatewayV2httpRequest| {
+            json_response(200, &ApiResponse::success(()))
+        });
+        handler.call(&state, &request).await;
+
+        let recorded = fields.0.lock().unwrap().clone();
+        assert!(recorded.contains(&("method".to_string(), "GET".to_string())));
+        assert!(recorded.contains(&("path".to_string(), "/health".to_string())));
+        assert!(recorded.contains(&("status".to_string(), "200".to_string())));
+    }
+
+    /// A request through the router, authenticated as `user-1`
+    fn authed_request(method: &str, path: &str, body: Option<&str>) -> ApiGatewayV2httpRequest {
+        authed_request_as("user-1", method, path, body)
+    }
+
+    fn authed_request_as(
+        user_id: &str,
+        method: &str,
+        path: &str,
+        body: Option<&str>,
+    ) -> ApiGatewayV2httpRequest {
+        let mut request = ApiGatewayV2httpRequest {
+            raw_path: Some(path.to_string()),
+            body: body.map(String::from),
+            ..Default::default()
+        };
+        request.request_context.http.method = method.parse().unwrap();
+        let token = testing::access_token(user_id);
+        request
+            .headers
+            .insert("authorization", format!("Bearer {token}").parse().unwrap());
+        request
+            .headers
+            .insert("content-type", "application/json".parse().unwrap());
+        request
+    }
+
+    async fn invoke(
+        state: &AppState,
+        request: ApiGatewayV2httpRequest,
+    ) -> (i64, serde_json::Value) {
+        let event = LambdaEvent::new(request, lambda_runtime::Context::default());
+        let response = router(state, event).await.unwrap();
+        let body = match response.body {
+            Some(Body::Text(text)) => serde_json::from_str(&text).unwrap(),
+            _ => serde_json::Value::Null,
+        };
+        (response.status_code, body)
+    }
+
+    #[tokio::test]
+    async fn test_router_creates_then_reads_item() {
+        let state = AppState::for_tests();
+
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Widget"}"#));
+        let (status, body) = invoke(&state, create).await;
+        assert_eq!(status, 201);
+        let id = body["data"]["id"].as_str().unwrap().to_string();
+
+        let (status, body) =
+            invoke(&state, authed_request("GET", &format!("/items/{id}"), None)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["name"], "Widget");
+        assert_eq!(body["data"]["created_by"], "user-1");
+
+        let (status, body) = invoke(&state, authed_request("GET", "/items", None)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["items"][0]["id"], id.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_router_deleted_item_is_not_found() {
+        let state = AppState::for_tests();
+
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Short-lived"}"#));
+        let (_, body) = invoke(&state, create).await;
+        let path = format!("/items/{}", body["data"]["id"].as_str().unwrap());
+
+        let (status, body) = invoke(&state, authed_request("DELETE", &path, None)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["id"], path.trim_start_matches("/items/"));
+
+        let (status, _) = invoke(&state, authed_request("DELETE", &path, None)).await;
+        assert_eq!(status, 404);
+
+        let (status, _) = invoke(&state, authed_request("GET", &path, None)).await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_router_delete_honors_if_unmodified_since() {
+        let state = AppState::for_tests();
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Widget"}"#));
+        let (_, body) = invoke(&state, create).await;
+        let path = format!("/items/{}", body["data"]["id"].as_str().unwrap());
+        let updated_at =
+            chrono::DateTime::parse_from_rfc3339(body["data"]["updated_at"].as_str().unwrap())
+                .unwrap();
+
+        let delete = |since: &str| {
+            let mut request = authed_request("DELETE", &path, None);
+            request
+                .headers
+                .insert("if-unmodified-since", since.parse().unwrap());
+            request
+        };
+
+        // Modified after the client's copy: refused, and the item survives
+        let (status, _) = invoke(&state, delete("Sat, 01 Jan 2000 00:00:00 GMT")).await;
+        assert_eq!(status, 412);
+        let (status, _) = invoke(&state, authed_request("GET", &path, None)).await;
+        assert_eq!(status, 200);
+
+        // The item's own Last-Modified second counts as unmodified
+        let since = updated_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        let (status, _) = invoke(&state, delete(&since)).await;
+        assert_eq!(status, 200);
+        let (status, _) = invoke(&state, delete(&since)).await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_router_refuses_to_patch_another_users_item() {
+        let state = AppState::for_tests();
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Diary"}"#));
+        let (_, body) = invoke(&state, create).await;
+        let path = format!("/items/{}", body["data"]["id"].as_str().unwrap());
+
+        let patch = r#"{"name":"Mine now","public":true}"#;
+        let request = authed_request_as("user-2", "PATCH", &path, Some(patch));
+        let (status, _) = invoke(&state, request).await;
+        assert_eq!(status, 404);
+
+        let (_, body) = invoke(&state, authed_request("GET", &path, None)).await;
+        assert_eq!(body["data"]["name"], "Diary");
+        assert_eq!(body["data"]["public"], false);
+    }
+
+    #[tokio::test]
+    async fn test_router_refuses_to_delete_another_users_item() {
+        let state = AppState::for_tests();
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Diary"}"#));
+        let (_, body) = invoke(&state, create).await;
+        let path = format!("/items/{}", body["data"]["id"].as_str().unwrap());
+
+        let (status, _) = invoke(&state, authed_request_as("user-2", "DELETE", &path, None)).await;
+        assert_eq!(status, 404);
+
+        // Not a 412 either, which would confirm the item exists
+        let mut request = authed_request_as("user-2", "DELETE", &path, None);
+        request.headers.insert(
+            "if-unmodified-since",
+            "Sat, 01 Jan 2000 00:00:00 GMT".parse().unwrap(),
+        );
+        let (status, _) = invoke(&state, request).await;
+        assert_eq!(status, 404);
+
+        let (status, _) = invoke(&state, authed_request("GET", &path, None)).await;
+        assert_eq!(status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_router_list_items_carry_the_etag_get_returns() {
+        let state = AppState::for_tests();
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Widget"}"#));
+        let (_, body) = invoke(&state, create).await;
+        let path = format!("/items/{}", body["data"]["id"].as_str().unwrap());
+
+        let event = LambdaEvent::new(
+            authed_request("GET", &path, None),
+            lambda_runtime::Context::default(),
+        );
+        let get = router(&state, event).await.unwrap();
+        let etag = get.headers.get("etag").unwrap().to_str().unwrap();
+
+        let (_, body) = invoke(&state, authed_request("GET", "/items", None)).await;
+        assert_eq!(body["data"]["items"][0]["etag"], etag);
+
+        // A projection carries it too once updated_at is selected
+        let mut list = authed_request("GET", "/items", None);
+        list.query_string_parameters = std::collections::HashMap::from([(
+            "fields".to_string(),
+            "name,updated_at".to_string(),
+        )])
+        .into();
+        let (_, body) = invoke(&state, list).await;
+        assert_eq!(body["data"]["items"][0]["etag"], etag);
+
+        // The ETag revalidates the item
+        let mut conditional = authed_request("GET", &path, None);
+        conditional
+            .headers
+            .insert("if-none-match", etag.parse().unwrap());
+        let event = LambdaEvent::new(conditional, lambda_runtime::Context::default());
+        assert_eq!(router(&state, event).await.unwrap().status_code, 304);
+    }
+
+    #[tokio::test]
+    async fn test_router_list_projects_requested_fields() {
+        let state = AppState::for_tests();
+        let create = authed_request(
+            "POST",
+            "/items",
+            Some(r#"{"name":"Widget","description":"Long"}"#),
+        );
+        invoke(&state, create).await;
+
+        let mut list = authed_request("GET", "/items", None);
+        list.query_string_parameters =
+            std::collections::HashMap::from([("fields".to_string(), "name".to_string())]).into();
+        let (status, body) = invoke(&state, list).await;
+        assert_eq!(status, 200);
+        let item = body["data"]["items"][0].as_object().unwrap();
+        let mut keys: Vec<_> = item.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["id", "name"]);
+
+        let mut list = authed_request("GET", "/items", None);
+        list.query_string_parameters =
+            std::collections::HashMap::from([("fields".to_string(), "name,secret".to_string())])
+                .into();
+        let (status, _) = invoke(&state, list).await;
+        assert_eq!(status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_router_list_reads_repeated_query_params() {
+        let state = AppState::for_tests();
+        // Public, so user-1 can see the other users' items too
+        for (user, name) in [("user-1", "One"), ("user-2", "Two"), ("user-3", "Three")] {
+            let body = format!(r#"{{"name":"{name}","description":"Long","public":true}}"#);
+            invoke(
+                &state,
+                authed_request_as(user, "POST", "/items", Some(&body)),
+            )
+            .await;
+        }
+        let query = |pairs: &[(&str, &[&str])]| {
+            std::collections::HashMap::from_iter(pairs.iter().map(|(key, values)| {
+                (
+                    key.to_string(),
+                    values.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+                )
+            }))
+            .into()
+        };
+
+        let mut list = authed_request("GET", "/items", None);
+        list.query_string_parameters = query(&[("created_by", &["user-1", "user-3"])]);
+        let (status, body) = invoke(&state, list).await;
+        assert_eq!(status, 200);
+        let mut names: Vec<_> = body["data"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["name"].as_str().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["One", "Three"]);
+
+        // `?fields=name,description` arrives split into two values
+        let mut list = authed_request("GET", "/items", None);
+        list.query_string_parameters = query(&[
+            ("fields", &["name", "description"]),
+            ("created_by", &["user-2"]),
+        ]);
+        let (status, body) = invoke(&state, list).await;
+        assert_eq!(status, 200);
+        let items = body["data"]["items"].as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        let mut keys: Vec<_> = items[0]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        assert_eq!(keys, vec!["description", "id", "name"]);
+    }
+
+    #[tokio::test]
+    async fn test_router_pages_through_items_with_next_cursor() {
+        let state = AppState::for_tests();
+        for name in ["A", "B", "C", "D", "E"] {
+            let body = format!(r#"{{"name":"{name}"}}"#);
+            invoke(&state, authed_request("POST", "/items", Some(&body))).await;
+        }
+
+        let mut names = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut pages = 0;
+        loop {
+            let mut params = vec![("limit".to_string(), "2".to_string())];
+            params.extend(cursor.map(|cursor| ("cursor".to_string(), cursor)));
+            let mut list = authed_request("GET", "/items", None);
+            list.query_string_parameters = std::collections::HashMap::from_iter(params).into();
+            let (status, body) = invoke(&state, list).await;
+            assert_eq!(status, 200);
+            pages += 1;
+            names.extend(listed_names(&body));
+            assert_eq!(
+                body["data"]["has_more"],
+                !body["data"]["next_cursor"].is_null()
+            );
+            cursor = body["data"]["next_cursor"].as_str().map(String::from);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        names.sort();
+        assert_eq!(names, vec!["A", "B", "C", "D", "E"]);
+        assert_eq!(pages, 3);
+
+        // Search takes the cursor back in its body
+        let (_, first) = invoke(
+            &state,
+            authed_request("POST", "/items/search", Some(r#"{"limit":3}"#)),
+        )
+        .await;
+        let next = format!(r#"{{"limit":3,"cursor":{}}}"#, first["data"]["next_cursor"]);
+        let (status, second) =
+            invoke(&state, authed_request("POST", "/items/search", Some(&next))).await;
+        assert_eq!(status, 200);
+        let mut searched = listed_names(&first);
+        searched.extend(listed_names(&second));
+        searched.sort();
+        assert_eq!(searched, vec!["A", "B", "C", "D", "E"]);
+        assert!(second["data"]["next_cursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_router_rejects_a_cursor_it_did_not_issue() {
+        let state = AppState::for_tests();
+        let mut list = authed_request("GET", "/items", None);
+        list.query_string_parameters =
+            std::collections::HashMap::from([("cursor".to_string(), "not-a-cursor".to_string())])
+                .into();
+        let (status, _) = invoke(&state, list).await;
+        assert_eq!(status, 400);
+
+        let search = r#"{"cursor":"not-a-cursor"}"#;
+        let (status, body) = invoke(
+            &state,
+            authed_request("POST", "/items/search", Some(search)),
+        )
+        .await;
+        assert_eq!(status, 400);
+        assert_eq!(body["fields"][0]["field"], "cursor");
+    }
+
+    #[tokio::test]
+    async fn test_router_lists_by_name_prefix() {
+        let state = AppState::for_tests();
+        for name in ["Blue Widget", "Red Widget", "blue whale"] {
+            let body = format!(r#"{{"name":"{name}"}}"#);
+            invoke(&state, authed_request("POST", "/items", Some(&body))).await;
+        }
+
+        let mut list = authed_request("GET", "/items", None);
+        list.query_string_parameters =
+            std::collections::HashMap::from([("name_prefix".to_string(), "BLUE".to_string())])
+                .into();
+        let (status, body) = invoke(&state, list).await;
+        assert_eq!(status, 200);
+        let names: Vec<_> = body["data"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["blue whale", "Blue Widget"]);
+
+        let mut list = authed_request("GET", "/items", None);
+        list.query_string_parameters = std::collections::HashMap::from([
+            ("name_prefix".to_string(), "blue".to_string()),
+            (
+                "modified_since".to_string(),
+                "2024-01-01T00:00:00Z".to_string(),
+            ),
+        ])
+        .into();
+        let (status, _) = invoke(&state, list).await;
+        assert_eq!(status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_router_lists_items_created_in_range() {
+        let state = AppState::for_tests();
+        let (_, body) = invoke(
+            &state,
+            authed_request("POST", "/items", Some(r#"{"name":"Template"}"#)),
+        )
+        .await;
+        let template: Item = serde_json::from_value(body["data"].clone()).unwrap();
+        state.items.delete(&template.id).await.unwrap();
+        for (id, created_at) in [
+            ("may", "2024-05-31T23:59:59+00:00"),
+            ("june-start", "2024-06-01T00:00:00+00:00"),
+            ("june-mid", "2024-06-15T12:00:00.5+00:00"),
+            ("july", "2024-07-01T00:00:00+00:00"),
+        ] {
+            let mut item = template.clone();
+            item.id = id.to_string();
+            item.created_at = created_at.to_string();
+            // Edited after the range ends, which must not hide it
+            item.updated_at = "2024-08-01T00:00:00+00:00".to_string();
+            state.items.put(&item).await.unwrap();
+        }
+
+        let range = |after: &str, before: &str| {
+            let mut list = authed_request("GET", "/items", None);
+            list.query_string_parameters = std::collections::HashMap::from([
+                ("created_after".to_string(), after.to_string()),
+                ("created_before".to_string(), before.to_string()),
+            ])
+            .into();
+            list
+        };
+
+        let (status, body) = invoke(
+            &state,
+            range("2024-06-01T00:00:00Z", "2024-06-30T23:59:59Z"),
+        )
+        .await;
+        assert_eq!(status, 200);
+        let mut ids: Vec<_> = body["data"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_str().unwrap())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["june-mid", "june-start"]);
+
+        let (status, body) = invoke(
+            &state,
+            range("2024-07-01T00:00:00Z", "2024-06-01T00:00:00Z"),
+        )
+        .await;
+        assert_eq!(status, 400);
+        assert_eq!(
+            body["error"],
+            "created_after must not be later than created_before"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_router_rejects_event_without_method_or_path() {
+        let state = AppState::for_tests();
+        // What a harness or another trigger might send: no method, no path
+        let request: ApiGatewayV2httpRequest =
+            serde_json::from_str(r#"{"requestContext":{"http":{"method":""}}}"#).unwrap();
+
+        let (status, body) = invoke(&state, request).await;
+        assert_eq!(status, 400);
+        assert_eq!(body["error"], "Malformed request: missing path");
+
+        let mut request = authed_request("GET", "", None);
+        request.raw_path = None;
+        let (status, _) = invoke(&state, request).await;
+        assert_eq!(status, 400);
+
+        // A real route miss is still a 404
+        let (status, body) = invoke(&state, authed_request("GET", "/nope", None)).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["error"], "Not found");
+    }
+
+    #[tokio::test]
+    async fn test_router_normalizes_paths_before_matching() {
+        let state = AppState::for_tests();
+        let (_, body) = invoke(
+            &state,
+            authed_request("POST", "/items/", Some(r#"{"name":"Widget"}"#)),
+        )
+        .await;
+        let id = body["data"]["id"].as_str().unwrap().to_string();
+
+        for path in ["/items", "/items/", "/items//"] {
+            let (status, body) = invoke(&state, authed_request("GET", path, None)).await;
+            assert_eq!(status, 200, "{path}");
+            assert_eq!(body["data"]["items"][0]["id"], id.as_str(), "{path}");
+        }
+
+        // The id survives normalization, trailing slash or not
+        for path in [
+            format!("/items/{id}"),
+            format!("/items/{id}/"),
+            format!("/items//{id}"),
+        ] {
+            let (status, body) = invoke(&state, authed_request("GET", &path, None)).await;
+            assert_eq!(status, 200, "{path}");
+            assert_eq!(body["data"]["id"], id.as_str(), "{path}");
+        }
+
+        let (status, _) = invoke(&state, authed_request("GET", "/items/%ff", None)).await;
+        assert_eq!(status, 400);
+    }
+
+    fn anonymous_request(method: &str, path: &str) -> ApiGatewayV2httpRequest {
+        let mut request = authed_request(method, path, None);
+        request.headers.remove("authorization");
+        request
+    }
+
+    /// user-1's public "Catalog" and private "Draft", and user-2's private
+    /// "Other draft", by name
+    async fn seed_public_and_private_items(
+        state: &AppState,
+    ) -> std::collections::HashMap<&'static str, String> {
+        let mut ids = std::collections::HashMap::new();
+        for (user, name, public) in [
+            ("user-1", "Catalog", true),
+            ("user-1", "Draft", false),
+            ("user-2", "Other draft", false),
+        ] {
+            let body = format!(r#"{{"name":"{name}","public":{public}}}"#);
+            let (status, body) = invoke(
+                state,
+                authed_request_as(user, "POST", "/items", Some(&body)),
+            )
+            .await;
+            assert_eq!(status, 201);
+            ids.insert(name, body["data"]["id"].as_str().unwrap().to_string());
+        }
+        ids
+    }
+
+    /// Sorted names of the items in a list response
+    fn listed_names(body: &serde_json::Value) -> Vec<String> {
+        let mut names: Vec<String> = body["data"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["name"].as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_public_reads_show_anonymous_callers_only_public_items() {
+        let mut state = AppState::for_tests();
+        state.config.public_reads = true;
+        let ids = seed_public_and_private_items(&state).await;
+
+        let (status, body) = invoke(&state, anonymous_request("GET", "/items")).await;
+        assert_eq!(status, 200);
+        assert_eq!(listed_names(&body), vec!["Catalog"]);
+
+        let mut projected = anonymous_request("GET", "/items");
+        projected.query_string_parameters =
+            std::collections::HashMap::from([("fields".to_string(), "name".to_string())]).into();
+        let (status, body) = invoke(&state, projected).await;
+        assert_eq!(status, 200);
+        assert_eq!(listed_names(&body), vec!["Catalog"]);
+
+        let (status, _) = invoke(
+            &state,
+            anonymous_request("GET", &format!("/items/{}", ids["Catalog"])),
+        )
+        .await;
+        assert_eq!(status, 200);
+        let (status, _) = invoke(
+            &state,
+            anonymous_request("GET", &format!("/items/{}", ids["Draft"])),
+        )
+        .await;
+        assert_eq!(status, 404);
+
+        // Writes and other reads still need a token
+        for request in [
+            anonymous_request("POST", "/items"),
+            anonymous_request("DELETE", &format!("/items/{}", ids["Catalog"])),
+            anonymous_request("GET", "/items/count"),
+        ] {
+            let (status, _) = invoke(&state, request).await;
+            assert_eq!(status, 401);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_in_reads_see_own_and_public_items() {
+        let mut state = AppState::for_tests();
+        state.config.public_reads = true;
+        let ids = seed_public_and_private_items(&state).await;
+
+        let (status, body) = invoke(&state, authed_request("GET", "/items", None)).await;
+        assert_eq!(status, 200);
+        assert_eq!(listed_names(&body), vec!["Catalog", "Draft"]);
+        let (status, body) =
+            invoke(&state, authed_request_as("user-2", "GET", "/items", None)).await;
+        assert_eq!(status, 200);
+        assert_eq!(listed_names(&body), vec!["Catalog", "Other draft"]);
+
+        let (status, body) = invoke(
+            &state,
+            authed_request("GET", &format!("/items/{}", ids["Draft"]), None),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["public"], false);
+        // Someone else's private item doesn't exist as far as user-1 can tell
+        let (status, _) = invoke(
+            &state,
+            authed_request("GET", &format!("/items/{}", ids["Other draft"]), None),
+        )
+        .await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_signed_in_list_pages_are_full_despite_other_users_private_items() {
+        let state = AppState::for_tests();
+        for i in 0..12 {
+            let (user, public) = match i % 4 {
+                0 => ("user-1", true),
+                1 => ("user-1", false),
+                2 => ("user-2", true),
+                _ => ("user-2", false),
+            };
+            let body = format!(r#"{{"name":"{user} {i:02}","public":{public}}}"#);
+            invoke(
+                &state,
+                authed_request_as(user, "POST", "/items", Some(&body)),
+            )
+            .await;
+        }
+
+        let mut names = Vec::new();
+        let mut page_counts = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut params = vec![("limit".to_string(), "2".to_string())];
+            params.extend(cursor.map(|cursor| ("cursor".to_string(), cursor)));
+            let mut list = authed_request("GET", "/items", None);
+            list.query_string_parameters = std::collections::HashMap::from_iter(params).into();
+            let (status, body) = invoke(&state, list).await;
+            assert_eq!(status, 200);
+            page_counts.push(body["data"]["page_count"].as_u64().unwrap());
+            names.extend(listed_names(&body));
+            cursor = body["data"]["next_cursor"].as_str().map(String::from);
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        // user-2's three private items are never read, so no page comes up short
+        assert_eq!(page_counts, vec![2, 2, 2, 2, 1]);
+        names.sort();
+        let mut expected: Vec<String> = [0, 1, 2, 4, 5, 6, 8, 9, 10]
+            .iter()
+            .map(|i| format!("user-{} {i:02}", if i % 4 < 2 { 1 } else { 2 }))
+            .collect();
+        expected.sort();
+        assert_eq!(names, expected);
+    }
+
+    #[tokio::test]
+    async fn test_modified_since_lists_only_items_the_caller_may_see() {
+        let mut state = AppState::for_tests();
+        state.config.public_reads = true;
+        seed_public_and_private_items(&state).await;
+        let since = |request: &mut ApiGatewayV2httpRequest| {
+            request.query_string_parameters = std::collections::HashMap::from([(
+                "modified_since".to_string(),
+                "2000-01-01T00:00:00Z".to_string(),
+            )])
+            .into();
+        };
+
+        let mut list = authed_request_as("user-2", "GET", "/items", None);
+        since(&mut list);
+        let (status, body) = invoke(&state, list).await;
+        assert_eq!(status, 200);
+        assert_eq!(listed_names(&body), vec!["Catalog", "Other draft"]);
+
+        let mut list = anonymous_request("GET", "/items");
+        since(&mut list);
+        let (status, body) = invoke(&state, list).await;
+        assert_eq!(status, 200);
+        assert_eq!(listed_names(&body), vec!["Catalog"]);
+    }
+
+    #[tokio::test]
+    async fn test_reads_require_a_token_unless_public_reads_enabled() {
+        let state = AppState::for_tests();
+        assert!(!state.config.public_reads);
+
+        for path in ["/items", "/items/some-id"] {
+            let (status, _) = invoke(&state, anonymous_request("GET", path)).await;
+            assert_eq!(status, 401, "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admin_lists_items_across_users() {
+        let state = AppState::for_tests();
+        for (user, body) in [
+            ("user-1", r#"{"name":"Mine"}"#),
+            ("user-2", r#"{"name":"Theirs"}"#),
+        ] {
+            invoke(
+                &state,
+                authed_request_as(user, "POST", "/items", Some(body)),
+            )
+            .await;
+        }
+
+        let mut request = authed_request("GET", "/admin/items", None);
+        let token = testing::access_token_with_scopes("admin-1", &[SCOPE_ADMIN]);
+        request
+            .headers
+            .insert("authorization", format!("Bearer {token}").parse().unwrap());
+        let (status, body) = invoke(&state, request).await;
+        assert_eq!(status, 200);
+        let mut owners: Vec<(&str, &str)> = body["data"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| {
+                (
+                    item["name"].as_str().unwrap(),
+                    item["created_by"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        owners.sort();
+        assert_eq!(owners, vec![("Mine", "user-1"), ("Theirs", "user-2")]);
+
+        // A regular token, even one with both item scopes, is not an admin
+        let (status, body) = invoke(&state, authed_request("GET", "/admin/items", None)).await;
+        assert_eq!(status, 403);
+        assert_eq!(body["error"], "Insufficient scope");
+        let (status, _) = invoke(&state, anonymous_request("GET", "/admin/items")).await;
+        assert_eq!(status, 401);
+    }
+
+    #[tokio::test]
+    async fn test_router_partial_batch_writes_valid_items() {
+        let state = AppState::for_tests();
+        let batch = r#"{"partial":true,"items":[{"name":"One"},{"name":""},{"name":"Three"}]}"#;
+
+        let (status, body) =
+            invoke(&state, authed_request("POST", "/items/batch", Some(batch))).await;
+        assert_eq!(status, 207);
+        let created: Vec<&str> = body["data"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(created, vec!["One", "Three"]);
+        assert_eq!(body["data"]["rejected"][0]["index"], 1);
+        assert_eq!(body["data"]["rejected"][0]["errors"][0]["field"], "name");
+
+        let (_, body) = invoke(&state, authed_request("GET", "/items", None)).await;
+        assert_eq!(body["data"]["items"].as_array().unwrap().len(), 2);
+
+        // Only invalid items: nothing is written, and it's still a 207
+        let batch = r#"{"partial":true,"items":[{"name":""}]}"#;
+        let (status, body) =
+            invoke(&state, authed_request("POST", "/items/batch", Some(batch))).await;
+        assert_eq!(status, 207);
+        assert_eq!(body["data"]["items"], serde_json::json!([]));
+
+        // Strict batches still reject everything for one bad item
+        let batch = r#"{"items":[{"name":"Four"},{"name":""}]}"#;
+        let (status, body) =
+            invoke(&state, authed_request("POST", "/items/batch", Some(batch))).await;
+        assert_eq!(status, 400);
+        assert_eq!(body["fields"][0]["field"], "items[1].name");
+        let (_, body) = invoke(&state, authed_request("GET", "/items", None)).await;
+        assert_eq!(body["data"]["items"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_router_search_matches_equivalent_list() {
+        let state = AppState::for_tests();
+        for (user, name) in [
+            ("user-1", "Blue widget"),
+            ("user-1", "Red widget"),
+            ("user-2", "Blue gadget"),
+            ("user-3", "Green widget"),
+        ] {
+            // Public, so user-1's lists include the other users' items too
+            let body = format!(r#"{{"name":"{name}","description":"Long","public":true}}"#);
+            invoke(
+                &state,
+                authed_request_as(user, "POST", "/items", Some(&body)),
+            )
+            .await;
+        }
+
+        type Params<'a> = &'a [(&'a str, &'a [&'a str])];
+        let cases: [(Params, &str); 4] = [
+            (&[], "{}"),
+            (
+                &[
+                    ("limit", &["2"]),
+                    ("created_by", &["user-1", "user-3"]),
+                    ("fields", &["name", "created_by"]),
+                ],
+                r#"{"limit":2,"filters":{"created_by":["user-1","user-3"]},"fields":["name","created_by"]}"#,
+            ),
+            (
+                &[("modified_since", &["2000-01-01T00:00:00Z"])],
+                r#"{"modified_since":"2000-01-01T00:00:00Z"}"#,
+            ),
+            (
+                &[("name_prefix", &["blue"])],
+                r#"{"filters":{"name_prefix":"blue"},"sort":"name"}"#,
+            ),
+        ];
+        for (params, search) in cases {
+            let mut list = authed_request_as("user-1", "GET", "/items", None);
+            list.query_string_parameters =
+                std::collections::HashMap::from_iter(params.iter().map(|(key, values)| {
+                    (
+                        key.to_string(),
+                        values.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+                    )
+                }))
+                .into();
+            let (list_status, list_body) = invoke(&state, list).await;
+            let (search_status, search_body) = invoke(
+                &state,
+                authed_request_as("user-1", "POST", "/items/search", Some(search)),
+            )
+            .await;
+
+            assert_eq!(list_status, 200, "{search}");
+            assert_eq!(search_status, 200, "{search}");
+            assert!(!list_body["data"]["items"].as_array().unwrap().is_empty());
+            assert_eq!(list_body, search_body, "{search}");
+        }
+
+        let (status, body) = invoke(
+            &state,
+            authed_request(
+                "POST",
+                "/items/search",
+                Some(r#"{"limit":0,"modified_since":"yesterday"}"#),
+            ),
+        )
+        .await;
+        assert_eq!(status, 400);
+        assert_eq!(body["error"], "Validation failed");
+        let fields: Vec<_> = body["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(fields, vec!["limit", "modified_since"]);
+    }
+
+    #[tokio::test]
+    async fn test_router_exports_items_as_ndjson() {
+        let mut state = AppState::for_tests();
+        for name in ["One", "Two", "Three"] {
+            let body = format!(r#"{{"name":"{name}"}}"#);
+            invoke(&state, authed_request("POST", "/items", Some(&body))).await;
+        }
+
+        let export = || {
+            let request = authed_request("GET", "/items/export", None);
+            LambdaEvent::new(request, lambda_runtime::Context::default())
+        };
+        let response = router(&state, export()).await.unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.headers["content-type"], "application/x-ndjson");
+        assert!(!response.headers.contains_key("x-export-truncated"));
+        let Some(Body::Text(text)) = response.body else {
+            panic!("expected a text body");
+        };
+        assert!(text.ends_with('\n'));
+        let mut names: Vec<String> = text
+            .lines()
+            .map(|line| {
+                let item: serde_json::Value = serde_json::from_str(line).unwrap();
+                assert!(item.is_object());
+                item["name"].as_str().unwrap().to_string()
+            })
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["One", "Three", "Two"]);
+
+        state.config.export_max_items = 2;
+        let response = router(&state, export()).await.unwrap();
+        assert_eq!(response.headers["x-export-truncated"], "true");
+        let Some(Body::Text(text)) = response.body else {
+            panic!("expected a text body");
+        };
+        assert_eq!(text.lines().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_router_export_leaves_out_other_users_items() {
+        let state = AppState::for_tests();
+        let mine = authed_request("POST", "/items", Some(r#"{"name":"Mine"}"#));
+        invoke(&state, mine).await;
+        let private = r#"{"name":"Private","public":false}"#;
+        invoke(
+            &state,
+            authed_request_as("user-2", "POST", "/items", Some(private)),
+        )
+        .await;
+
+        let request = authed_request("GET", "/items/export", None);
+        let event = LambdaEvent::new(request, lambda_runtime::Context::default());
+        let response = router(&state, event).await.unwrap();
+        let Some(Body::Text(text)) = response.body else {
+            panic!("expected a text body");
+        };
+        let items: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["name"], "Mine");
+    }
+
+    #[tokio::test]
+    async fn test_router_slugs_are_unique_per_user() {
+        let state = AppState::for_tests();
+        let body = r#"{"name":"Widget","slug":"blue-widget"}"#;
+
+        let (status, first) = invoke(&state, authed_request("POST", "/items", Some(body))).await;
+        assert_eq!(status, 201);
+        assert_eq!(first["data"]["slug"], "blue-widget");
+
+        let (status, _) = invoke(&state, authed_request("POST", "/items", Some(body))).await;
+        assert_eq!(status, 409);
+
+        let other_user = authed_request_as("user-2", "POST", "/items", Some(body));
+        let (status, _) = invoke(&state, other_user).await;
+        assert_eq!(status, 201);
+    }
+
+    #[tokio::test]
+    async fn test_router_update_rejects_taken_slug_and_frees_old_one() {
+        let state = AppState::for_tests();
+        let create = |body: &'static str| authed_request("POST", "/items", Some(body));
+        invoke(&state, create(r#"{"name":"A","slug":"taken"}"#)).await;
+        let (_, body) = invoke(&state, create(r#"{"name":"B","slug":"old"}"#)).await;
+        let path = format!("/items/{}", body["data"]["id"].as_str().unwrap());
+
+        let patch = authed_request("PATCH", &path, Some(r#"{"slug":"taken"}"#));
+        let (status, _) = invoke(&state, patch).await;
+        assert_eq!(status, 409);
+
+        let patch = authed_request("PATCH", &path, Some(r#"{"slug":"new"}"#));
+        let (status, body) = invoke(&state, patch).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["slug"], "new");
+
+        let (status, _) = invoke(&state, create(r#"{"name":"C","slug":"old"}"#)).await;
+        assert_eq!(status, 201);
+    }
+
+    #[tokio::test]
+    async fn test_router_slug_of_deleted_item_can_be_reused() {
+        let state = AppState::for_tests();
+        let body = r#"{"name":"Widget","slug":"reusable"}"#;
+        let (_, created) = invoke(&state, authed_request("POST", "/items", Some(body))).await;
+        let path = format!("/items/{}", created["data"]["id"].as_str().unwrap());
+        invoke(&state, authed_request("DELETE", &path, None)).await;
+
+        let (status, _) = invoke(&state, authed_request("POST", "/items", Some(body))).await;
+        assert_eq!(status, 201);
+    }
+
+    #[tokio::test]
+    async fn test_router_delete_missing_item_is_not_found() {
+        let state = AppState::for_tests();
+
+        let (status, body) = invoke(&state, authed_request("DELETE", "/items/missing", None)).await;
+
+        assert_eq!(status, 404);
+        assert_eq!(body["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_router_counts_only_the_callers_items() {
+        let state = AppState::for_tests();
+        for user in ["user-1", "user-1", "user-2"] {
+            invoke(
+                &state,
+                authed_request_as(user, "POST", "/items", Some(r#"{"name":"Widget"}"#)),
+            )
+            .await;
+        }
+
+        let (status, body) = invoke(&state, authed_request("GET", "/items/count", None)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["count"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_router_bulk_delete_only_removes_the_callers_items() {
+        let state = AppState::for_tests();
+        let mut ids = Vec::new();
+        for user in ["user-1", "user-2"] {
+            let (_, body) = invoke(
+                &state,
+                authed_request_as(user, "POST", "/items", Some(r#"{"name":"Widget"}"#)),
+            )
+            .await;
+            ids.push(body["data"]["id"].as_str().unwrap().to_string());
+        }
+
+        let body = serde_json::json!({ "ids": [ids[0], ids[1], "missing"] }).to_string();
+        let (status, body) = invoke(
+            &state,
+            authed_request("POST", "/items/bulk-delete", Some(&body)),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["deleted"], 1);
+        assert_eq!(body["data"]["unprocessed_ids"], serde_json::json!([]));
+
+        let (status, _) = invoke(
+            &state,
+            authed_request_as("user-2", "GET", &format!("/items/{}", ids[1]), None),
+        )
+        .await;
+        assert_eq!(status, 200);
+        let (status, _) = invoke(
+            &state,
+            authed_request("GET", &format!("/items/{}", ids[0]), None),
+        )
+        .await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_router_preflight_lists_only_the_paths_methods() {
+        let state = AppState::for_tests();
+        let preflight = |path: &str| {
+            let mut request = ApiGatewayV2httpRequest {
+                raw_path: Some(path.to_string()),
+                ..Default::default()
+            };
+            request.request_context.http.method = "OPTIONS".parse().unwrap();
+            LambdaEvent::new(request, lambda_runtime::Context::default())
+        };
+
+        let response = router(&state, preflight("/health")).await.unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(
+            response.headers["access-control-allow-methods"],
+            "GET, OPTIONS"
+        );
+
+        let response = router(&state, preflight("/items")).await.unwrap();
+        assert_eq!(
+            response.headers["access-control-allow-methods"],
+            "GET, POST, OPTIONS"
+        );
+
+        let response = router(&state, preflight("/items/abc")).await.unwrap();
+        assert_eq!(
+            response.headers["access-control-allow-methods"],
+            "GET, PATCH, DELETE, OPTIONS"
+        );
+
+        let response = router(&state, preflight("/nope")).await.unwrap();
+        assert_eq!(response.status_code, 404);
+    }
+
+    #[tokio::test]
+    async fn test_router_preflight_includes_cors_max_age() {
+        let mut state = AppState::for_tests();
+        state.config.allowed_origins = vec!["https://app.example".to_string()];
+        state.config.cors_max_age = 7200;
+        state.config.cors_allow_credentials = true;
+        let mut request = ApiGatewayV2httpRequest {
+            raw_path: Some("/items".to_string()),
+            ..Default::default()
+        };
+        request.request_context.http.method = "OPTIONS".parse().unwrap();
+        request
+            .headers
+            .insert("origin", "https://app.example".parse().unwrap());
+
+        let event = LambdaEvent::new(request.clone(), lambda_runtime::Context::default());
+        let response = router(&state, event).await.unwrap();
+
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.headers["access-control-max-age"], "7200");
+        assert_eq!(
+            response.headers["access-control-allow-origin"],
+            "https://app.example"
+        );
+        assert_eq!(response.headers["access-control-allow-credentials"], "true");
+
+        // Actual responses carry the same headers
+        request.request_context.http.method = "GET".parse().unwrap();
+        let event = LambdaEvent::new(request, lambda_runtime::Context::default());
+        let response = router(&state, event).await.unwrap();
+        assert_eq!(response.status_code, 401);
+        assert_eq!(response.headers["access-control-allow-credentials"], "true");
+    }
+
+    #[tokio::test]
+    async fn test_upload_presigns_post_for_allowed_type() {
+        let mut state = AppState::for_tests();
+        state.config.max_upload_bytes = 2048;
+        let body = r#"{"filename":"cat.png","content_type":"image/png"}"#;
+
+        let (status, body) = invoke(&state, authed_request("POST", "/uploads", Some(body))).await;
+
+        assert_eq!(status, 200);
+        let key = body["data"]["key"].as_str().unwrap();
+        assert!(key.starts_with("user-1/"), "{key}");
+        assert!(key.ends_with("/cat.png"), "{key}");
+        assert_eq!(body["data"]["max_bytes"], 2048);
+        assert_eq!(body["data"]["fields"]["key"], key);
+        assert_eq!(body["data"]["fields"]["Content-Type"], "image/png");
+        assert!(body["data"]["fields"]["policy"].is_string());
+        assert!(body["data"]["url"]
+            .as_str()
+            .unwrap()
+            .starts_with("https://"));
+    }
+
+    #[tokio::test]
+    async fn test_upload_rejects_disallowed_type() {
+        let state = AppState::for_tests();
+        let body = r#"{"filename":"setup.exe","content_type":"application/x-msdownload"}"#;
+
+        let (status, body) = invoke(&state, authed_request("POST", "/uploads", Some(body))).await;
+
+        assert_eq!(status, 400);
+        assert_eq!(body["error"], "Validation failed");
+        assert_eq!(body["fields"][0]["field"], "content_type");
+    }
+
+    #[tokio::test]
+    async fn test_download_presigns_url_for_own_key() {
+        let state = AppState::for_tests();
+
+        let (status, body) = invoke(
+            &state,
+            authed_request("GET", "/uploads/user-1/docs/report.pdf", None),
+        )
+        .await;
+
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["key"], "user-1/docs/report.pdf");
+        assert_eq!(body["data"]["content_type"], "application/pdf");
+        let url = body["data"]["url"].as_str().unwrap();
+        assert!(url.contains("/user-1/docs/report.pdf?"), "{url}");
+        assert!(url.contains("X-Amz-Expires=300"), "{url}");
+        assert!(
+            url.contains("response-content-type=application%2Fpdf"),
+            "{url}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_rejects_other_users_key() {
+        let state = AppState::for_tests();
+
+        let (status, _) =
+            invoke(&state, authed_request("GET", "/uploads/user-2/a.txt", None)).await;
+
+        assert_eq!(status, 403);
+    }
+
+    #[tokio::test]
+    async fn test_download_rejects_namespace_escape() {
+        let state = AppState::for_tests();
+
+        for path in [
+            "/uploads/user-1/../user-2/a.txt",
+            "/uploads/user-1/%2E%2E/user-2/a.txt",
+            "/uploads/user-1/%ff",
+        ] {
+            let (status, _) = invoke(&state, authed_request("GET", path, None)).await;
+            assert_eq!(status, 400, "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mode_blocks_writes_but_serves_reads() {
+        let mut state = AppState::for_tests();
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Widget"}"#));
+        let (_, body) = invoke(&state, create).await;
+        let path = format!("/items/{}", body["data"]["id"].as_str().unwrap());
+        state.config.read_only = true;
+
+        for request in [
+            authed_request("POST", "/items", Some(r#"{"name":"Other"}"#)),
+            authed_request("PATCH", &path, Some(r#"{"name":"Renamed"}"#)),
+            authed_request("DELETE", &path, None),
+            authed_request("POST", "/items/batch", Some(r#"{"items":[]}"#)),
+        ] {
+            let event = LambdaEvent::new(request, lambda_runtime::Context::default());
+            let response = router(&state, event).await.unwrap();
+            assert_eq!(response.status_code, 503);
+            assert_eq!(response.headers.get("retry-after").unwrap(), "300");
+        }
+
+        let (status, body) = invoke(&state, authed_request("GET", &path, None)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["name"], "Widget");
+        let (status, _) = invoke(&state, authed_request("GET", "/items", None)).await;
+        assert_eq!(status, 200);
+        let (status, _) = invoke(&state, authed_request("GET", "/health", None)).await;
+        assert_eq!(status, 200);
+        // Unknown routes still 404 rather than claiming maintenance
+        let (status, _) = invoke(&state, authed_request("POST", "/nope", None)).await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mode_still_serves_search() {
+        let mut state = AppState::for_tests();
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Widget"}"#));
+        invoke(&state, create).await;
+        state.config.read_only = true;
+
+        let search = authed_request("POST", "/items/search", Some(r#"{"limit":10}"#));
+        let (status, body) = invoke(&state, search).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["items"][0]["name"], "Widget");
+    }
+
+    #[tokio::test]
+    async fn test_writes_allowed_when_not_read_only() {
+        let mut state = AppState::for_tests();
+        state.config.read_only = false;
+
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Widget"}"#));
+        let (status, body) = invoke(&state, create).await;
+        assert_eq!(status, 201);
+        let path = format!("/items/{}", body["data"]["id"].as_str().unwrap());
+
+        let (status, _) = invoke(&state, authed_request("DELETE", &path, None)).await;
+        assert_eq!(status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_error_response_carries_request_id() {
+        let state = AppState::for_tests();
+
+        let mut get = authed_request("GET", "/items/missing", None);
+        get.request_context.request_id = Some("req-abc".to_string());
+        let (status, body) = invoke(&state, get).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"], "Item not found");
+        assert_eq!(body["request_id"], "req-abc");
+
+        let mut list = authed_request("GET", "/items", None);
+        list.request_context.request_id = Some("req-def".to_string());
+        let (status, body) = invoke(&state, list).await;
+        assert_eq!(status, 200);
+        assert!(body.get("request_id").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_create_uses_configured_id_scheme() {
+        let mut state = AppState::for_tests();
+        state.config.id_scheme = shared::ids::IdScheme::Ulid;
+
+        let mut ids = Vec::new();
+        for name in ["First", "Second", "Third"] {
+            let body = format!(r#"{{"name":"{name}"}}"#);
+            let (status, body) =
+                invoke(&state, authed_request("POST", "/items", Some(&body))).await;
+            assert_eq!(status, 201);
+            ids.push(body["data"]["id"].as_str().unwrap().to_string());
+        }
+
+        assert!(ids.iter().all(|id| id.len() == 26));
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(ids, sorted);
+        let (status, _) = invoke(
+            &state,
+            authed_request("GET", &format!("/items/{}", ids[0]), None),
+        )
+        .await;
+        assert_eq!(status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_get_distinguishes_live_soft_deleted_and_absent_items() {
+        let state = AppState::for_tests();
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Live"}"#));
+        let (_, body) = invoke(&state, create).await;
+        let live_path = format!("/items/{}", body["data"]["id"].as_str().unwrap());
+
+        let mut deleted: Item = serde_json::from_value(body["data"].clone()).unwrap();
+        deleted.id = "gone".to_string();
+        deleted.deleted_at = Some("2024-06-01T12:00:00+00:00".to_string());
+        state.items.put(&deleted).await.unwrap();
+
+        let (status, body) = invoke(&state, authed_request("GET", &live_path, None)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["name"], "Live");
+
+        let (status, body) = invoke(&state, authed_request("GET", "/items/gone", None)).await;
+        assert_eq!(status, 410);
+        assert_eq!(body["error"], "Item has been deleted");
+        assert_eq!(body["data"]["id"], "gone");
+        assert_eq!(body["data"]["deleted_at"], "2024-06-01T12:00:00+00:00");
+
+        let mut get = authed_request("GET", "/items/gone", None);
+        get.query_string_parameters =
+            std::collections::HashMap::from([("include_deleted".to_string(), "true".to_string())])
+                .into();
+        let (status, body) = invoke(&state, get).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["deleted_at"], "2024-06-01T12:00:00+00:00");
+
+        let (status, _) = invoke(&state, authed_request("GET", "/items/absent", None)).await;
+        assert_eq!(status, 404);
+    }
+
+    /// Create "Live" and "Gone" as user-1, then soft-delete "Gone"; returns its path
+    async fn seed_soft_deleted_item(state: &AppState) -> String {
+        invoke(
+            state,
+            authed_request("POST", "/items", Some(r#"{"name":"Live"}"#)),
+        )
+        .await;
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Gone"}"#));
+        let (_, body) = invoke(state, create).await;
+        let mut deleted: Item = serde_json::from_value(body["data"].clone()).unwrap();
+        deleted.deleted_at = Some("2024-06-01T12:00:00+00:00".to_string());
+        state.items.put(&deleted).await.unwrap();
+        format!("/items/{}", deleted.id)
+    }
+
+    #[tokio::test]
+    async fn test_lists_skip_soft_deleted_items() {
+        let state = AppState::for_tests();
+        seed_soft_deleted_item(&state).await;
+
+        let (_, body) = invoke(&state, authed_request("GET", "/items", None)).await;
+        assert_eq!(listed_names(&body), vec!["Live"]);
+
+        let mut projected = authed_request("GET", "/items", None);
+        projected.query_string_parameters =
+            std::collections::HashMap::from([("fields".to_string(), "name".to_string())]).into();
+        let (_, body) = invoke(&state, projected).await;
+        assert_eq!(listed_names(&body), vec!["Live"]);
+
+        let request = authed_request("GET", "/items/export", None);
+        let event = LambdaEvent::new(request, lambda_runtime::Context::default());
+        let response = router(&state, event).await.unwrap();
+        let Some(Body::Text(text)) = response.body else {
+            panic!("expected a text body");
+        };
+        assert_eq!(text.lines().count(), 1);
+        assert!(text.contains(r#""name":"Live""#));
+    }
+
+    #[tokio::test]
+    async fn test_patch_of_soft_deleted_item_is_gone() {
+        let state = AppState::for_tests();
+        let path = seed_soft_deleted_item(&state).await;
+
+        let patch = authed_request("PATCH", &path, Some(r#"{"name":"Revived"}"#));
+        let (status, body) = invoke(&state, patch).await;
+        assert_eq!(status, 410);
+        assert_eq!(body["data"]["deleted_at"], "2024-06-01T12:00:00+00:00");
+
+        let mut get = authed_request("GET", &path, None);
+        get.query_string_parameters =
+            std::collections::HashMap::from([("include_deleted".to_string(), "true".to_string())])
+                .into();
+        let (_, body) = invoke(&state, get).await;
+        assert_eq!(body["data"]["name"], "Gone");
+    }
+
+    #[tokio::test]
+    async fn test_store_rechecks_item_limits_on_write() {
+        let mut state = AppState::for_tests();
+        let settings = shared::repository::TableSettings {
+            item_limits: shared::models::ItemLimits {
+                name_max_len: 3,
+                ..state.config.item_limits()
+            },
+            ..state.config.table_settings()
+        };
+        state.items = Box::new(testing::InMemoryStore::new(settings));
+
+        // Request validation allows the name; the store's own check does not
+        let create = authed_request("POST", "/items", Some(r#"{"name":"Widget"}"#));
+        let (status, _) = invoke(&state, create).await;
+        assert_eq!(status, 500);
+
+        let (status, body) = invoke(&state, authed_request("GET", "/items", None)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["items"], serde_json::json!([]));
+    }
+}
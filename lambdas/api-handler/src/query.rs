@@ -0,0 +1,162 @@
+#![allow(dead_code)]
+
+use aws_lambda_events::apigw::ApiGatewayV2httpResponse;
+use aws_lambda_events::query_map::QueryMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::{json_response, ApiResponse};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum QueryParamError {
+    #[error("Query parameter '{0}' must be an integer")]
+    InvalidInt(String),
+    #[error("Query parameter '{0}' must be true or false")]
+    InvalidBool(String),
+    #[error("Query parameter '{0}' must not be empty")]
+    Empty(String),
+}
+
+impl QueryParamError {
+    /// 400 response carrying the error message
+    pub fn into_response(self) -> ApiGatewayV2httpResponse {
+        json_response(400, &ApiResponse::<()>::error(self.to_string()))
+    }
+}
+
+/// Typed access to query string parameters; missing parameters are `Ok(None)`,
+/// present but malformed ones are errors
+pub struct QueryParams<'a>(&'a QueryMap);
+
+impl<'a> QueryParams<'a> {
+    pub fn new(query: &'a QueryMap) -> Self {
+        Self(query)
+    }
+
+    pub fn get_int<T: FromStr>(&self, name: &str) -> Result<Option<T>, QueryParamError> {
+        self.0
+            .first(name)
+            .map(|v| {
+                v.trim()
+                    .parse()
+                    .map_err(|_| QueryParamError::InvalidInt(name.to_string()))
+            })
+            .transpose()
+    }
+
+    pub fn get_bool(&self, name: &str) -> Result<Option<bool>, QueryParamError> {
+        self.0
+            .first(name)
+            .map(|v| match v.to_ascii_lowercase().as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(QueryParamError::InvalidBool(name.to_string())),
+            })
+            .transpose()
+    }
+
+    pub fn get_string(&self, name: &str) -> Result<Option<&'a str>, QueryParamError> {
+        match self.0.first(name) {
+            Some("") => Err(QueryParamError::Empty(name.to_string())),
+            value => Ok(value),
+        }
+    }
+
+    /// Every value of a repeated parameter (`?tag=a&tag=b`), in request order;
+    /// empty when the parameter is absent. API Gateway also splits comma-joined
+    /// values, so `?tag=a,b` yields the same list.
+    pub fn get_all(&self, name: &str) -> Result<Vec<String>, QueryParamError> {
+        let values = self.0.all(name).unwrap_or_default();
+        if values.iter().any(|v| v.trim().is_empty()) {
+            return Err(QueryParamError::Empty(name.to_string()));
+        }
+        Ok(values.iter().map(|v| v.trim().to_string()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn query(pairs: &[(&str, &str)]) -> QueryMap {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>()
+            .into()
+    }
+
+    #[test]
+    fn test_get_int() {
+        let map = query(&[("limit", "25"), ("bad", "abc")]);
+        let params = QueryParams::new(&map);
+
+        assert_eq!(params.get_int::<i32>("limit"), Ok(Some(25)));
+        assert_eq!(params.get_int::<i32>("missing"), Ok(None));
+        assert_eq!(
+            params.get_int::<i32>("bad"),
+            Err(QueryParamError::InvalidInt("bad".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_get_bool() {
+        let map = query(&[("yes", "TRUE"), ("no", "false"), ("bad", "maybe")]);
+        let params = QueryParams::new(&map);
+
+        assert_eq!(params.get_bool("yes"), Ok(Some(true)));
+        assert_eq!(params.get_bool("no"), Ok(Some(false)));
+        assert_eq!(params.get_bool("missing"), Ok(None));
+        assert!(params.get_bool("bad").is_err());
+    }
+
+    #[test]
+    fn test_get_string() {
+        let map = query(&[("name", "widget"), ("blank", "")]);
+        let params = QueryParams::new(&map);
+
+        assert_eq!(params.get_string("name"), Ok(Some("widget")));
+        assert_eq!(params.get_string("missing"), Ok(None));
+        assert_eq!(
+            params.get_string("blank"),
+            Err(QueryParamError::Empty("blank".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_get_all_single_and_repeated() {
+        let map: QueryMap = HashMap::from([
+            ("tag".to_string(), vec!["a".to_string(), " b".to_string()]),
+            ("name".to_string(), vec!["widget".to_string()]),
+            ("blank".to_string(), vec!["a".to_string(), "".to_string()]),
+        ])
+        .into();
+        let params = QueryParams::new(&map);
+
+        assert_eq!(
+            params.get_all("tag"),
+            Ok(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(params.get_all("name"), Ok(vec!["widget".to_string()]));
+        assert_eq!(params.get_all("missing"), Ok(vec![]));
+        assert_eq!(
+            params.get_all("blank"),
+            Err(QueryParamError::Empty("blank".to_string()))
+        );
+        // Other accessors still see the first value
+        assert_eq!(params.get_string("tag"), Ok(Some("a")));
+    }
+
+    #[test]
+    fn test_error_response_is_400() {
+        let response = QueryParamError::InvalidInt("limit".to_string()).into_response();
+
+        assert_eq!(response.status_code, 400);
+        let body = match response.body {
+            Some(aws_lambda_events::encodings::Body::Text(text)) => text,
+            other => panic!("unexpected body: {other:?}"),
+        };
+        assert!(body.contains("Query parameter 'limit' must be an integer"));
+    }
+}
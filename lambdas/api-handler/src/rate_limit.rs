@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Buckets kept before idle (fully refilled) ones are pruned
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Token bucket holding up to `capacity` requests, refilled continuously
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(capacity: f64, now: Instant) -> Self {
+        Self {
+            tokens: capacity,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, capacity: f64, per_second: f64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(capacity);
+        self.updated_at = now;
+    }
+}
+
+/// Per-user request limiter. State lives in the Lambda's memory, so limits
+/// are per execution environment and reset on cold start.
+pub struct RateLimiter {
+    capacity: f64,
+    per_second: f64,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(burst: u32, per_second: f64) -> Self {
+        Self {
+            capacity: f64::from(burst.max(1)),
+            per_second: per_second.max(f64::MIN_POSITIVE),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take one token for `key`, or return how long until one is available
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= PRUNE_THRESHOLD {
+            let (capacity, per_second) = (self.capacity, self.per_second);
+            buckets.retain(|_, bucket| {
+                bucket.refill(capacity, per_second, now);
+                bucket.tokens < capacity
+            });
+        }
+
+        let bucket = buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::full(self.capacity, now));
+        bucket.refill(self.capacity, self.per_second, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.per_second,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows_burst_then_rejects() {
+        let limiter = RateLimiter::new(3, 1.0);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at("user-1", now).is_ok());
+        }
+        let retry_after = limiter.check_at("user-1", now).unwrap_err();
+        assert!(retry_after > Duration::ZERO && retry_after <= Duration::from_secs(1));
+
+        // Other users have their own bucket
+        assert!(limiter.check_at("user-2", now).is_ok());
+    }
+
+    #[test]
+    fn test_refills_over_time() {
+        let limiter = RateLimiter::new(2, 2.0);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("user-1", now).is_ok());
+        assert!(limiter.check_at("user-1", now).is_ok());
+        assert!(limiter.check_at("user-1", now).is_err());
+
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.check_at("user-1", later).is_ok());
+        assert!(limiter.check_at("user-1", later).is_err());
+
+        // Refill never exceeds the burst size
+        let much_later = later + Duration::from_secs(60);
+        assert!(limiter.check_at("user-1", much_later).is_ok());
+        assert!(limiter.check_at("user-1", much_later).is_ok());
+        assert!(limiter.check_at("user-1", much_later).is_err());
+    }
+}
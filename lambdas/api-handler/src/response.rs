@@ -0,0 +1,111 @@
+use crate::{ApiResponse, CacheControl};
+use aws_lambda_events::apigw::ApiGatewayV2httpResponse;
+use aws_lambda_events::encodings::Body;
+use aws_lambda_events::http::HeaderMap;
+use serde::Serialize;
+
+/// Builds an API Gateway response from a status, an optional body and
+/// headers, starting from [`base_headers`]. CORS headers are not set here;
+/// the router adds them to every response on the way out (see `cors::apply`).
+#[must_use]
+pub struct ResponseBuilder {
+    status_code: i64,
+    headers: HeaderMap,
+    body: Option<String>,
+}
+
+impl ResponseBuilder {
+    pub fn new(status_code: i64) -> Self {
+        Self {
+            status_code,
+            headers: base_headers(),
+            body: None,
+        }
+    }
+
+    /// Serialize `body` as the usual JSON envelope
+    pub fn json<T: Serialize>(self, body: &ApiResponse<T>) -> Self {
+        self.text(
+            "application/json",
+            serde_json::to_string(body).unwrap_or_default(),
+        )
+    }
+
+    /// A pre-rendered body served as-is with its content type
+    pub fn text(mut self, content_type: &str, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self.header("content-type", content_type)
+    }
+
+    pub fn cache(self, cache: CacheControl) -> Self {
+        self.header("cache-control", cache.header_value())
+    }
+
+    /// Set a header, replacing any earlier value
+    pub fn header(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.headers
+            .insert(name, value.to_string().parse().unwrap());
+        self
+    }
+
+    pub fn build(self) -> ApiGatewayV2httpResponse {
+        ApiGatewayV2httpResponse {
+            status_code: self.status_code,
+            headers: self.headers,
+            multi_value_headers: HeaderMap::new(),
+            body: self.body.map(Body::Text),
+            is_base64_encoded: false,
+            cookies: vec![],
+        }
+    }
+}
+
+/// Headers every response starts with: uncacheable unless the handler says otherwise
+pub fn base_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "cache-control",
+        CacheControl::NoStore.header_value().parse().unwrap(),
+    );
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_response_headers() {
+        let response = ResponseBuilder::new(201)
+            .json(&ApiResponse::success("created"))
+            .cache(CacheControl::Private { max_age: 60 })
+            .header("location", "/items/1")
+            .build();
+
+        assert_eq!(response.status_code, 201);
+        assert_eq!(response.headers.len(), 3);
+        assert_eq!(response.headers["content-type"], "application/json");
+        assert_eq!(response.headers["cache-control"], "private, max-age=60");
+        assert_eq!(response.headers["location"], "/items/1");
+        assert!(matches!(
+            response.body,
+            Some(Body::Text(ref text)) if text.contains("\"created\"")
+        ));
+    }
+
+    #[test]
+    fn test_preflight_response_headers() {
+        let response = ResponseBuilder::new(200)
+            .header("access-control-allow-methods", "GET, OPTIONS")
+            .build();
+
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.headers.len(), 2);
+        assert_eq!(response.headers["cache-control"], "no-store");
+        assert_eq!(
+            response.headers["access-control-allow-methods"],
+            "GET, OPTIONS"
+        );
+        assert!(response.body.is_none());
+    }
+}
@@ -1,19 +1,178 @@
+use crate::query::QueryParams;
 use crate::{json_response, ApiResponse, AppState};
-use aws_lambda_events::apigw::ApiGatewayV2httpResponse;
+use aws_lambda_events::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
 use serde::Serialize;
+use tracing::warn;
 
 #[derive(Serialize)]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
+    pub git_sha: String,
+    pub build_time: String,
+    pub target: String,
+    /// Reachability of each dependency; only checked with `?deep=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<DependencyStatus>,
 }
 
-pub async fn handle(_state: &AppState) -> ApiGatewayV2httpResponse {
-    json_response(
-        200,
-        &ApiResponse::success(HealthResponse {
+impl HealthResponse {
+    fn current() -> Self {
+        Self {
             status: "healthy".to_string(),
             version: env!("CARGO_PKG_VERSION").to_string(),
-        }),
-    )
+            git_sha: build_value(option_env!("BUILD_GIT_SHA")),
+            build_time: build_value(option_env!("BUILD_TIME")),
+            target: build_value(option_env!("BUILD_TARGET")),
+            dependencies: None,
+        }
+    }
+
+    /// Include the dependency checks, marking the service unhealthy if any failed
+    fn with_dependencies(self, dependencies: DependencyStatus) -> Self {
+        Self {
+            status: if dependencies.all_ok() {
+                self.status
+            } else {
+                "unhealthy".to_string()
+            },
+            dependencies: Some(dependencies),
+            ..self
+        }
+    }
+
+    fn status_code(&self) -> i64 {
+        match &self.dependencies {
+            Some(dependencies) if !dependencies.all_ok() => 503,
+            _ => 200,
+        }
+    }
+}
+
+/// `"ok"` or `"unavailable"` per dependency
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DependencyStatus {
+    pub dynamodb: &'static str,
+    pub s3: &'static str,
+}
+
+impl DependencyStatus {
+    fn new(dynamodb_ok: bool, s3_ok: bool) -> Self {
+        let status = |ok| if ok { "ok" } else { "unavailable" };
+        Self {
+            dynamodb: status(dynamodb_ok),
+            s3: status(s3_ok),
+        }
+    }
+
+    fn all_ok(&self) -> bool {
+        self.dynamodb == "ok" && self.s3 == "ok"
+    }
+}
+
+/// Check the table and the storage bucket can be reached, concurrently
+async fn check_dependencies(state: &AppState) -> DependencyStatus {
+    let dynamodb = state
+        .dynamo
+        .describe_table()
+        .table_name(&state.config.table_name)
+        .send();
+    let s3 = state
+        .s3
+        .head_bucket()
+        .bucket(&state.config.storage_bucket)
+        .send();
+
+    let (dynamodb, s3) = tokio::join!(dynamodb, s3);
+    if let Err(e) = &dynamodb {
+        warn!(error = %e, "Health check could not reach DynamoDB");
+    }
+    if let Err(e) = &s3 {
+        warn!(error = %e, "Health check could not reach S3");
+    }
+    DependencyStatus::new(dynamodb.is_ok(), s3.is_ok())
+}
+
+/// Build metadata set by build.rs, or "unknown" for local builds
+fn build_value(value: Option<&str>) -> String {
+    value
+        .filter(|v| !v.is_empty())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Liveness by default; `?deep=true` also checks DynamoDB and S3 and
+/// returns 503 if either is down
+pub async fn handle(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+) -> ApiGatewayV2httpResponse {
+    let deep = match QueryParams::new(&request.query_string_parameters).get_bool("deep") {
+        Ok(deep) => deep.unwrap_or(false),
+        Err(e) => return e.into_response(),
+    };
+
+    let mut health = HealthResponse::current();
+    if deep {
+        health = health.with_dependencies(check_dependencies(state).await);
+    }
+    json_response(health.status_code(), &ApiResponse::success(health))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_response_serializes_all_fields() {
+        let json = serde_json::to_value(HealthResponse::current()).unwrap();
+
+        assert_eq!(json["status"], "healthy");
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        for field in ["git_sha", "build_time", "target"] {
+            assert!(json[field].as_str().is_some_and(|v| !v.is_empty()));
+        }
+        assert_ne!(json["target"], "unknown");
+    }
+
+    #[test]
+    fn test_missing_build_values_fall_back_to_unknown() {
+        assert_eq!(build_value(None), "unknown");
+        assert_eq!(build_value(Some("")), "unknown");
+        assert_eq!(build_value(Some("abc123")), "abc123");
+    }
+
+    #[test]
+    fn test_deep_health_reports_both_dependencies() {
+        let health = HealthResponse::current().with_dependencies(DependencyStatus::new(true, true));
+        assert_eq!(health.status_code(), 200);
+        let json = serde_json::to_value(&health).unwrap();
+        assert_eq!(json["status"], "healthy");
+        assert_eq!(json["dependencies"]["dynamodb"], "ok");
+        assert_eq!(json["dependencies"]["s3"], "ok");
+
+        for (dynamodb_ok, s3_ok) in [(true, false), (false, true), (false, false)] {
+            let health = HealthResponse::current()
+                .with_dependencies(DependencyStatus::new(dynamodb_ok, s3_ok));
+            assert_eq!(health.status_code(), 503);
+            let json = serde_json::to_value(&health).unwrap();
+            assert_eq!(json["status"], "unhealthy");
+            assert_eq!(
+                json["dependencies"]["s3"],
+                if s3_ok { "ok" } else { "unavailable" }
+            );
+            assert_eq!(
+                json["dependencies"]["dynamodb"],
+                if dynamodb_ok { "ok" } else { "unavailable" }
+            );
+        }
+    }
+
+    #[test]
+    fn test_shallow_health_omits_dependencies() {
+        let health = HealthResponse::current();
+        assert_eq!(health.status_code(), 200);
+        let json = serde_json::to_value(&health).unwrap();
+        assert!(json.get("dependencies").is_none());
+    }
 }
@@ -1,207 +1,2889 @@
-use crate::{json_response, ApiResponse, AppState};
+use crate::auth::AuthUser;
+use crate::cursor::{self, Positions};
+use crate::errors::dynamo_error_response;
+use crate::query::{QueryParamError, QueryParams};
+use crate::response::ResponseBuilder;
+use crate::validation::{collect_errors, validated, Validate};
+use crate::{
+    base_url, decoded_body, json_response, ApiResponse, AppState, CacheControl, FieldError,
+};
 use aws_lambda_events::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
+use aws_lambda_events::http::HeaderMap;
 use aws_sdk_dynamodb::types::AttributeValue;
-use chrono::Utc;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::future::try_join_all;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use shared::models::Item;
-use tracing::{error, info};
-use uuid::Uuid;
+use serde_json::{Map, Value};
+use shared::config::AppConfig;
+use shared::ids::IdScheme;
+use shared::models::{parse_timestamp, Item, SlugClaim, UNKNOWN_CREATOR};
+use shared::repository::{
+    DeleteCondition, DynamoEntity, MatchAny, QueryPage, RepositoryError, TableSettings,
+    MAX_BATCH_WRITE,
+};
+use std::collections::HashMap;
+use tracing::{error, info, warn};
 
 #[derive(Debug, Deserialize)]
 pub struct CreateItemRequest {
     pub name: String,
     #[serde(default)]
     pub description: Option<String>,
+    /// Epoch seconds at which the item should expire
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    /// Show the item to anonymous readers (see `PUBLIC_READS`)
+    #[serde(default)]
+    pub public: bool,
+}
+
+impl Validate for CreateItemRequest {
+    /// Normalize the name (trim and collapse internal whitespace), then validate
+    fn validate(&mut self, config: &AppConfig) -> Result<(), Vec<FieldError>> {
+        self.name = normalize_whitespace(&self.name);
+
+        collect_errors([
+            validate_name(&self.name, config),
+            validate_description(self.description.as_deref(), config),
+            validate_expires_at(self.expires_at, Utc::now().timestamp()),
+            self.slug.as_deref().and_then(validate_slug),
+            validate_metadata(&self.metadata),
+        ])
+    }
 }
 
 impl CreateItemRequest {
-    pub fn validate(&self) -> Result<(), &'static str> {
-        if self.name.is_empty() || self.name.len() > 256 {
-            return Err("Name must be 1-256 characters");
+    /// Build a new item owned by the given user, with an id from `id_scheme`
+    pub fn into_item(self, user: &AuthUser, id_scheme: IdScheme) -> Item {
+        let now = Utc::now().to_rfc3339();
+        Item {
+            id: id_scheme.generate(),
+            name: self.name,
+            description: self.description,
+            created_at: now.clone(),
+            updated_at: now,
+            created_by: user.id.clone(),
+            expires_at: self.expires_at,
+            slug: self.slug,
+            deleted_at: None,
+            metadata: self.metadata,
+            public: self.public,
         }
-        if let Some(desc) = &self.description {
-            if desc.len() > 4096 {
-                return Err("Description must be under 4096 characters");
-            }
+    }
+}
+
+/// Partial update; absent fields are left untouched
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateItemRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `None` leaves the description alone, `Some(None)` (JSON `null`) clears it
+    #[serde(default, deserialize_with = "double_option")]
+    pub description: Option<Option<String>>,
+    /// `None` leaves the slug alone, `Some(None)` (JSON `null`) removes it
+    #[serde(default, deserialize_with = "double_option")]
+    pub slug: Option<Option<String>>,
+    #[serde(default)]
+    pub public: Option<bool>,
+}
+
+impl Validate for UpdateItemRequest {
+    /// Normalize the name if present, then validate the present fields
+    fn validate(&mut self, config: &AppConfig) -> Result<(), Vec<FieldError>> {
+        if let Some(name) = &mut self.name {
+            *name = normalize_whitespace(name);
+        }
+
+        collect_errors([
+            self.name
+                .as_deref()
+                .and_then(|name| validate_name(name, config)),
+            self.description
+                .as_ref()
+                .and_then(|desc| validate_description(desc.as_deref(), config)),
+            self.slug
+                .clone()
+                .flatten()
+                .as_deref()
+                .and_then(validate_slug),
+        ])
+    }
+}
+
+impl UpdateItemRequest {
+    /// Apply the present fields to an item and bump `updated_at`
+    pub fn apply(self, item: &mut Item) {
+        if let Some(name) = self.name {
+            item.name = name;
+        }
+        if let Some(description) = self.description {
+            item.description = description;
+        }
+        if let Some(slug) = self.slug {
+            item.slug = slug;
+        }
+        if let Some(public) = self.public {
+            item.public = public;
+        }
+        item.updated_at = Utc::now().to_rfc3339();
+    }
+}
+
+fn validate_expires_at(expires_at: Option<u64>, now: i64) -> Option<FieldError> {
+    match expires_at {
+        Some(expires_at) if i64::try_from(expires_at).is_ok_and(|e| e <= now) => Some(
+            FieldError::new("expires_at", "Expiry must be in the future"),
+        ),
+        _ => None,
+    }
+}
+
+// Distinguish a missing field (None) from an explicit null (Some(None))
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Byte cap on a name whatever `name_max_len` is configured to, keeping
+/// items well inside DynamoDB's 400 KB item limit
+const NAME_MAX_BYTES: usize = 4 * 1024;
+
+/// The limit users see counts characters, so multi-byte text isn't penalized
+fn validate_name(name: &str, config: &AppConfig) -> Option<FieldError> {
+    if name.is_empty() || name.chars().count() > config.name_max_len {
+        return Some(FieldError::new(
+            "name",
+            format!("Name must be 1-{} characters", config.name_max_len),
+        ));
+    }
+    if name.len() > NAME_MAX_BYTES {
+        return Some(FieldError::new(
+            "name",
+            format!("Name must be at most {NAME_MAX_BYTES} bytes of UTF-8"),
+        ));
+    }
+    None
+}
+
+/// Unlike names, descriptions are limited in bytes (`description_max_len`)
+fn validate_description(description: Option<&str>, config: &AppConfig) -> Option<FieldError> {
+    match description {
+        Some(desc) if desc.len() > config.description_max_len => Some(FieldError::new(
+            "description",
+            format!(
+                "Description must be at most {} bytes of UTF-8",
+                config.description_max_len
+            ),
+        )),
+        _ => None,
+    }
+}
+
+const SLUG_MAX_LEN: usize = 64;
+
+/// Slugs appear in URLs: lowercase letters, digits and inner hyphens only
+fn validate_slug(slug: &str) -> Option<FieldError> {
+    let valid = !slug.is_empty()
+        && slug.len() <= SLUG_MAX_LEN
+        && slug
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-');
+    (!valid).then(|| {
+        FieldError::new(
+            "slug",
+            format!("Slug must be 1-{SLUG_MAX_LEN} lowercase letters, digits or inner hyphens"),
+        )
+    })
+}
+
+const METADATA_MAX_KEYS: usize = 20;
+
+/// Combined UTF-8 size of all metadata keys and values
+const METADATA_MAX_BYTES: usize = 2048;
+
+/// Metadata is meant for small tags, so cap how many keys and how much text
+fn validate_metadata(metadata: &HashMap<String, String>) -> Option<FieldError> {
+    let message = if metadata.len() > METADATA_MAX_KEYS {
+        format!("Metadata can have at most {METADATA_MAX_KEYS} keys")
+    } else if metadata.keys().any(|key| key.trim().is_empty()) {
+        "Metadata keys must not be empty".to_string()
+    } else if metadata
+        .iter()
+        .map(|(key, value)| key.len() + value.len())
+        .sum::<usize>()
+        > METADATA_MAX_BYTES
+    {
+        format!("Metadata must be at most {METADATA_MAX_BYTES} bytes in total")
+    } else {
+        return None;
+    };
+    Some(FieldError::new("metadata", message))
+}
+
+/// Trim and collapse runs of whitespace into a single space
+fn normalize_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchCreateRequest {
+    pub items: Vec<CreateItemRequest>,
+    /// Write the valid items and report the invalid ones, rather than
+    /// rejecting the whole batch when any item is invalid
+    #[serde(default)]
+    pub partial: bool,
+}
+
+/// Why one item of a partial batch was not written
+#[derive(Debug, Serialize)]
+pub struct BatchItemError {
+    /// Position of the item in the request
+    pub index: usize,
+    pub errors: Vec<FieldError>,
+}
+
+impl BatchCreateRequest {
+    /// Validate every item in place, returning the errors of the invalid ones
+    fn item_errors(&mut self, config: &AppConfig) -> Vec<BatchItemError> {
+        self.items
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let mut errors = item.validate(config).err().unwrap_or_default();
+                // Batch writes can't claim slugs, so uniqueness couldn't be enforced
+                if item.slug.is_some() {
+                    errors.push(FieldError::new(
+                        "slug",
+                        "Slugs can't be set in a batch create",
+                    ));
+                }
+                (!errors.is_empty()).then_some(BatchItemError { index, errors })
+            })
+            .collect()
+    }
+
+    /// Split a partial batch into the valid items, keyed by request index,
+    /// and the errors of the rest
+    fn partition(
+        mut self,
+        config: &AppConfig,
+    ) -> (Vec<(usize, CreateItemRequest)>, Vec<BatchItemError>) {
+        let rejected = self.item_errors(config);
+        let valid = self
+            .items
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !rejected.iter().any(|e| e.index == *index))
+            .collect();
+        (valid, rejected)
+    }
+}
+
+impl Validate for BatchCreateRequest {
+    /// Validate every item; errors are reported as `items[<index>].<field>`.
+    /// A partial batch only checks its size here and leaves the items to
+    /// `partition`.
+    fn validate(&mut self, config: &AppConfig) -> Result<(), Vec<FieldError>> {
+        if self.items.is_empty() || self.items.len() > MAX_BATCH_WRITE {
+            return Err(vec![FieldError::new(
+                "items",
+                "Between 1 and 25 items can be created at once",
+            )]);
+        }
+        if self.partial {
+            return Ok(());
+        }
+
+        let errors: Vec<FieldError> = self
+            .item_errors(config)
+            .into_iter()
+            .flat_map(|BatchItemError { index, errors }| {
+                errors
+                    .into_iter()
+                    .map(move |e| FieldError::new(format!("items[{index}].{}", e.field), e.message))
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
-        Ok(())
     }
 }
 
 #[derive(Debug, Serialize)]
-pub struct ListItemsResponse {
+pub struct BatchCreateResponse {
     pub items: Vec<Item>,
-    pub count: usize,
+    /// Request indexes DynamoDB did not write even after retries
+    pub unprocessed_indexes: Vec<usize>,
+    /// Invalid items a partial batch skipped; a non-empty list makes the
+    /// response a 207
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rejected: Vec<BatchItemError>,
 }
 
-pub async fn list(state: &AppState, request: &ApiGatewayV2httpRequest) -> ApiGatewayV2httpResponse {
-    let limit = request
-        .query_string_parameters
-        .first("limit")
-        .and_then(|l| l.parse::<i32>().ok())
-        .unwrap_or(50)
-        .clamp(1, 100);
+#[derive(Debug, Deserialize)]
+pub struct BulkDeleteRequest {
+    pub ids: Vec<String>,
+}
 
-    let result = state
-        .dynamo
-        .query()
-        .table_name(&state.config.table_name)
-        .key_condition_expression("pk = :pk")
-        .expression_attribute_values(":pk", AttributeValue::S("ITEM".to_string()))
-        .limit(limit)
-        .send()
-        .await;
+impl Validate for BulkDeleteRequest {
+    fn validate(&mut self, _config: &AppConfig) -> Result<(), Vec<FieldError>> {
+        let message = if self.ids.is_empty() {
+            "At least one id is required"
+        } else if self.ids.len() > MAX_BATCH_WRITE {
+            "At most 25 ids can be deleted at once"
+        } else if self.ids.iter().any(|id| id.is_empty()) {
+            "Ids must not be empty"
+        } else {
+            return Ok(());
+        };
+        Err(vec![FieldError::new("ids", message)])
+    }
+}
 
-    match result {
-        Ok(output) => {
-            let items: Vec<Item> = output
-                .items
-                .unwrap_or_default()
-                .into_iter()
-                .filter_map(|item| Item::from_dynamo(&item).ok())
-                .collect();
-            let count = items.len();
-            info!(count = count, "Listed items");
-            json_response(
-                200,
-                &ApiResponse::success(ListItemsResponse { items, count }),
-            )
+#[derive(Debug, Serialize)]
+pub struct BulkDeleteResponse {
+    /// Items that were removed; ids that didn't exist or belong to someone else aren't counted
+    pub deleted: usize,
+    /// Ids DynamoDB did not process; the caller should retry these
+    pub unprocessed_ids: Vec<String>,
+}
+
+/// `POST /items/search` body: the `GET /items` query params as JSON, for
+/// filters that don't fit comfortably in a query string
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchRequest {
+    #[serde(default)]
+    pub limit: Option<i32>,
+    /// `next_cursor` of the previous page, like `?cursor=`
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Only items updated after this RFC3339 timestamp, like `?modified_since=`
+    #[serde(default)]
+    pub modified_since: Option<String>,
+    #[serde(default)]
+    pub filters: SearchFilters,
+    /// Fields to return, as with `?fields=`; every field when empty
+    #[serde(default)]
+    pub fields: Vec<String>,
+    #[serde(default)]
+    pub sort: Option<SearchSort>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchFilters {
+    #[serde(default)]
+    pub created_by: Vec<String>,
+    #[serde(default)]
+    pub name_prefix: Option<String>,
+    #[serde(default)]
+    pub created_after: Option<String>,
+    #[serde(default)]
+    pub created_before: Option<String>,
+}
+
+/// Result order. Each is served by a different index, so it has to agree
+/// with the filters that pick that index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    /// Table order; an unfiltered search
+    Id,
+    /// Oldest update first (gsi3); what `modified_since` returns
+    UpdatedAt,
+    /// Oldest first (gsi1); what created ranges return
+    CreatedAt,
+    /// The caller's own items by name (gsi2); what `name_prefix` returns
+    Name,
+}
+
+impl SearchRequest {
+    /// Validate the search and translate it into the equivalent list query,
+    /// or report every invalid field
+    fn into_list_query(self, config: &AppConfig) -> Result<ListQuery, Vec<FieldError>> {
+        let SearchFilters {
+            created_by,
+            mut name_prefix,
+            created_after,
+            created_before,
+        } = self.filters;
+        let has_range = created_after.is_some() || created_before.is_some();
+
+        let limit = page_limit(self.limit, config).map_err(|m| FieldError::new("limit", m));
+        let cursor = self
+            .cursor
+            .as_deref()
+            .map(|cursor| {
+                cursor::decode(cursor).map_err(|e| FieldError::new("cursor", e.to_string()))
+            })
+            .transpose();
+        let modified_since = self
+            .modified_since
+            .as_deref()
+            .map(|since| {
+                parse_modified_since(since).map_err(|m| FieldError::new("modified_since", m))
+            })
+            .transpose();
+        let created =
+            created_range(created_after.as_deref(), created_before.as_deref()).map_err(|message| {
+                let field = if message.starts_with("created_before") {
+                    "filters.created_before"
+                } else {
+                    "filters.created_after"
+                };
+                FieldError::new(field, message)
+            });
+        let fields = if self.fields.is_empty() {
+            Ok(None)
+        } else {
+            projection_fields(&self.fields.join(","))
+                .map(Some)
+                .map_err(|m| FieldError::new("fields", m))
+        };
+
+        let conflict = match self.sort {
+            _ if name_prefix.is_some() && (self.modified_since.is_some() || has_range) => Some((
+                "filters.name_prefix",
+                "name_prefix cannot be combined with modified_since or a created range",
+            )),
+            _ if self.modified_since.is_some() && has_range => {
+                Some(("modified_since", "modified_since cannot be combined with a created range"))
+            }
+            Some(SearchSort::Id) if name_prefix.is_some() || self.modified_since.is_some() || has_range => {
+                Some((
+                    "sort",
+                    "sort by id cannot be combined with modified_since, name_prefix or a created range",
+                ))
+            }
+            Some(SearchSort::UpdatedAt) if name_prefix.is_some() || has_range => Some((
+                "sort",
+                "sort by updated_at cannot be combined with name_prefix or a created range",
+            )),
+            Some(SearchSort::CreatedAt) if name_prefix.is_some() || self.modified_since.is_some() => {
+                Some((
+                    "sort",
+                    "sort by created_at cannot be combined with modified_since or name_prefix",
+                ))
+            }
+            Some(SearchSort::Name) if self.modified_since.is_some() || has_range => Some((
+                "sort",
+                "sort by name cannot be combined with modified_since or a created range",
+            )),
+            _ => None,
+        };
+
+        match (limit, cursor, modified_since, created, fields, conflict) {
+            (Ok(limit), Ok(cursor), Ok(mut modified_since), Ok(mut created), Ok(fields), None) => {
+                match self.sort {
+                    Some(SearchSort::UpdatedAt) => {
+                        modified_since.get_or_insert_with(|| EARLIEST_TIMESTAMP.to_string());
+                    }
+                    Some(SearchSort::CreatedAt) => {
+                        created.get_or_insert_with(|| {
+                            (EARLIEST_TIMESTAMP.to_string(), LATEST_TIMESTAMP.to_string())
+                        });
+                    }
+                    Some(SearchSort::Name) => {
+                        name_prefix.get_or_insert_with(String::new);
+                    }
+                    _ => {}
+                }
+                Ok(ListQuery {
+                    limit,
+                    cursor,
+                    modified_since,
+                    fields,
+                    created_by,
+                    name_prefix,
+                    created,
+                })
+            }
+            (limit, cursor, modified_since, created, fields, conflict) => Err([
+                limit.err(),
+                cursor.err(),
+                modified_since.err(),
+                created.err(),
+                fields.err(),
+                conflict.map(|(field, message)| FieldError::new(field, message)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect()),
         }
-        Err(e) => {
-            error!(error = %e, "Failed to list items");
-            json_response(500, &ApiResponse::<()>::error("Failed to list items"))
+    }
+}
+
+/// An item in a list, with the ETag a `GET` of it would return so clients
+/// can cache it and revalidate with If-None-Match
+#[derive(Debug, Serialize)]
+pub struct ListedItem {
+    #[serde(flatten)]
+    pub item: Item,
+    pub etag: String,
+}
+
+impl From<Item> for ListedItem {
+    fn from(item: Item) -> Self {
+        Self {
+            etag: item_etag(&item),
+            item,
         }
     }
 }
 
-pub async fn create(
+#[derive(Debug, Serialize)]
+pub struct ListItemsResponse<T = ListedItem> {
+    pub items: Vec<T>,
+    /// Number of items in this page (not the total in the table)
+    pub page_count: usize,
+    /// Whether DynamoDB has more items beyond this page
+    pub has_more: bool,
+    /// Pass as `cursor` to fetch the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+    /// Number of items DynamoDB evaluated before filtering
+    pub scanned_count: usize,
+}
+
+impl<T> From<QueryPage<T>> for ListItemsResponse<T> {
+    fn from(page: QueryPage<T>) -> Self {
+        Self {
+            page_count: page.items.len(),
+            has_more: page.last_evaluated_key.is_some(),
+            next_cursor: page.last_evaluated_key.as_ref().map(cursor::encode_key),
+            scanned_count: page.scanned_count,
+            items: page.items,
+        }
+    }
+}
+
+impl<T> ListItemsResponse<T> {
+    /// Keep only the items `keep` accepts; the page stays where it was
+    fn retain(mut self, keep: impl FnMut(&T) -> bool) -> Self {
+        self.items.retain(keep);
+        self.page_count = self.items.len();
+        self
+    }
+
+    /// Convert each item, keeping the page details
+    fn map<U>(self, convert: impl FnMut(T) -> U) -> ListItemsResponse<U> {
+        ListItemsResponse {
+            items: self.items.into_iter().map(convert).collect(),
+            page_count: self.page_count,
+            has_more: self.has_more,
+            next_cursor: self.next_cursor,
+            scanned_count: self.scanned_count,
+        }
+    }
+}
+
+/// Who is reading items, which decides the ones they may see
+#[derive(Debug, Clone, Copy)]
+enum Viewer<'a> {
+    /// No token (see `PUBLIC_READS`): public items only
+    Anonymous,
+    /// The caller's own items plus everyone's public ones
+    User(&'a AuthUser),
+    /// `GET /admin/items`: every user's items
+    Admin(&'a AuthUser),
+}
+
+impl<'a> Viewer<'a> {
+    fn for_caller(user: Option<&'a AuthUser>) -> Self {
+        user.map_or(Viewer::Anonymous, Viewer::User)
+    }
+
+    fn user(self) -> Option<&'a AuthUser> {
+        match self {
+            Viewer::Anonymous => None,
+            Viewer::User(user) | Viewer::Admin(user) => Some(user),
+        }
+    }
+
+    /// gsi4 partitions holding what the viewer may list, each with the name
+    /// its position goes under in a cursor; `None` for admins, who list
+    /// every item
+    fn gsi4_partitions(self) -> Option<Vec<(&'static str, String)>> {
+        match self {
+            Viewer::Anonymous => Some(vec![("public", Item::public_gsi4pk())]),
+            Viewer::User(user) => Some(vec![
+                ("public", Item::public_gsi4pk()),
+                ("own", Item::private_gsi4pk_for(&user.id)),
+            ]),
+            Viewer::Admin(_) => None,
+        }
+    }
+
+    /// `can_see` as a query filter, for indexes holding everyone's items
+    fn filter(self) -> Option<MatchAny> {
+        let public = ("public".to_string(), AttributeValue::Bool(true));
+        match self {
+            Viewer::Anonymous => Some(MatchAny(vec![public])),
+            Viewer::User(user) => Some(MatchAny(vec![
+                public,
+                ("created_by".to_string(), AttributeValue::S(user.id.clone())),
+            ])),
+            Viewer::Admin(_) => None,
+        }
+    }
+
+    fn can_see(self, created_by: &str, public: bool) -> bool {
+        match self {
+            Viewer::Anonymous => public,
+            Viewer::User(user) => public || created_by == user.id,
+            Viewer::Admin(_) => true,
+        }
+    }
+}
+
+/// `GET /items`: the caller's own items and everyone's public ones;
+/// anonymous callers (see `PUBLIC_READS`) see only public items
+pub async fn list(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+    user: Option<AuthUser>,
+) -> ApiGatewayV2httpResponse {
+    let params = QueryParams::new(&request.query_string_parameters);
+    match list_query(&params, &state.config) {
+        Ok(query) => list_items(state, Viewer::for_caller(user.as_ref()), query).await,
+        Err(response) => response,
+    }
+}
+
+/// `GET /admin/items`: items from every user, each with its `created_by`.
+/// Takes the same query params as `GET /items`.
+pub async fn admin_list(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
+) -> ApiGatewayV2httpResponse {
+    let params = QueryParams::new(&request.query_string_parameters);
+    match list_query(&params, &state.config) {
+        Ok(query) => list_items(state, Viewer::Admin(&user), query).await,
+        Err(response) => response,
+    }
+}
+
+/// `POST /items/search`: the same listing as `GET /items`, described by a
+/// JSON body instead of query params
+pub async fn search(
     state: &AppState,
     request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
 ) -> ApiGatewayV2httpResponse {
-    let body = match &request.body {
-        Some(body) => body,
-        None => return json_response(400, &ApiResponse::<()>::error("Missing request body")),
+    let search: SearchRequest = match parse_body(request, &state.config) {
+        Ok(search) => search,
+        Err(response) => return response,
     };
+    match search.into_list_query(&state.config) {
+        Ok(query) => list_items(state, Viewer::User(&user), query).await,
+        Err(fields) => json_response(400, &ApiResponse::<()>::validation_error(fields)),
+    }
+}
 
-    let create_req: CreateItemRequest = match serde_json::from_str(body) {
-        Ok(req) => req,
-        Err(e) => {
-            return json_response(400, &ApiResponse::<()>::error(format!("Invalid JSON: {e}")))
-        }
+/// A parsed and normalized list request, from either `GET /items` query
+/// params or a `POST /items/search` body
+#[derive(Debug, Default, PartialEq)]
+struct ListQuery {
+    limit: i32,
+    /// Where the previous page stopped, from its `next_cursor`
+    cursor: Option<Positions>,
+    modified_since: Option<String>,
+    fields: Option<Vec<&'static str>>,
+    created_by: Vec<String>,
+    name_prefix: Option<String>,
+    created: Option<(String, String)>,
+}
+
+/// Read the `GET /items` query params
+#[allow(clippy::result_large_err)]
+fn list_query(
+    params: &QueryParams,
+    config: &AppConfig,
+) -> Result<ListQuery, ApiGatewayV2httpResponse> {
+    let bad_request = |message: &str| json_response(400, &ApiResponse::<()>::error(message));
+
+    let limit = params
+        .get_int::<i32>("limit")
+        .map_err(QueryParamError::into_response)?;
+    let limit = page_limit(limit, config).map_err(bad_request)?;
+    let cursor = params
+        .get_string("cursor")
+        .map_err(QueryParamError::into_response)?
+        .map(cursor::decode)
+        .transpose()
+        .map_err(|e| bad_request(&e.to_string()))?;
+    let modified_since = params
+        .get_string("modified_since")
+        .map_err(QueryParamError::into_response)?
+        .map(parse_modified_since)
+        .transpose()
+        .map_err(bad_request)?;
+    // API Gateway splits `?fields=a,b` into separate values, so rejoin them
+    let fields = match params
+        .get_all("fields")
+        .map_err(QueryParamError::into_response)?
+    {
+        values if values.is_empty() => None,
+        values => Some(projection_fields(&values.join(",")).map_err(|m| bad_request(&m))?),
     };
+    let created_by = params
+        .get_all("created_by")
+        .map_err(QueryParamError::into_response)?;
+    let name_prefix = params
+        .get_string("name_prefix")
+        .map_err(QueryParamError::into_response)?;
+    let created = created_range(
+        params
+            .get_string("created_after")
+            .map_err(QueryParamError::into_response)?,
+        params
+            .get_string("created_before")
+            .map_err(QueryParamError::into_response)?,
+    )
+    .map_err(bad_request)?;
 
-    if let Err(e) = create_req.validate() {
-        return json_response(400, &ApiResponse::<()>::error(e));
-    }
+    Ok(ListQuery {
+        limit,
+        cursor,
+        modified_since,
+        fields,
+        created_by,
+        name_prefix: name_prefix.map(str::to_string),
+        created,
+    })
+}
+
+/// Run a list query against the index that serves its filters, keeping the
+/// items `viewer` may see
+async fn list_items(
+    state: &AppState,
+    viewer: Viewer<'_>,
+    query: ListQuery,
+) -> ApiGatewayV2httpResponse {
+    let ListQuery {
+        limit,
+        cursor,
+        modified_since,
+        fields,
+        created_by,
+        name_prefix,
+        created,
+    } = query;
 
-    let id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
+    // Without a range or prefix, callers other than admins list through the
+    // visibility partitions instead of reading everyone's items
+    if name_prefix.is_none() && created.is_none() && modified_since.is_none() {
+        if let Some(partitions) = viewer.gsi4_partitions() {
+            return match list_visible(state, &partitions, limit, cursor).await {
+                Ok(page) => filtered_list_response(
+                    page,
+                    fields.as_deref(),
+                    &created_by,
+                    viewer,
+                    &state.config,
+                ),
+                Err(response) => response,
+            };
+        }
+    }
 
-    let item = Item {
-        id: id.clone(),
-        name: create_req.name,
-        description: create_req.description,
-        created_at: now.clone(),
-        updated_at: now,
+    let start_key = match cursor.map(cursor::start_key).transpose() {
+        Ok(start_key) => start_key,
+        Err(e) => return json_response(400, &ApiResponse::<()>::error(e.to_string())),
     };
+    // The indexes below hold everyone's items, so DynamoDB filters them
+    let filter = viewer.filter();
 
-    let result = state
-        .dynamo
-        .put_item()
-        .table_name(&state.config.table_name)
-        .item("pk", AttributeValue::S("ITEM".to_string()))
-        .item("sk", AttributeValue::S(format!("ITEM#{id}")))
-        .item("id", AttributeValue::S(item.id.clone()))
-        .item("name", AttributeValue::S(item.name.clone()))
-        .item(
-            "description",
-            item.description
-                .as_ref()
-                .map(|d| AttributeValue::S(d.clone()))
-                .unwrap_or(AttributeValue::Null(true)),
+    if let Some(prefix) = name_prefix {
+        // Name order is per owner, so there is no anonymous equivalent
+        let Some(user) = viewer.user() else {
+            return json_response(
+                401,
+                &ApiResponse::<()>::error("name_prefix requires authentication"),
+            );
+        };
+        if modified_since.is_some() || created.is_some() {
+            return json_response(
+                400,
+                &ApiResponse::<()>::error(
+                    "name_prefix cannot be combined with modified_since or a created range",
+                ),
+            );
+        }
+        return list_by_name_prefix(
+            state,
+            user,
+            &prefix,
+            limit,
+            start_key,
+            fields.as_deref(),
+            &created_by,
         )
-        .item("created_at", AttributeValue::S(item.created_at.clone()))
-        .item("updated_at", AttributeValue::S(item.updated_at.clone()))
-        .item("gsi1pk", AttributeValue::S("ITEM".to_string()))
-        .item("gsi1sk", AttributeValue::S(item.created_at.clone()))
-        .send()
         .await;
+    }
 
-    match result {
-        Ok(_) => {
-            info!(id = %id, "Created item");
-            json_response(201, &ApiResponse::success(item))
+    if let Some((after, before)) = created {
+        if modified_since.is_some() {
+            return json_response(
+                400,
+                &ApiResponse::<()>::error("modified_since cannot be combined with a created range"),
+            );
         }
-        Err(e) => {
-            error!(error = %e, "Failed to create item");
-            json_response(500, &ApiResponse::<()>::error("Failed to create item"))
+        let result = state
+            .items
+            .query_gsi1_created_between(
+                Item::ENTITY_TYPE,
+                &after,
+                &before,
+                limit,
+                start_key,
+                filter.as_ref(),
+            )
+            .await;
+        return match result {
+            Ok(page) => filtered_list_response(
+                page.into(),
+                fields.as_deref(),
+                &created_by,
+                viewer,
+                &state.config,
+            ),
+            Err(e) => dynamo_error_response(&e, "Failed to list items"),
+        };
+    }
+
+    if let Some(fields) = fields {
+        let ttl_attribute = state.config.ttl_attribute.as_str();
+        // Visibility, the created_by filter and skipping soft-deleted items
+        // need these whether or not they were asked for
+        let mut attributes: Vec<&str> = fields
+            .iter()
+            .map(|field| stored_attribute(field, ttl_attribute))
+            .collect();
+        for attribute in ["created_by", "public", "deleted_at"] {
+            if !attributes.contains(&attribute) {
+                attributes.push(attribute);
+            }
+        }
+        let result = match &modified_since {
+            Some(since) => {
+                state
+                    .items
+                    .query_gsi3_after_projected(
+                        Item::ENTITY_TYPE,
+                        since,
+                        &attributes,
+                        limit,
+                        start_key,
+                        filter.as_ref(),
+                    )
+                    .await
+            }
+            None => {
+                state
+                    .items
+                    .query_projected(Item::ENTITY_TYPE, &attributes, limit, start_key)
+                    .await
+            }
+        };
+        return match result {
+            Ok(page) => list_response(
+                ListItemsResponse::from(page)
+                    .retain(|attrs| {
+                        !attrs.contains_key("deleted_at")
+                            && created_by_any(&created_by, stored_creator(attrs))
+                            && viewer.can_see(stored_creator(attrs), stored_public(attrs))
+                    })
+                    .map(|attrs| projected_item(&attrs, &fields, ttl_attribute)),
+            ),
+            Err(e) => dynamo_error_response(&e, "Failed to list items"),
+        };
+    }
+
+    let result = match &modified_since {
+        Some(since) => {
+            state
+                .items
+                .query_gsi3_after(Item::ENTITY_TYPE, since, limit, start_key, filter.as_ref())
+                .await
         }
+        None => state.items.query(Item::ENTITY_TYPE, limit, start_key).await,
+    };
+
+    match result {
+        Ok(page) => filtered_list_response(page.into(), None, &created_by, viewer, &state.config),
+        Err(e) => dynamo_error_response(&e, "Failed to list items"),
     }
 }
 
-pub async fn get(state: &AppState, request: &ApiGatewayV2httpRequest) -> ApiGatewayV2httpResponse {
-    let path = request.raw_path.as_deref().unwrap_or("");
-    let id = path.trim_start_matches("/items/");
+/// Merge a page from each of the viewer's gsi4 partitions. They hold
+/// disjoint items in the same (table key) order, so the first `limit` of the
+/// merged items are the page, and the cursor records where each partition
+/// stopped. A partition whose items didn't all fit is read again from there.
+async fn list_visible(
+    state: &AppState,
+    partitions: &[(&'static str, String)],
+    limit: i32,
+    cursor: Option<Positions>,
+) -> Result<ListItemsResponse<Item>, ApiGatewayV2httpResponse> {
+    // Without a cursor, every partition starts from the top
+    let positions = cursor.unwrap_or_else(|| {
+        partitions
+            .iter()
+            .map(|(name, _)| (name.to_string(), None))
+            .collect()
+    });
+    if positions
+        .keys()
+        .any(|name| partitions.iter().all(|(partition, _)| partition != name))
+    {
+        return Err(json_response(
+            400,
+            &ApiResponse::<()>::error(cursor::InvalidCursor.to_string()),
+        ));
+    }
 
-    if id.is_empty() {
-        return json_response(400, &ApiResponse::<()>::error("Missing item ID"));
+    let fetches = partitions.iter().filter_map(|(name, gsi4pk)| {
+        let start_key = positions.get(*name)?.clone();
+        Some(async move {
+            let page = state.items.query_gsi4(gsi4pk, limit, start_key).await?;
+            Ok::<_, RepositoryError>((*name, page))
+        })
+    });
+    let pages = try_join_all(fetches)
+        .await
+        .map_err(|e| dynamo_error_response(&e, "Failed to list items"))?;
+
+    let scanned_count = pages.iter().map(|(_, page)| page.scanned_count).sum();
+    let mut merged = Vec::new();
+    let mut fetched = Vec::new();
+    for (name, page) in pages {
+        fetched.push((name, page.items.len(), page.last_evaluated_key));
+        merged.extend(page.items.into_iter().map(|item| (name, item)));
+    }
+    merged.sort_by_key(|(_, item)| item.sk());
+    merged.truncate(limit.max(0) as usize);
+
+    let settings = state.config.table_settings();
+    let mut next = Positions::new();
+    for (name, count, last_evaluated_key) in fetched {
+        let used: Vec<&Item> = merged
+            .iter()
+            .filter(|(from, _)| *from == name)
+            .map(|(_, item)| item)
+            .collect();
+        let position = match used.last() {
+            // Everything fetched made the page: carry on where DynamoDB stopped
+            _ if used.len() == count => last_evaluated_key.map(Some),
+            Some(item) => Some(Some(gsi4_key(item, &settings))),
+            None => positions.get(name).cloned(),
+        };
+        if let Some(position) = position {
+            next.insert(name.to_string(), position);
+        }
     }
 
+    let next_cursor = cursor::encode(&next);
+    Ok(ListItemsResponse {
+        page_count: merged.len(),
+        has_more: next_cursor.is_some(),
+        next_cursor,
+        scanned_count,
+        items: merged.into_iter().map(|(_, item)| item).collect(),
+    })
+}
+
+/// The key DynamoDB would return as `last_evaluated_key` had a gsi4 query
+/// stopped at `item`
+fn gsi4_key(item: &Item, settings: &TableSettings) -> HashMap<String, AttributeValue> {
+    let attrs = item.to_dynamo(settings);
+    ["pk", "sk", "gsi4pk", "gsi4sk"]
+        .into_iter()
+        .filter_map(|name| Some((name.to_string(), attrs.get(name)?.clone())))
+        .collect()
+}
+
+/// `?created_by=` filter: keeps items created by any of the listed users,
+/// or everything when none are listed. It applies to the page DynamoDB
+/// returned, so a filtered page can be short while `has_more` is still true.
+fn created_by_any(created_by: &[String], creator: &str) -> bool {
+    created_by.is_empty() || created_by.iter().any(|user| user == creator)
+}
+
+/// `public` of a projected item; absent means private, as in `Item::from_dynamo`
+fn stored_public(attrs: &HashMap<String, AttributeValue>) -> bool {
+    attrs
+        .get("public")
+        .and_then(|value| value.as_bool().ok())
+        .is_some_and(|public| *public)
+}
+
+/// `created_by` of a projected item, defaulting like `Item::from_dynamo`
+fn stored_creator(attrs: &HashMap<String, AttributeValue>) -> &str {
+    attrs
+        .get("created_by")
+        .and_then(|value| value.as_s().ok())
+        .map_or(UNKNOWN_CREATOR, String::as_str)
+}
+
+/// The caller's own items whose name starts with `prefix` (case-insensitive),
+/// in name order via gsi2. Any `fields` projection is applied to the fetched items.
+async fn list_by_name_prefix(
+    state: &AppState,
+    user: &AuthUser,
+    prefix: &str,
+    limit: i32,
+    start_key: Option<HashMap<String, AttributeValue>>,
+    fields: Option<&[&str]>,
+    created_by: &[String],
+) -> ApiGatewayV2httpResponse {
     let result = state
-        .dynamo
-        .get_item()
-        .table_name(&state.config.table_name)
-        .key("pk", AttributeValue::S("ITEM".to_string()))
-        .key("sk", AttributeValue::S(format!("ITEM#{id}")))
-        .send()
+        .items
+        .query_gsi2_prefix(
+            &Item::gsi2pk_for(&user.id),
+            &Item::gsi2sk_for(prefix),
+            limit,
+            start_key,
+        )
         .await;
 
     match result {
-        Ok(output) => match output.item {
-            Some(item) => match Item::from_dynamo(&item) {
-                Ok(item) => json_response(200, &ApiResponse::success(item)),
-                Err(e) => {
-                    error!(error = %e, "Failed to parse item");
-                    json_response(500, &ApiResponse::<()>::error("Failed to parse item"))
-                }
-            },
-            None => json_response(404, &ApiResponse::<()>::error("Item not found")),
-        },
-        Err(e) => {
-            error!(error = %e, "Failed to get item");
-            json_response(500, &ApiResponse::<()>::error("Failed to get item"))
-        }
+        Ok(page) => filtered_list_response(
+            page.into(),
+            fields,
+            created_by,
+            Viewer::User(user),
+            &state.config,
+        ),
+        Err(e) => dynamo_error_response(&e, "Failed to list items"),
     }
 }
 
-pub async fn delete(
-    state: &AppState,
-    request: &ApiGatewayV2httpRequest,
+/// Keep the live items `viewer` may see that pass the `created_by` filter,
+/// and apply the `fields` projection to them; without one, each item is
+/// listed with its ETag
+fn filtered_list_response(
+    page: ListItemsResponse<Item>,
+    fields: Option<&[&str]>,
+    created_by: &[String],
+    viewer: Viewer<'_>,
+    config: &AppConfig,
 ) -> ApiGatewayV2httpResponse {
-    let path = request.raw_path.as_deref().unwrap_or("");
-    let id = path.trim_start_matches("/items/");
+    let page = page.retain(|item| {
+        item.deleted_at.is_none()
+            && created_by_any(created_by, &item.created_by)
+            && viewer.can_see(&item.created_by, item.public)
+    });
 
-    if id.is_empty() {
-        return json_response(400, &ApiResponse::<()>::error("Missing item ID"));
+    match fields {
+        Some(fields) => {
+            let settings = config.table_settings();
+            list_response(page.map(|item| {
+                projected_item(&item.to_dynamo(&settings), fields, &config.ttl_attribute)
+            }))
+        }
+        None => list_response(page.map(ListedItem::from)),
     }
+}
 
-    let result = state
-        .dynamo
-        .delete_item()
-        .table_name(&state.config.table_name)
-        .key("pk", AttributeValue::S("ITEM".to_string()))
-        .key("sk", AttributeValue::S(format!("ITEM#{id}")))
-        .send()
-        .await;
+fn list_response<T: Serialize>(response: ListItemsResponse<T>) -> ApiGatewayV2httpResponse {
+    info!(
+        count = response.page_count,
+        has_more = response.has_more,
+        "Listed items"
+    );
+    json_response(200, &ApiResponse::success(response))
+}
 
-    match result {
-        Ok(_) => {
-            info!(id = %id, "Deleted item");
-            json_response(204, &ApiResponse::success(()))
+/// Item fields `?fields=` may select
+const PROJECTABLE_FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "description",
+    "created_at",
+    "updated_at",
+    "created_by",
+    "expires_at",
+    "public",
+];
+
+/// Attribute a projectable field is stored under: its own name, except
+/// expiry, which lives on the table's TTL attribute
+fn stored_attribute<'a>(field: &'a str, ttl_attribute: &'a str) -> &'a str {
+    match field {
+        "expires_at" => ttl_attribute,
+        field => field,
+    }
+}
+
+/// Parse a comma-separated `fields` list into field names.
+/// `id` is always included so clients can still address the items.
+fn projection_fields(value: &str) -> Result<Vec<&'static str>, String> {
+    let mut fields = vec![PROJECTABLE_FIELDS[0]];
+    for name in value.split(',').map(str::trim) {
+        let field = PROJECTABLE_FIELDS
+            .iter()
+            .find(|field| **field == name)
+            .ok_or_else(|| format!("Unknown field in fields: '{name}'"))?;
+        if !fields.contains(field) {
+            fields.push(*field);
         }
-        Err(e) => {
-            error!(error = %e, "Failed to delete item");
-            json_response(500, &ApiResponse::<()>::error("Failed to delete item"))
+    }
+    Ok(fields)
+}
+
+/// Render projected attributes under their API field names, the way the
+/// same fields of a full `Item` serialize. Selecting `updated_at` also
+/// brings the item's `etag`, which is derived from it.
+fn projected_item(
+    attrs: &HashMap<String, AttributeValue>,
+    fields: &[&str],
+    ttl_attribute: &str,
+) -> Map<String, Value> {
+    let mut item: Map<String, Value> = fields
+        .iter()
+        .filter_map(|field| {
+            let value = match attrs.get(stored_attribute(field, ttl_attribute)) {
+                Some(AttributeValue::S(value)) => Value::from(value.as_str()),
+                Some(AttributeValue::N(value)) => Value::from(value.parse::<u64>().ok()?),
+                Some(AttributeValue::Bool(value)) => Value::from(*value),
+                None if *field == "created_by" => Value::from(UNKNOWN_CREATOR),
+                None if *field == "public" => Value::from(false),
+                // Absent optional fields (stored as NULL) are omitted
+                _ => return None,
+            };
+            Some((field.to_string(), value))
+        })
+        .collect();
+    if let Some(updated_at) = item.get("updated_at").and_then(Value::as_str) {
+        let etag = etag_for(updated_at);
+        item.insert("etag".to_string(), Value::from(etag));
+    }
+    item
+}
+
+/// Normalize a `modified_since` RFC3339 timestamp to the UTC form stored in
+/// `gsi3sk` (`updated_at`), so DynamoDB's string `>` compares chronologically
+fn parse_modified_since(value: &str) -> Result<String, &'static str> {
+    parse_timestamp(value)
+        .map(|ts| ts.to_rfc3339())
+        .ok_or("modified_since must be an RFC3339 timestamp")
+}
+
+/// Earliest and latest timestamps a range can bound, standing in for
+/// whichever end the caller left open
+const EARLIEST_TIMESTAMP: &str = "0000-01-01T00:00:00+00:00";
+const LATEST_TIMESTAMP: &str = "9999-12-31T23:59:59.999999999+00:00";
+
+/// Normalize `created_after`/`created_before` to the UTC form stored in
+/// `created_at`, or `None` when neither is given. Both ends are inclusive.
+fn created_range(
+    after: Option<&str>,
+    before: Option<&str>,
+) -> Result<Option<(String, String)>, &'static str> {
+    if after.is_none() && before.is_none() {
+        return Ok(None);
+    }
+    let after = after
+        .map(|value| parse_timestamp(value).ok_or("created_after must be an RFC3339 timestamp"))
+        .transpose()?;
+    let before = before
+        .map(|value| parse_timestamp(value).ok_or("created_before must be an RFC3339 timestamp"))
+        .transpose()?;
+    if let (Some(after), Some(before)) = (after, before) {
+        if after > before {
+            return Err("created_after must not be later than created_before");
         }
     }
+    Ok(Some((
+        after.map_or_else(|| EARLIEST_TIMESTAMP.to_string(), |ts| ts.to_rfc3339()),
+        before.map_or_else(|| LATEST_TIMESTAMP.to_string(), |ts| ts.to_rfc3339()),
+    )))
+}
+
+/// Resolve the requested page size against the configured default and maximum
+fn page_limit(requested: Option<i32>, config: &AppConfig) -> Result<i32, &'static str> {
+    match requested {
+        Some(limit) if limit < 1 => Err("limit must be at least 1"),
+        Some(limit) => Ok(limit.min(config.max_page_size)),
+        None => Ok(config.default_page_size.min(config.max_page_size)),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationResponse {
+    pub valid: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CountItemsResponse {
+    pub count: usize,
+}
+
+/// Count the caller's items without fetching them
+pub async fn count(
+    state: &AppState,
+    _request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
+) -> ApiGatewayV2httpResponse {
+    match state.items.count_gsi2(&Item::gsi2pk_for(&user.id)).await {
+        Ok(count) => {
+            info!(count, user = %user.id, "Counted items");
+            json_response(200, &ApiResponse::success(CountItemsResponse { count }))
+        }
+        Err(e) => dynamo_error_response(&e, "Failed to count items"),
+    }
+}
+
+/// `GET /items/export`: the caller's items as NDJSON, one JSON object per
+/// line, so clients can process the export without parsing one huge array.
+/// Lambda buffers the response anyway, so at most `export_max_items` are
+/// returned; `X-Export-Truncated: true` marks an export that stopped at that limit.
+pub async fn export(
+    state: &AppState,
+    _request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
+) -> ApiGatewayV2httpResponse {
+    let mut page = match state
+        .items
+        .query_gsi2_all(&Item::gsi2pk_for(&user.id), state.config.export_max_items)
+        .await
+    {
+        Ok(page) => page,
+        Err(e) => return dynamo_error_response(&e, "Failed to export items"),
+    };
+    let truncated = page.last_evaluated_key.is_some();
+    page.items.retain(|item| item.deleted_at.is_none());
+    info!(count = page.items.len(), truncated, user = %user.id, "Exported items");
+
+    // Served as-is rather than inside the ApiResponse envelope, but with the usual headers
+    let mut response = ResponseBuilder::new(200).text("application/x-ndjson", ndjson(&page.items));
+    if truncated {
+        response = response.header("x-export-truncated", "true");
+    }
+    response.build()
+}
+
+/// One JSON document per line, each line newline-terminated
+fn ndjson<T: Serialize>(values: &[T]) -> String {
+    values
+        .iter()
+        .filter_map(|value| serde_json::to_string(value).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
+
+/// Deserialize the JSON request body, decoding it first when API Gateway
+/// delivered it base64-encoded
+#[allow(clippy::result_large_err)]
+fn parse_body<T: DeserializeOwned>(
+    request: &ApiGatewayV2httpRequest,
+    config: &AppConfig,
+) -> Result<T, ApiGatewayV2httpResponse> {
+    let body = match decoded_body(request) {
+        Ok(Some(body)) => body,
+        Ok(None) => {
+            return Err(json_response(
+                400,
+                &ApiResponse::<()>::error("Missing request body"),
+            ))
+        }
+        Err(e) => return Err(invalid_body(config, format!("Invalid base64: {e}"))),
+    };
+    serde_json::from_slice(&body).map_err(|e| invalid_body(config, format!("Invalid JSON: {e}")))
+}
+
+/// 400 for a body that couldn't be parsed. The parser's message describes
+/// the expected types and fields, so clients only see it with `debug_errors`.
+fn invalid_body(config: &AppConfig, detail: String) -> ApiGatewayV2httpResponse {
+    warn!(error = %detail, "Invalid request body");
+    let message = if config.debug_errors {
+        format!("Invalid request body: {detail}")
+    } else {
+        "Invalid request body".to_string()
+    };
+    json_response(400, &ApiResponse::<()>::error(message))
+}
+
+/// `parse_body`, then `Validate` the request; either failure is the 400 to return
+#[allow(clippy::result_large_err)]
+pub(crate) fn parse_valid_body<T: DeserializeOwned + Validate>(
+    request: &ApiGatewayV2httpRequest,
+    config: &AppConfig,
+) -> Result<T, ApiGatewayV2httpResponse> {
+    validated(parse_body(request, config)?, config)
+}
+
+pub async fn create(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
+) -> ApiGatewayV2httpResponse {
+    let validate_only =
+        match QueryParams::new(&request.query_string_parameters).get_bool("validate_only") {
+            Ok(validate_only) => validate_only.unwrap_or(false),
+            Err(e) => return e.into_response(),
+        };
+
+    let create_req: CreateItemRequest = match parse_valid_body(request, &state.config) {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+
+    // Dry run: report the validation outcome without writing
+    if validate_only {
+        return json_response(
+            200,
+            &ApiResponse::success(ValidationResponse { valid: true }),
+        );
+    }
+
+    let item = create_req.into_item(&user, state.config.id_scheme);
+
+    if let Some(slug) = &item.slug {
+        if let Err(response) = claim_slug(state, &item.created_by, slug, &item.id).await {
+            return response;
+        }
+    }
+
+    match state.items.put_new(&item).await {
+        Ok(()) => {
+            info!(id = %item.id, user = %user.id, "Created item");
+            created_response(&item, base_url(request).as_deref())
+        }
+        Err(e) => {
+            if let Some(slug) = &item.slug {
+                release_slug(state, &item.created_by, slug).await;
+            }
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Reserve `slug` among `owner`'s items for `item_id`, or 409 if another of
+/// their items uses it. A claim whose item was since deleted, expired or
+/// re-slugged is stale and gets taken over.
+#[allow(clippy::result_large_err)]
+async fn claim_slug(
+    state: &AppState,
+    owner: &str,
+    slug: &str,
+    item_id: &str,
+) -> Result<(), ApiGatewayV2httpResponse> {
+    let claim = SlugClaim::new(owner, slug, item_id);
+    let result = match state.slugs.put_new(&claim).await {
+        Err(RepositoryError::ConditionFailed) => take_over_stale_claim(state, &claim).await,
+        result => result,
+    };
+
+    match result {
+        Ok(()) => Ok(()),
+        Err(RepositoryError::ConditionFailed) => {
+            info!(owner = %owner, slug = %slug, "Slug already in use");
+            Err(json_response(
+                409,
+                &ApiResponse::<()>::error("Slug is already used by another item"),
+            ))
+        }
+        Err(e) => Err(dynamo_error_response(&e, "Failed to reserve slug")),
+    }
+}
+
+/// Replace an existing claim if its item no longer uses the slug. The write is
+/// conditioned on the claim still pointing at that item, so a concurrent
+/// takeover fails with `ConditionFailed`.
+async fn take_over_stale_claim(state: &AppState, claim: &SlugClaim) -> Result<(), RepositoryError> {
+    let Some(existing) = state.slugs.get_with_consistency(&claim.id, true).await? else {
+        // Released since our put; claim it normally
+        return state.slugs.put_new(claim).await;
+    };
+    if existing.item_id == claim.item_id {
+        return Ok(());
+    }
+
+    let in_use = state
+        .items
+        .get_with_consistency(&existing.item_id, true)
+        .await?
+        .is_some_and(|item| item.slug.as_deref() == Some(claim.slug.as_str()));
+    if in_use {
+        return Err(RepositoryError::ConditionFailed);
+    }
+    state
+        .slugs
+        .put_if_match(claim, "item_id", &existing.item_id)
+        .await
+}
+
+/// Best-effort removal of a claim; a leftover one is taken over as stale later
+async fn release_slug(state: &AppState, owner: &str, slug: &str) {
+    if let Err(e) = state.slugs.delete(&SlugClaim::id_for(owner, slug)).await {
+        warn!(error = %e, owner = %owner, slug = %slug, "Failed to release slug");
+    }
+}
+
+/// 201 with the item body and a Location header pointing at the new resource;
+/// absolute when the public base URL is known, otherwise a path
+fn created_response(item: &Item, base_url: Option<&str>) -> ApiGatewayV2httpResponse {
+    let location = format!("{}/items/{}", base_url.unwrap_or(""), item.id);
+    ResponseBuilder::new(201)
+        .json(&ApiResponse::success(item))
+        .header("location", location)
+        .build()
+}
+
+/// Map a failed create to a response; an id collision and an oversized item get
+/// create-specific messages, anything else the shared DynamoDB mapping
+fn create_error_response(e: &RepositoryError) -> ApiGatewayV2httpResponse {
+    match e {
+        RepositoryError::ConditionFailed => {
+            error!(error = %e, "Item id already exists");
+            json_response(409, &ApiResponse::<()>::error("Item already exists"))
+        }
+        RepositoryError::ItemTooLarge => {
+            error!(error = %e, "Item too large to store");
+            json_response(
+                413,
+                &ApiResponse::<()>::error(
+                    "Item is too large to store; shorten the name or description",
+                ),
+            )
+        }
+        _ => dynamo_error_response(e, "Failed to create item"),
+    }
+}
+
+/// `GET /items/{id}`; anonymous callers get 404 for anything not public
+pub async fn get(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+    user: Option<AuthUser>,
+) -> ApiGatewayV2httpResponse {
+    let path = request.raw_path.as_deref().unwrap_or("");
+    let id = path.trim_start_matches("/items/");
+
+    if id.is_empty() {
+        return json_response(400, &ApiResponse::<()>::error("Missing item ID"));
+    }
+
+    let consistent_read = wants_consistent_read(request);
+    let include_deleted =
+        match QueryParams::new(&request.query_string_parameters).get_bool("include_deleted") {
+            Ok(include_deleted) => include_deleted.unwrap_or(false),
+            Err(e) => return e.into_response(),
+        };
+
+    let viewer = Viewer::for_caller(user.as_ref());
+    match state.items.get_with_consistency(id, consistent_read).await {
+        // Someone else's private item is as good as absent
+        Ok(Some(item)) if !viewer.can_see(&item.created_by, item.public) => {
+            json_response(404, &ApiResponse::<()>::error("Item not found"))
+        }
+        Ok(Some(Item {
+            id,
+            deleted_at: Some(deleted_at),
+            ..
+        })) if !include_deleted => gone(id, deleted_at),
+        Ok(Some(item)) => conditional_response(
+            &item,
+            &request.headers,
+            CacheControl::for_item(&state.config),
+        ),
+        Ok(None) => json_response(404, &ApiResponse::<()>::error("Item not found")),
+        Err(e @ RepositoryError::Model(_)) => {
+            error!(error = %e, "Failed to parse item");
+            json_response(500, &ApiResponse::<()>::error("Failed to parse item"))
+        }
+        Err(e) => dynamo_error_response(&e, "Failed to get item"),
+    }
+}
+
+/// Body of a 410 for a soft-deleted item
+#[derive(Debug, Serialize)]
+pub struct DeletedItemResponse {
+    pub id: String,
+    pub deleted_at: String,
+}
+
+/// 410 for a soft-deleted item, saying when it was deleted
+fn gone(id: String, deleted_at: String) -> ApiGatewayV2httpResponse {
+    json_response(
+        410,
+        &ApiResponse::error_with(
+            "Item has been deleted",
+            DeletedItemResponse { id, deleted_at },
+        ),
+    )
+}
+
+/// `?consistent=true` requests a strongly consistent read (e.g. right after a create).
+/// This doubles the read capacity consumed, so it is opt-in.
+fn wants_consistent_read(request: &ApiGatewayV2httpRequest) -> bool {
+    request
+        .query_string_parameters
+        .first("consistent")
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+}
+
+/// Weak ETag for an item, derived from its last modification time
+pub fn item_etag(item: &Item) -> String {
+    etag_for(&item.updated_at)
+}
+
+fn etag_for(updated_at: &str) -> String {
+    format!("W/\"{updated_at}\"")
+}
+
+/// Check an If-None-Match header value against an ETag (weak comparison)
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    if_none_match
+        .split(',')
+        .map(|candidate| candidate.trim())
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+/// Build the GET response for an item, honoring If-None-Match with a 304
+fn conditional_response(
+    item: &Item,
+    headers: &HeaderMap,
+    cache: CacheControl,
+) -> ApiGatewayV2httpResponse {
+    let etag = item_etag(item);
+
+    let not_modified = headers
+        .get("if-none-match")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| etag_matches(h, &etag));
+
+    let response = if not_modified {
+        ResponseBuilder::new(304)
+    } else {
+        ResponseBuilder::new(200).json(&ApiResponse::success(item))
+    };
+    response.cache(cache).header("etag", etag).build()
+}
+
+/// `PATCH /items/{id}`: the caller's own items only; anyone else's is a 404
+pub async fn update(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
+) -> ApiGatewayV2httpResponse {
+    let path = request.raw_path.as_deref().unwrap_or("");
+    let id = path.trim_start_matches("/items/");
+
+    if id.is_empty() {
+        return json_response(400, &ApiResponse::<()>::error("Missing item ID"));
+    }
+
+    let update_req: UpdateItemRequest = match parse_valid_body(request, &state.config) {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+
+    // Someone else's item is as good as missing to the caller
+    let existing = match state.items.get(id).await {
+        Ok(existing) => existing.filter(|item| item.created_by == user.id),
+        Err(e) => return dynamo_error_response(&e, "Failed to update item"),
+    };
+    if let Some(Item {
+        id,
+        deleted_at: Some(deleted_at),
+        ..
+    }) = &existing
+    {
+        return gone(id.clone(), deleted_at.clone());
+    }
+
+    let expected_version = match check_if_match(existing.as_ref(), &request.headers) {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+
+    let previous_slug = existing.as_ref().and_then(|item| item.slug.clone());
+    let item = match patched_item(existing, update_req) {
+        Ok(item) => item,
+        Err(response) => return response,
+    };
+    let new_slug = item
+        .slug
+        .as_deref()
+        .filter(|slug| previous_slug.as_deref() != Some(*slug));
+
+    if let Some(slug) = new_slug {
+        if let Err(response) = claim_slug(state, &item.created_by, slug, &item.id).await {
+            return response;
+        }
+    }
+
+    // The condition catches writes that land between our read and this put
+    let result = match &expected_version {
+        Some(version) => state.items.put_if_match(&item, "updated_at", version).await,
+        None => state.items.put(&item).await,
+    };
+
+    match result {
+        Ok(()) => {
+            info!(id = %item.id, user = %user.id, "Updated item");
+            if let Some(previous) = previous_slug.filter(|slug| item.slug.as_ref() != Some(slug)) {
+                release_slug(state, &item.created_by, &previous).await;
+            }
+            json_response(200, &ApiResponse::success(item))
+        }
+        Err(e) => {
+            if let Some(slug) = new_slug {
+                release_slug(state, &item.created_by, slug).await;
+            }
+            update_error_response(&e)
+        }
+    }
+}
+
+/// The `updated_at` an If-Match header requires, or None when absent or `*`
+fn if_match_version(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get("if-match")?.to_str().ok()?.trim();
+    if value == "*" {
+        return None;
+    }
+    let version = value.trim_start_matches("W/").trim_matches('"');
+    Some(version.to_string())
+}
+
+/// Compare If-Match (weakly, since our ETags are weak) against the stored item.
+/// Returns the version to condition the write on, or 412 if it is already stale.
+#[allow(clippy::result_large_err)]
+fn check_if_match(
+    existing: Option<&Item>,
+    headers: &HeaderMap,
+) -> Result<Option<String>, ApiGatewayV2httpResponse> {
+    let Some(version) = if_match_version(headers) else {
+        return Ok(None);
+    };
+    match existing {
+        Some(item) if item.updated_at != version => Err(precondition_failed()),
+        _ => Ok(Some(version)),
+    }
+}
+
+fn precondition_failed() -> ApiGatewayV2httpResponse {
+    json_response(
+        412,
+        &ApiResponse::<()>::error("Item was modified; fetch it again and retry"),
+    )
+}
+
+/// Map a failed update write; a lost If-Match race is a 412, not a 500
+fn update_error_response(e: &RepositoryError) -> ApiGatewayV2httpResponse {
+    match e {
+        RepositoryError::ConditionFailed => {
+            info!(error = %e, "Update precondition failed");
+            precondition_failed()
+        }
+        _ => dynamo_error_response(e, "Failed to update item"),
+    }
+}
+
+/// Apply a patch to the stored item, or 404 if it doesn't exist
+#[allow(clippy::result_large_err)]
+fn patched_item(
+    existing: Option<Item>,
+    update: UpdateItemRequest,
+) -> Result<Item, ApiGatewayV2httpResponse> {
+    let mut item =
+        existing.ok_or_else(|| json_response(404, &ApiResponse::<()>::error("Item not found")))?;
+    update.apply(&mut item);
+    Ok(item)
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteItemResponse {
+    pub id: String,
+}
+
+/// `DELETE /items/{id}`: the caller's own items only; anyone else's is a 404
+pub async fn delete(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
+) -> ApiGatewayV2httpResponse {
+    let path = request.raw_path.as_deref().unwrap_or("");
+    let id = path.trim_start_matches("/items/");
+
+    if id.is_empty() {
+        return json_response(400, &ApiResponse::<()>::error("Missing item ID"));
+    }
+
+    // Ownership is part of the condition so it holds at the moment of the delete
+    let before = if_unmodified_since(&request.headers);
+    let condition = DeleteCondition {
+        equals: Some(("created_by", &user.id)),
+        before: before.as_deref().map(|before| ("updated_at", before)),
+    };
+    match state.items.delete_if(id, condition).await {
+        Ok(Some(item)) => {
+            info!(id = %id, "Deleted item");
+            if let Some(slug) = &item.slug {
+                release_slug(state, &item.created_by, slug).await;
+            }
+            json_response(
+                200,
+                &ApiResponse::success(DeleteItemResponse { id: item.id }),
+            )
+        }
+        Ok(None) => json_response(404, &ApiResponse::<()>::error("Item not found")),
+        Err(RepositoryError::ConditionFailed) => delete_condition_failed(state, id, &user).await,
+        Err(e) => dynamo_error_response(&e, "Failed to delete item"),
+    }
+}
+
+/// Tell apart the two ways a delete condition fails: someone else's item is
+/// a 404, the caller's own item modified since If-Unmodified-Since is a 412
+async fn delete_condition_failed(
+    state: &AppState,
+    id: &str,
+    user: &AuthUser,
+) -> ApiGatewayV2httpResponse {
+    match state.items.get(id).await {
+        Ok(Some(item)) if item.created_by == user.id => {
+            info!(id = %id, "Delete precondition failed");
+            precondition_failed()
+        }
+        Ok(_) => json_response(404, &ApiResponse::<()>::error("Item not found")),
+        Err(e) => dynamo_error_response(&e, "Failed to delete item"),
+    }
+}
+
+/// The `updated_at` an item must be earlier than to satisfy an
+/// If-Unmodified-Since header. HTTP dates have whole-second precision, so a
+/// write anywhere within the named second still counts as unmodified. An
+/// unparseable date is ignored, as RFC 9110 requires.
+fn if_unmodified_since(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get("if-unmodified-since")?.to_str().ok()?;
+    let since = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some((since.with_timezone(&Utc) + TimeDelta::seconds(1)).to_rfc3339())
+}
+
+pub async fn batch_create(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
+) -> ApiGatewayV2httpResponse {
+    let batch_req: BatchCreateRequest = match parse_valid_body(request, &state.config) {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+
+    let (valid, rejected) = if batch_req.partial {
+        batch_req.partition(&state.config)
+    } else {
+        (
+            batch_req.items.into_iter().enumerate().collect(),
+            Vec::new(),
+        )
+    };
+    let (indexes, items): (Vec<usize>, Vec<Item>) = valid
+        .into_iter()
+        .map(|(index, req)| (index, req.into_item(&user, state.config.id_scheme)))
+        .unzip();
+
+    // BatchWriteItem rejects an empty request, which a partial batch of
+    // only invalid items would send
+    let unprocessed_ids = if items.is_empty() {
+        Ok(Vec::new())
+    } else {
+        state.items.batch_put(&items).await
+    };
+    match unprocessed_ids {
+        Ok(unprocessed_ids) => {
+            let (unprocessed_indexes, items) =
+                split_unprocessed(indexes.into_iter().zip(items), &unprocessed_ids);
+            info!(
+                user = %user.id,
+                created = items.len(),
+                unprocessed = unprocessed_indexes.len(),
+                rejected = rejected.len(),
+                "Batch created items"
+            );
+            json_response(
+                if rejected.is_empty() { 201 } else { 207 },
+                &ApiResponse::success(BatchCreateResponse {
+                    items,
+                    unprocessed_indexes,
+                    rejected,
+                }),
+            )
+        }
+        Err(e) => dynamo_error_response(&e, "Failed to create items"),
+    }
+}
+
+/// Separate written items from the request indexes of those left unprocessed
+fn split_unprocessed(
+    items: impl IntoIterator<Item = (usize, Item)>,
+    unprocessed_ids: &[String],
+) -> (Vec<usize>, Vec<Item>) {
+    let mut unprocessed_indexes = Vec::new();
+    let mut written = Vec::new();
+    for (index, item) in items {
+        if unprocessed_ids.contains(&item.id) {
+            unprocessed_indexes.push(index);
+        } else {
+            written.push(item);
+        }
+    }
+    (unprocessed_indexes, written)
+}
+
+pub async fn bulk_delete(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
+) -> ApiGatewayV2httpResponse {
+    let mut delete_req: BulkDeleteRequest = match parse_valid_body(request, &state.config) {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+
+    // BatchWriteItem rejects duplicate keys within one request
+    delete_req.ids.sort();
+    delete_req.ids.dedup();
+
+    // BatchWriteItem deletes can't be conditional, so load the items first
+    // and only delete the caller's own; missing ids are not counted as deleted
+    let owned = match owned_ids(state, &user, &delete_req.ids).await {
+        Ok(owned) => owned,
+        Err(e) => return dynamo_error_response(&e, "Failed to delete items"),
+    };
+    if owned.is_empty() {
+        return json_response(
+            200,
+            &ApiResponse::success(BulkDeleteResponse {
+                deleted: 0,
+                unprocessed_ids: Vec::new(),
+            }),
+        );
+    }
+
+    match state.items.batch_delete(&owned).await {
+        Ok(unprocessed_ids) => {
+            let deleted = owned.len() - unprocessed_ids.len();
+            info!(
+                user = %user.id,
+                requested = delete_req.ids.len(),
+                deleted = deleted,
+                unprocessed = unprocessed_ids.len(),
+                "Bulk deleted items"
+            );
+            json_response(
+                200,
+                &ApiResponse::success(BulkDeleteResponse {
+                    deleted,
+                    unprocessed_ids,
+                }),
+            )
+        }
+        Err(e) => dynamo_error_response(&e, "Failed to delete items"),
+    }
+}
+
+/// Those of `ids` that exist and were created by `user`
+async fn owned_ids(
+    state: &AppState,
+    user: &AuthUser,
+    ids: &[String],
+) -> Result<Vec<String>, RepositoryError> {
+    let items = try_join_all(ids.iter().map(|id| state.items.get(id))).await?;
+    Ok(items
+        .into_iter()
+        .flatten()
+        .filter(|item| item.created_by == user.id)
+        .map(|item| item.id)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_lambda_events::encodings::Body;
+    use base64::prelude::{Engine, BASE64_STANDARD};
+    use shared::repository::TableSettings;
+    use std::collections::HashMap;
+
+    /// The built-in defaults, so `NAME_MAX_LEN` and friends set in the
+    /// environment don't change what these tests accept
+    fn config() -> AppConfig {
+        AppConfig::default()
+    }
+
+    fn sample_item() -> Item {
+        Item {
+            id: "abc".to_string(),
+            name: "Sample".to_string(),
+            description: None,
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-02T00:00:00+00:00".to_string(),
+            created_by: "user-1".to_string(),
+            expires_at: None,
+            slug: None,
+            deleted_at: None,
+            metadata: HashMap::new(),
+            public: false,
+        }
+    }
+
+    fn headers_with_if_none_match(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("if-none-match", value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_matching_if_none_match_returns_304() {
+        let item = sample_item();
+        let headers = headers_with_if_none_match(&item_etag(&item));
+
+        let response = conditional_response(&item, &headers, CacheControl::NoStore);
+
+        assert_eq!(response.status_code, 304);
+        assert!(response.body.is_none());
+        assert_eq!(response.headers.get("etag").unwrap(), &item_etag(&item));
+    }
+
+    #[test]
+    fn test_non_matching_if_none_match_returns_200() {
+        let item = sample_item();
+        let headers = headers_with_if_none_match("W/\"2023-12-31T00:00:00+00:00\"");
+
+        let response = conditional_response(&item, &headers, CacheControl::NoStore);
+
+        assert_eq!(response.status_code, 200);
+        assert!(response.body.is_some());
+        assert_eq!(response.headers.get("etag").unwrap(), &item_etag(&item));
+    }
+
+    #[test]
+    fn test_list_response_has_more_when_cursor_returned() {
+        let page = QueryPage {
+            items: vec![sample_item(), sample_item()],
+            last_evaluated_key: Some(Item::key("abc")),
+            scanned_count: 2,
+        };
+
+        let response = ListItemsResponse::from(page);
+
+        assert!(response.has_more);
+        assert_eq!(response.page_count, 2);
+        assert_eq!(response.scanned_count, 2);
+        let cursor = response.next_cursor.unwrap();
+        assert_eq!(
+            cursor::decode(&cursor).and_then(cursor::start_key),
+            Ok(Item::key("abc"))
+        );
+    }
+
+    #[test]
+    fn test_list_response_no_more_without_cursor() {
+        let page = QueryPage {
+            items: vec![sample_item()],
+            last_evaluated_key: None,
+            scanned_count: 1,
+        };
+
+        let response = ListItemsResponse::from(page);
+
+        assert!(!response.has_more);
+        assert_eq!(response.page_count, 1);
+        assert_eq!(response.next_cursor, None);
+    }
+
+    #[test]
+    fn test_bulk_delete_rejects_more_than_25_ids() {
+        let mut request = BulkDeleteRequest {
+            ids: (0..26).map(|i| i.to_string()).collect(),
+        };
+        assert!(request.validate(&config()).is_err());
+
+        let mut request = BulkDeleteRequest {
+            ids: (0..25).map(|i| i.to_string()).collect(),
+        };
+        assert!(request.validate(&config()).is_ok());
+    }
+
+    fn create_request(name: &str) -> CreateItemRequest {
+        CreateItemRequest {
+            name: name.to_string(),
+            description: None,
+            expires_at: None,
+            slug: None,
+            metadata: HashMap::new(),
+            public: false,
+        }
+    }
+
+    #[test]
+    fn test_into_item_stamps_creator() {
+        let user = AuthUser {
+            id: "user-42".to_string(),
+            email: None,
+            name: None,
+            groups: Vec::new(),
+            scopes: Vec::new(),
+            claims: Map::new(),
+        };
+
+        let item = create_request("Owned").into_item(&user, IdScheme::Uuid);
+
+        assert_eq!(item.created_by, "user-42");
+        assert_eq!(item.name, "Owned");
+        assert_eq!(item.created_at, item.updated_at);
+    }
+
+    #[test]
+    fn test_whitespace_only_name_rejected() {
+        assert!(create_request("   \t\n ").validate(&config()).is_err());
+    }
+
+    #[test]
+    fn test_padded_name_trimmed_and_collapsed() {
+        let mut request = create_request("  My    padded \t item  ");
+        request.validate(&config()).unwrap();
+        assert_eq!(request.name, "My padded item");
+    }
+
+    #[test]
+    fn test_name_at_limit() {
+        let mut request = create_request(&format!("  {}  ", "a".repeat(256)));
+        assert!(request.validate(&config()).is_ok());
+        assert_eq!(request.name.len(), 256);
+
+        assert!(create_request(&"a".repeat(257))
+            .validate(&config())
+            .is_err());
+    }
+
+    #[test]
+    fn test_metadata_limits() {
+        let mut request = create_request("Tagged");
+        request.metadata = (0..METADATA_MAX_KEYS)
+            .map(|i| (format!("key-{i}"), "value".to_string()))
+            .collect();
+        assert!(request.validate(&config()).is_ok());
+
+        request
+            .metadata
+            .insert("one-too-many".to_string(), "value".to_string());
+        let errors = request.validate(&config()).unwrap_err();
+        assert_eq!(errors[0].field, "metadata");
+        assert_eq!(errors[0].message, "Metadata can have at most 20 keys");
+
+        let mut request = create_request("Tagged");
+        request.metadata = HashMap::from([("notes".to_string(), "x".repeat(METADATA_MAX_BYTES))]);
+        let errors = request.validate(&config()).unwrap_err();
+        assert_eq!(errors[0].field, "metadata");
+
+        let mut request = create_request("Tagged");
+        request.metadata = HashMap::from([(" ".to_string(), "value".to_string())]);
+        assert!(request.validate(&config()).is_err());
+    }
+
+    #[test]
+    fn test_create_request_metadata_reaches_item() {
+        let request: CreateItemRequest =
+            serde_json::from_str(r#"{"name":"Tagged","metadata":{"color":"blue"}}"#).unwrap();
+        let user = AuthUser {
+            id: "user-1".to_string(),
+            email: None,
+            name: None,
+            groups: Vec::new(),
+            scopes: Vec::new(),
+            claims: Map::new(),
+        };
+
+        let item = request.into_item(&user, IdScheme::Uuid);
+
+        assert_eq!(item.metadata["color"], "blue");
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["metadata"]["color"], "blue");
+    }
+
+    #[test]
+    fn test_validate_reports_all_field_errors() {
+        let mut request = CreateItemRequest {
+            name: "a".repeat(257),
+            description: Some("d".repeat(4097)),
+            expires_at: None,
+            slug: None,
+            metadata: HashMap::new(),
+            public: false,
+        };
+
+        let errors = request.validate(&config()).unwrap_err();
+
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["name", "description"]);
+    }
+
+    fn parse_update(body: &str) -> UpdateItemRequest {
+        let mut update: UpdateItemRequest = serde_json::from_str(body).unwrap();
+        update.validate(&config()).unwrap();
+        update
+    }
+
+    #[test]
+    fn test_patch_rename_keeps_description() {
+        let original = Item {
+            description: Some("Keep me".to_string()),
+            ..sample_item()
+        };
+
+        let item = patched_item(
+            Some(original.clone()),
+            parse_update(r#"{"name": "Renamed"}"#),
+        )
+        .unwrap();
+
+        assert_eq!(item.name, "Renamed");
+        assert_eq!(item.description.as_deref(), Some("Keep me"));
+        assert_ne!(item.updated_at, original.updated_at);
+        assert_eq!(item.created_at, original.created_at);
+    }
+
+    #[test]
+    fn test_patch_null_clears_description() {
+        let original = Item {
+            description: Some("Remove me".to_string()),
+            ..sample_item()
+        };
+
+        let item = patched_item(Some(original), parse_update(r#"{"description": null}"#)).unwrap();
+
+        assert_eq!(item.name, "Sample");
+        assert_eq!(item.description, None);
+    }
+
+    #[test]
+    fn test_patch_missing_item_returns_404() {
+        let response = patched_item(None, parse_update(r#"{"name": "Renamed"}"#)).unwrap_err();
+        assert_eq!(response.status_code, 404);
+    }
+
+    #[test]
+    fn test_consistent_query_param() {
+        let mut request = ApiGatewayV2httpRequest::default();
+        assert!(!wants_consistent_read(&request));
+
+        request.query_string_parameters =
+            HashMap::from([("consistent".to_string(), "true".to_string())]).into();
+        assert!(wants_consistent_read(&request));
+
+        request.query_string_parameters =
+            HashMap::from([("consistent".to_string(), "no".to_string())]).into();
+        assert!(!wants_consistent_read(&request));
+    }
+
+    #[test]
+    fn test_batch_create_size_limit() {
+        let mut request = BatchCreateRequest {
+            items: (0..26)
+                .map(|i| create_request(&format!("Item {i}")))
+                .collect(),
+            partial: false,
+        };
+        let errors = request.validate(&config()).unwrap_err();
+        assert_eq!(errors[0].field, "items");
+
+        let mut request = BatchCreateRequest {
+            items: vec![],
+            partial: false,
+        };
+        assert!(request.validate(&config()).is_err());
+
+        let mut request = BatchCreateRequest {
+            items: (0..25)
+                .map(|i| create_request(&format!("Item {i}")))
+                .collect(),
+            partial: false,
+        };
+        assert!(request.validate(&config()).is_ok());
+    }
+
+    #[test]
+    fn test_batch_create_reports_invalid_index() {
+        let mut request = BatchCreateRequest {
+            items: vec![
+                create_request("Valid"),
+                create_request("   "),
+                create_request("Also valid"),
+            ],
+            partial: false,
+        };
+
+        let errors = request.validate(&config()).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "items[1].name");
+    }
+
+    #[test]
+    fn test_partial_batch_partitions_invalid_items() {
+        let mut slugged = create_request("Slugged");
+        slugged.slug = Some("slugged".to_string());
+        let mut request = BatchCreateRequest {
+            items: vec![
+                create_request("  Valid  "),
+                create_request("   "),
+                slugged,
+                create_request("Also valid"),
+            ],
+            partial: true,
+        };
+
+        // Item errors don't fail a partial batch as a whole
+        assert!(request.validate(&config()).is_ok());
+        let (valid, rejected) = request.partition(&config());
+
+        assert_eq!(
+            valid
+                .iter()
+                .map(|(index, req)| (*index, req.name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(0, "Valid"), (3, "Also valid")]
+        );
+        assert_eq!(
+            rejected
+                .iter()
+                .map(|e| (e.index, e.errors[0].field.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(1, "name"), (2, "slug")]
+        );
+
+        // The size limit still applies
+        let mut request = BatchCreateRequest {
+            items: vec![],
+            partial: true,
+        };
+        assert!(request.validate(&config()).is_err());
+    }
+
+    #[test]
+    fn test_split_unprocessed_items() {
+        let items = vec![
+            Item {
+                id: "a".to_string(),
+                ..sample_item()
+            },
+            Item {
+                id: "b".to_string(),
+                ..sample_item()
+            },
+        ];
+
+        let (unprocessed, written) =
+            split_unprocessed(items.into_iter().enumerate(), &["b".to_string()]);
+
+        assert_eq!(unprocessed, vec![1]);
+        assert_eq!(written.len(), 1);
+        assert_eq!(written[0].id, "a");
+    }
+
+    #[test]
+    fn test_create_conflict_maps_to_409() {
+        assert_eq!(
+            create_error_response(&RepositoryError::ConditionFailed).status_code,
+            409
+        );
+
+        let other = RepositoryError::Dynamo {
+            code: Some("InternalServerError".to_string()),
+            message: "boom".to_string(),
+        };
+        assert_eq!(create_error_response(&other).status_code, 500);
+    }
+
+    #[test]
+    fn test_validation_uses_configured_limits() {
+        let config = AppConfig {
+            name_max_len: 5,
+            description_max_len: 10,
+            ..AppConfig::default()
+        };
+
+        let mut request = create_request("Short");
+        assert!(request.validate(&config).is_ok());
+
+        let mut request = CreateItemRequest {
+            name: "Too long".to_string(),
+            description: Some("d".repeat(11)),
+            expires_at: None,
+            slug: None,
+            metadata: HashMap::new(),
+            public: false,
+        };
+        let errors = request.validate(&config).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].message, "Name must be 1-5 characters");
+
+        let mut request = CreateItemRequest {
+            name: "a".repeat(300),
+            description: Some("d".repeat(5000)),
+            expires_at: None,
+            slug: None,
+            metadata: HashMap::new(),
+            public: false,
+        };
+        let config = AppConfig {
+            name_max_len: 512,
+            description_max_len: 8192,
+            ..AppConfig::default()
+        };
+        assert!(request.validate(&config).is_ok());
+    }
+
+    #[test]
+    fn test_name_limit_counts_characters_not_bytes() {
+        let config = AppConfig {
+            name_max_len: 5,
+            ..AppConfig::default()
+        };
+
+        // Five 2-byte and five 4-byte characters sit exactly at the limit
+        assert!(validate_name("ééééé", &config).is_none());
+        assert!(validate_name("😀😀😀😀😀", &config).is_none());
+        assert!(validate_name("éééééé", &config).is_some());
+        assert!(validate_name("😀😀😀😀😀😀", &config).is_some());
+    }
+
+    #[test]
+    fn test_description_limit_counts_bytes() {
+        let config = AppConfig {
+            description_max_len: 10,
+            ..AppConfig::default()
+        };
+
+        // Five 2-byte characters fill the limit; a sixth goes over it
+        assert!(validate_description(Some("ééééé"), &config).is_none());
+        let error = validate_description(Some("éééééé"), &config).unwrap();
+        assert_eq!(
+            error.message,
+            "Description must be at most 10 bytes of UTF-8"
+        );
+    }
+
+    #[test]
+    fn test_name_byte_size_guarded_despite_char_limit() {
+        let config = AppConfig {
+            name_max_len: 10_000,
+            ..AppConfig::default()
+        };
+
+        assert!(validate_name(&"😀".repeat(NAME_MAX_BYTES / 4), &config).is_none());
+        let error = validate_name(&"😀".repeat(NAME_MAX_BYTES / 4 + 1), &config).unwrap();
+        assert_eq!(error.message, "Name must be at most 4096 bytes of UTF-8");
+    }
+
+    #[test]
+    fn test_created_response_has_location() {
+        let item = sample_item();
+
+        let response = created_response(&item, None);
+
+        assert_eq!(response.status_code, 201);
+        assert_eq!(
+            response.headers.get("location").unwrap(),
+            &format!("/items/{}", item.id)
+        );
+
+        let response = created_response(&item, Some("https://api.example.com"));
+        assert_eq!(
+            response.headers.get("location").unwrap(),
+            &format!("https://api.example.com/items/{}", item.id)
+        );
+    }
+
+    #[test]
+    fn test_create_item_too_large_maps_to_413() {
+        let response = create_error_response(&RepositoryError::ItemTooLarge);
+
+        assert_eq!(response.status_code, 413);
+    }
+
+    fn paging_config() -> AppConfig {
+        AppConfig {
+            default_page_size: 20,
+            max_page_size: 500,
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_page_limit_clamped_to_max() {
+        assert_eq!(page_limit(Some(1000), &paging_config()), Ok(500));
+        assert_eq!(page_limit(Some(300), &paging_config()), Ok(300));
+    }
+
+    #[test]
+    fn test_page_limit_defaults_when_absent() {
+        assert_eq!(page_limit(None, &paging_config()), Ok(20));
+        assert_eq!(page_limit(None, &config()), Ok(50));
+    }
+
+    #[test]
+    fn test_page_limit_below_one_rejected() {
+        assert!(page_limit(Some(0), &paging_config()).is_err());
+        assert!(page_limit(Some(-5), &paging_config()).is_err());
+        assert_eq!(page_limit(Some(1), &paging_config()), Ok(1));
+    }
+
+    fn validate_only_request(body: &str) -> ApiGatewayV2httpRequest {
+        ApiGatewayV2httpRequest {
+            body: Some(body.to_string()),
+            query_string_parameters: HashMap::from([(
+                "validate_only".to_string(),
+                "true".to_string(),
+            )])
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    fn test_user() -> AuthUser {
+        AuthUser {
+            id: "user-1".to_string(),
+            email: None,
+            name: None,
+            groups: Vec::new(),
+            scopes: Vec::new(),
+            claims: Map::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_only_skips_write() {
+        let state = AppState::for_tests();
+        let request = validate_only_request(r#"{"name":"  Valid   name "}"#);
+
+        let response = create(&state, &request, test_user()).await;
+
+        assert_eq!(response.status_code, 200);
+        let body = match response.body {
+            Some(Body::Text(text)) => text,
+            other => panic!("unexpected body: {other:?}"),
+        };
+        assert!(body.contains(r#""valid":true"#));
+        let stored = state
+            .items
+            .query(Item::ENTITY_TYPE, 10, None)
+            .await
+            .unwrap();
+        assert!(stored.items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_validate_only_reports_field_errors() {
+        let state = AppState::for_tests();
+        let request = validate_only_request(r#"{"name":"   "}"#);
+
+        let response = create(&state, &request, test_user()).await;
+
+        assert_eq!(response.status_code, 400);
+    }
+
+    #[test]
+    fn test_item_get_cache_control() {
+        let item = sample_item();
+        let cache = CacheControl::Private { max_age: 60 };
+
+        let response = conditional_response(&item, &HeaderMap::new(), cache);
+        assert_eq!(
+            response.headers.get("cache-control").unwrap(),
+            "private, max-age=60"
+        );
+
+        let headers = headers_with_if_none_match(&item_etag(&item));
+        let response = conditional_response(&item, &headers, cache);
+        assert_eq!(response.status_code, 304);
+        assert_eq!(
+            response.headers.get("cache-control").unwrap(),
+            "private, max-age=60"
+        );
+
+        let response = conditional_response(&item, &HeaderMap::new(), CacheControl::NoStore);
+        assert_eq!(response.headers.get("cache-control").unwrap(), "no-store");
+    }
+
+    #[test]
+    fn test_modified_since_selects_only_newer_items() {
+        let since = parse_modified_since("2024-06-01T12:00:00+02:00").unwrap();
+        assert_eq!(since, "2024-06-01T10:00:00+00:00");
+
+        let updated = [
+            "2024-06-01T09:59:59.999+00:00",
+            "2024-06-01T10:00:00+00:00",
+            "2024-06-01T10:00:00.250+00:00",
+            "2024-06-02T08:00:00+00:00",
+        ];
+        // Mirror the gsi3sk > :after key condition
+        let newer: Vec<String> = updated
+            .iter()
+            .map(|updated_at| {
+                let mut item = sample_item();
+                item.updated_at = updated_at.to_string();
+                item
+            })
+            .filter(|item| {
+                item.to_dynamo(&TableSettings::default())["gsi3sk"]
+                    .as_s()
+                    .unwrap()
+                    > &since
+            })
+            .map(|item| item.updated_at)
+            .collect();
+
+        assert_eq!(newer, vec![updated[2], updated[3]]);
+    }
+
+    #[test]
+    fn test_malformed_modified_since_rejected() {
+        assert!(parse_modified_since("yesterday").is_err());
+        assert!(parse_modified_since("2024-06-01").is_err());
+        assert!(parse_modified_since("").is_err());
+    }
+
+    #[test]
+    fn test_created_range_normalizes_and_fills_open_ends() {
+        assert_eq!(created_range(None, None), Ok(None));
+        assert_eq!(
+            created_range(
+                Some("2024-06-01T02:00:00+02:00"),
+                Some("2024-06-30T00:00:00Z")
+            ),
+            Ok(Some((
+                "2024-06-01T00:00:00+00:00".to_string(),
+                "2024-06-30T00:00:00+00:00".to_string()
+            )))
+        );
+        assert_eq!(
+            created_range(Some("2024-06-01T00:00:00Z"), None),
+            Ok(Some((
+                "2024-06-01T00:00:00+00:00".to_string(),
+                LATEST_TIMESTAMP.to_string()
+            )))
+        );
+        assert_eq!(
+            created_range(None, Some("2024-06-01T00:00:00Z")),
+            Ok(Some((
+                EARLIEST_TIMESTAMP.to_string(),
+                "2024-06-01T00:00:00+00:00".to_string()
+            )))
+        );
+        // A single instant is a valid range
+        assert!(created_range(Some("2024-06-01T00:00:00Z"), Some("2024-06-01T00:00:00Z")).is_ok());
+    }
+
+    #[test]
+    fn test_created_range_rejects_malformed_or_inverted_bounds() {
+        assert!(created_range(Some("2024-06-01"), None).is_err());
+        assert!(created_range(None, Some("tomorrow")).is_err());
+        assert_eq!(
+            created_range(Some("2024-06-02T00:00:00Z"), Some("2024-06-01T00:00:00Z")),
+            Err("created_after must not be later than created_before")
+        );
+    }
+
+    fn headers_with_if_match(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("if-match", value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_matching_if_match_conditions_the_write() {
+        let item = sample_item();
+        let headers = headers_with_if_match(&item_etag(&item));
+
+        let version = check_if_match(Some(&item), &headers).unwrap();
+
+        assert_eq!(version.as_deref(), Some(item.updated_at.as_str()));
+        assert_eq!(
+            check_if_match(Some(&item), &HeaderMap::new()).unwrap(),
+            None
+        );
+        assert_eq!(
+            check_if_match(Some(&item), &headers_with_if_match("*")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_stale_if_match_returns_412() {
+        let item = sample_item();
+        let headers = headers_with_if_match("W/\"2023-12-31T00:00:00+00:00\"");
+
+        let response = check_if_match(Some(&item), &headers).unwrap_err();
+        assert_eq!(response.status_code, 412);
+
+        // A concurrent write that wins the race fails the DynamoDB condition
+        let response = update_error_response(&RepositoryError::ConditionFailed);
+        assert_eq!(response.status_code, 412);
+    }
+
+    #[test]
+    fn test_if_unmodified_since_covers_the_whole_second() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(if_unmodified_since(&headers), None);
+
+        headers.insert(
+            "if-unmodified-since",
+            "Mon, 01 Jan 2024 00:00:00 GMT".parse().unwrap(),
+        );
+        let before = if_unmodified_since(&headers).unwrap();
+        assert_eq!(before, "2024-01-01T00:00:01+00:00");
+        assert!("2024-01-01T00:00:00.999+00:00" < before.as_str());
+        assert!("2024-01-01T00:00:01.001+00:00" > before.as_str());
+
+        headers.insert("if-unmodified-since", "yesterday".parse().unwrap());
+        assert_eq!(if_unmodified_since(&headers), None);
+    }
+
+    #[test]
+    fn test_expires_at_must_be_in_future() {
+        assert!(validate_expires_at(None, 1_000).is_none());
+        assert!(validate_expires_at(Some(1_001), 1_000).is_none());
+        assert_eq!(
+            validate_expires_at(Some(1_000), 1_000).unwrap().field,
+            "expires_at"
+        );
+
+        let mut request = create_request("Temporary");
+        request.expires_at = Some(1);
+        assert!(request.validate(&config()).is_err());
+    }
+
+    #[test]
+    fn test_into_item_carries_expiry() {
+        let mut request = create_request("Temporary");
+        request.expires_at = Some(1_900_000_000);
+
+        let item = request.into_item(&test_user(), IdScheme::Uuid);
+
+        assert_eq!(item.expires_at, Some(1_900_000_000));
+    }
+
+    #[test]
+    fn test_projection_fields_always_include_id() {
+        let fields = projection_fields("name, name,expires_at").unwrap();
+        assert_eq!(fields, vec!["id", "name", "expires_at"]);
+    }
+
+    #[test]
+    fn test_projection_fields_reject_unknown_names() {
+        assert!(projection_fields("name,password").is_err());
+        assert!(projection_fields("pk").is_err());
+        assert!(projection_fields("name,").is_err());
+    }
+
+    #[test]
+    fn test_projected_item_limits_fields() {
+        let mut item = sample_item();
+        item.expires_at = Some(1_900_000_000);
+        let fields = projection_fields("name,description,expires_at").unwrap();
+
+        let settings = TableSettings {
+            ttl_attribute: "expireAt".to_string(),
+            ..TableSettings::default()
+        };
+
+        let projected = projected_item(&item.to_dynamo(&settings), &fields, "expireAt");
+
+        assert_eq!(
+            serde_json::Value::Object(projected),
+            serde_json::json!({"id": item.id, "name": item.name, "expires_at": 1_900_000_000u64})
+        );
+    }
+
+    #[test]
+    fn test_plain_and_base64_bodies_parse_the_same() {
+        let json = r#"{"name":"Widget","description":"Blue","expires_at":1900000000}"#;
+        let plain = ApiGatewayV2httpRequest {
+            body: Some(json.to_string()),
+            ..Default::default()
+        };
+        let encoded = ApiGatewayV2httpRequest {
+            body: Some(BASE64_STANDARD.encode(json)),
+            is_base64_encoded: true,
+            ..Default::default()
+        };
+
+        let plain: CreateItemRequest = parse_body(&plain, &config()).unwrap();
+        let encoded: CreateItemRequest = parse_body(&encoded, &config()).unwrap();
+
+        assert_eq!(plain.name, encoded.name);
+        assert_eq!(plain.description, encoded.description);
+        assert_eq!(plain.expires_at, encoded.expires_at);
+        assert_eq!(encoded.name, "Widget");
+    }
+
+    #[test]
+    fn test_invalid_base64_body_rejected() {
+        let request = ApiGatewayV2httpRequest {
+            body: Some("not base64!".to_string()),
+            is_base64_encoded: true,
+            ..Default::default()
+        };
+
+        let response = parse_body::<CreateItemRequest>(&request, &config()).unwrap_err();
+        assert_eq!(response.status_code, 400);
+    }
+
+    #[test]
+    fn test_parse_failure_detail_only_in_debug_mode() {
+        let request = ApiGatewayV2httpRequest {
+            body: Some(r#"{"name":42}"#.to_string()),
+            ..Default::default()
+        };
+        let error_message = |debug_errors| {
+            let config = AppConfig {
+                debug_errors,
+                ..config()
+            };
+            let response = parse_body::<CreateItemRequest>(&request, &config).unwrap_err();
+            assert_eq!(response.status_code, 400);
+            let Some(Body::Text(body)) = response.body else {
+                panic!("expected a text body");
+            };
+            let body: Value = serde_json::from_str(&body).unwrap();
+            body["error"].as_str().unwrap().to_string()
+        };
+
+        assert_eq!(error_message(false), "Invalid request body");
+        let detailed = error_message(true);
+        assert!(
+            detailed.starts_with("Invalid request body: Invalid JSON: invalid type"),
+            "{detailed}"
+        );
+    }
+
+    #[test]
+    fn test_validate_slug() {
+        assert!(validate_slug("blue-widget-2").is_none());
+        for slug in ["", "Blue", "blue widget", "-blue", "blue-", "blue_widget"] {
+            assert!(validate_slug(slug).is_some(), "{slug:?} should be rejected");
+        }
+        assert!(validate_slug(&"a".repeat(SLUG_MAX_LEN + 1)).is_some());
+    }
+
+    #[test]
+    fn test_batch_create_rejects_slugs() {
+        let mut item = create_request("Widget");
+        item.slug = Some("widget".to_string());
+        let mut request = BatchCreateRequest {
+            items: vec![create_request("Other"), item],
+            partial: false,
+        };
+
+        let errors = request.validate(&config()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "items[1].slug");
+    }
+
+    fn search(body: &str) -> Result<ListQuery, Vec<String>> {
+        serde_json::from_str::<SearchRequest>(body)
+            .unwrap()
+            .into_list_query(&AppConfig::default())
+            .map_err(|fields| fields.into_iter().map(|f| f.field).collect())
+    }
+
+    #[test]
+    fn test_search_sort_selects_index() {
+        let config = AppConfig::default();
+        let unsorted = search("{}").unwrap();
+        assert_eq!(
+            unsorted.limit,
+            config.default_page_size.min(config.max_page_size)
+        );
+        assert_eq!(unsorted, search(r#"{"sort":"id"}"#).unwrap());
+
+        let by_update = search(r#"{"sort":"updated_at"}"#).unwrap();
+        assert_eq!(
+            by_update.modified_since.as_deref(),
+            Some(EARLIEST_TIMESTAMP)
+        );
+        let after = search(r#"{"modified_since":"2024-06-01T12:00:00+02:00","sort":"updated_at"}"#)
+            .unwrap();
+        assert_eq!(
+            after.modified_since.as_deref(),
+            Some("2024-06-01T10:00:00+00:00")
+        );
+
+        let by_creation = search(r#"{"sort":"created_at"}"#).unwrap();
+        assert_eq!(
+            by_creation.created,
+            Some((EARLIEST_TIMESTAMP.to_string(), LATEST_TIMESTAMP.to_string()))
+        );
+        assert_eq!(by_creation.modified_since, None);
+
+        let by_name = search(r#"{"sort":"name"}"#).unwrap();
+        assert_eq!(by_name.name_prefix.as_deref(), Some(""));
+        assert_eq!(by_name.modified_since, None);
+    }
+
+    #[test]
+    fn test_search_reports_every_invalid_field() {
+        assert_eq!(
+            search(r#"{"limit":0,"modified_since":"soon","fields":["secret"]}"#).unwrap_err(),
+            vec!["limit", "modified_since", "fields"]
+        );
+        assert_eq!(
+            search(
+                r#"{"filters":{"created_after":"2024-07-01T00:00:00Z","created_before":"june"}}"#
+            )
+            .unwrap_err(),
+            vec!["filters.created_before"]
+        );
+        assert_eq!(
+            search(r#"{"modified_since":"2024-06-01T00:00:00Z","filters":{"name_prefix":"a"}}"#)
+                .unwrap_err(),
+            vec!["filters.name_prefix"]
+        );
+        assert_eq!(
+            search(r#"{"modified_since":"2024-06-01T00:00:00Z","filters":{"created_after":"2024-06-01T00:00:00Z"}}"#)
+                .unwrap_err(),
+            vec!["modified_since"]
+        );
+        assert_eq!(
+            search(r#"{"filters":{"name_prefix":"a"},"sort":"updated_at"}"#).unwrap_err(),
+            vec!["sort"]
+        );
+        assert_eq!(
+            search(r#"{"modified_since":"2024-06-01T00:00:00Z","sort":"id"}"#).unwrap_err(),
+            vec!["sort"]
+        );
+        assert_eq!(
+            search(r#"{"filters":{"created_after":"2024-06-01T00:00:00Z"},"sort":"updated_at"}"#)
+                .unwrap_err(),
+            vec!["sort"]
+        );
+        assert_eq!(
+            search(r#"{"modified_since":"2024-06-01T00:00:00Z","sort":"created_at"}"#).unwrap_err(),
+            vec!["sort"]
+        );
+        assert_eq!(
+            search(r#"{"filters":{"created_before":"2024-06-01T00:00:00Z"},"sort":"name"}"#)
+                .unwrap_err(),
+            vec!["sort"]
+        );
+    }
 }
@@ -1,2 +1,4 @@
 pub mod health;
 pub mod items;
+pub mod openapi;
+pub mod uploads;
@@ -0,0 +1,140 @@
+use crate::response::ResponseBuilder;
+use crate::AppState;
+use aws_lambda_events::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
+
+/// Hand-maintained OpenAPI 3 description of the API; the tests below check
+/// its schemas against the serialized Rust types so the two don't drift
+const OPENAPI_DOCUMENT: &str = include_str!("../../openapi.json");
+
+pub async fn handle(
+    _state: &AppState,
+    _request: &ApiGatewayV2httpRequest,
+) -> ApiGatewayV2httpResponse {
+    // Served as-is rather than inside the ApiResponse envelope, but with the usual headers
+    ResponseBuilder::new(200)
+        .text("application/json", OPENAPI_DOCUMENT)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::items::{
+        CountItemsResponse, DeleteItemResponse, DeletedItemResponse, ListItemsResponse, ListedItem,
+    };
+    use crate::routes::uploads::{DownloadResponse, UploadResponse};
+    use serde::Serialize;
+    use serde_json::Value;
+    use shared::models::Item;
+    use std::collections::{BTreeSet, HashMap};
+
+    fn document() -> Value {
+        serde_json::from_str(OPENAPI_DOCUMENT).unwrap()
+    }
+
+    fn schema_properties(name: &str) -> BTreeSet<String> {
+        document()["components"]["schemas"][name]["properties"]
+            .as_object()
+            .unwrap_or_else(|| panic!("missing schema {name}"))
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    fn serialized_fields(value: impl Serialize) -> BTreeSet<String> {
+        serde_json::to_value(value)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    fn item() -> Item {
+        Item {
+            id: "item-1".to_string(),
+            name: "Widget".to_string(),
+            description: Some("A widget".to_string()),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+            created_by: "user-1".to_string(),
+            expires_at: Some(1_900_000_000),
+            slug: Some("blue-widget".to_string()),
+            deleted_at: Some("2024-01-03T00:00:00+00:00".to_string()),
+            metadata: HashMap::from([("color".to_string(), "blue".to_string())]),
+            public: false,
+        }
+    }
+
+    #[test]
+    fn test_document_is_valid_json_with_item_paths() {
+        let doc = document();
+
+        assert_eq!(doc["openapi"], "3.0.3");
+        let paths = doc["paths"].as_object().unwrap();
+        for path in ["/items", "/items/{id}", "/items/batch", "/items/count"] {
+            assert!(paths.contains_key(path), "missing {path}");
+        }
+    }
+
+    #[test]
+    fn test_schemas_match_serialized_types() {
+        assert_eq!(schema_properties("Item"), serialized_fields(item()));
+        let mut listed = schema_properties("Item");
+        listed.extend(
+            document()["components"]["schemas"]["ListedItem"]["allOf"][1]["properties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned(),
+        );
+        assert_eq!(listed, serialized_fields(ListedItem::from(item())));
+        assert_eq!(
+            schema_properties("ListItemsResponse"),
+            serialized_fields(ListItemsResponse {
+                items: vec![item()],
+                page_count: 1,
+                has_more: false,
+                next_cursor: None,
+                scanned_count: 1,
+            })
+        );
+        assert_eq!(
+            schema_properties("CountItemsResponse"),
+            serialized_fields(CountItemsResponse { count: 3 })
+        );
+        assert_eq!(
+            schema_properties("DeleteItemResponse"),
+            serialized_fields(DeleteItemResponse {
+                id: "abc".to_string()
+            })
+        );
+        assert_eq!(
+            schema_properties("DeletedItemResponse"),
+            serialized_fields(DeletedItemResponse {
+                id: "abc".to_string(),
+                deleted_at: "2024-01-03T00:00:00+00:00".to_string(),
+            })
+        );
+        assert_eq!(
+            schema_properties("DownloadResponse"),
+            serialized_fields(DownloadResponse {
+                url: "https://example.com".to_string(),
+                key: "user-1/a.txt".to_string(),
+                content_type: "text/plain".to_string(),
+                expires_in: 300,
+            })
+        );
+        assert_eq!(
+            schema_properties("UploadResponse"),
+            serialized_fields(UploadResponse {
+                url: "https://storage.s3.us-east-1.amazonaws.com/".to_string(),
+                key: "user-1/abc/a.txt".to_string(),
+                fields: Default::default(),
+                max_bytes: 1024,
+                expires_in: 300,
+            })
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use crate::auth::AuthUser;
+use crate::routes::items::parse_valid_body;
+use crate::validation::{collect_errors, Validate};
+use crate::{json_response, ApiResponse, AppState, FieldError};
+use aws_lambda_events::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
+use aws_sdk_s3::config::{Credentials, ProvideCredentials};
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sigv4::sign::v4::{calculate_signature, generate_signing_key};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use shared::config::AppConfig;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+use tracing::{error, info};
+use uuid::Uuid;
+
+/// Lifetime of a presigned download URL
+const DOWNLOAD_URL_TTL: Duration = Duration::from_secs(300);
+
+/// Lifetime of a presigned upload form
+const UPLOAD_URL_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Deserialize)]
+pub struct UploadRequest {
+    pub filename: String,
+    pub content_type: String,
+}
+
+impl Validate for UploadRequest {
+    /// Lowercase the content type, then check it against `allowed_upload_types`
+    fn validate(&mut self, config: &AppConfig) -> Result<(), Vec<FieldError>> {
+        self.content_type = self.content_type.trim().to_ascii_lowercase();
+
+        collect_errors([
+            (!valid_filename(&self.filename)).then(|| {
+                FieldError::new(
+                    "filename",
+                    "Filename must be a single path segment of at most 255 bytes",
+                )
+            }),
+            (!config.allowed_upload_types.contains(&self.content_type)).then(|| {
+                FieldError::new(
+                    "content_type",
+                    format!("Content type {} is not allowed", self.content_type),
+                )
+            }),
+        ])
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadResponse {
+    /// Form action: POST `fields`, then the file as a final `file` field
+    pub url: String,
+    pub key: String,
+    pub fields: BTreeMap<String, String>,
+    /// Largest file S3 will accept for this form
+    pub max_bytes: u64,
+    /// Seconds until the form stops working
+    pub expires_in: u64,
+}
+
+/// `POST /uploads`: presigned POST form for a new object under the caller's
+/// `{user_id}/` prefix. The signed policy pins the key and content type and
+/// caps the size at `max_upload_bytes`, so S3 itself enforces the limits.
+pub async fn upload(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
+) -> ApiGatewayV2httpResponse {
+    let upload: UploadRequest = match parse_valid_body(request, &state.config) {
+        Ok(upload) => upload,
+        Err(response) => return response,
+    };
+
+    let credentials = match &state.credentials {
+        Some(provider) => provider.provide_credentials().await,
+        None => {
+            error!("S3 client has no credentials provider");
+            return json_response(
+                500,
+                &ApiResponse::<()>::error("Failed to create upload URL"),
+            );
+        }
+    };
+    let credentials = match credentials {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            error!(error = %e, "Failed to load credentials for upload policy");
+            return json_response(
+                500,
+                &ApiResponse::<()>::error("Failed to create upload URL"),
+            );
+        }
+    };
+    let region = state
+        .s3
+        .config()
+        .region()
+        .map_or("us-east-1", |region| region.as_ref());
+
+    let bucket = &state.config.storage_bucket;
+    let key = format!("{}/{}/{}", user.id, Uuid::new_v4(), upload.filename);
+    let policy = PostPolicy {
+        bucket,
+        key: &key,
+        content_type: &upload.content_type,
+        max_bytes: state.config.max_upload_bytes,
+        expires_in: UPLOAD_URL_TTL,
+    };
+    let fields = policy.sign(&credentials, region, SystemTime::now());
+
+    info!(key = %key, content_type = %upload.content_type, "Presigned upload");
+    json_response(
+        200,
+        &ApiResponse::success(UploadResponse {
+            url: format!("https://{bucket}.s3.{region}.amazonaws.com/"),
+            key,
+            fields,
+            max_bytes: state.config.max_upload_bytes,
+            expires_in: UPLOAD_URL_TTL.as_secs(),
+        }),
+    )
+}
+
+/// Conditions of an S3 browser-based upload (POST policy)
+struct PostPolicy<'a> {
+    bucket: &'a str,
+    key: &'a str,
+    content_type: &'a str,
+    max_bytes: u64,
+    expires_in: Duration,
+}
+
+impl PostPolicy<'_> {
+    /// Form fields for the policy, including the base64 `policy` document
+    /// and its SigV4 `x-amz-signature`
+    fn sign(
+        &self,
+        credentials: &Credentials,
+        region: &str,
+        now: SystemTime,
+    ) -> BTreeMap<String, String> {
+        let time: DateTime<Utc> = now.into();
+        let expiration: DateTime<Utc> = (now + self.expires_in).into();
+        let credential = format!(
+            "{}/{}/{region}/s3/aws4_request",
+            credentials.access_key_id(),
+            time.format("%Y%m%d")
+        );
+
+        let mut fields = BTreeMap::from([
+            ("key".to_string(), self.key.to_string()),
+            ("Content-Type".to_string(), self.content_type.to_string()),
+            (
+                "x-amz-algorithm".to_string(),
+                "AWS4-HMAC-SHA256".to_string(),
+            ),
+            ("x-amz-credential".to_string(), credential),
+            (
+                "x-amz-date".to_string(),
+                time.format("%Y%m%dT%H%M%SZ").to_string(),
+            ),
+        ]);
+        if let Some(token) = credentials.session_token() {
+            fields.insert("x-amz-security-token".to_string(), token.to_string());
+        }
+
+        // Every field must match exactly, so clients can't change the key or type
+        let mut conditions: Vec<Value> = vec![
+            json!({ "bucket": self.bucket }),
+            json!(["content-length-range", 1, self.max_bytes]),
+        ];
+        conditions.extend(fields.iter().map(|(name, value)| json!({ name: value })));
+        let document = json!({
+            "expiration": expiration.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+            "conditions": conditions,
+        });
+        let policy = BASE64_STANDARD.encode(document.to_string());
+
+        let signing_key = generate_signing_key(credentials.secret_access_key(), now, region, "s3");
+        let signature = calculate_signature(signing_key, policy.as_bytes());
+        fields.insert("policy".to_string(), policy);
+        fields.insert("x-amz-signature".to_string(), signature);
+        fields
+    }
+}
+
+/// An upload's filename becomes the last key segment, so it can't contain
+/// separators, traversal names or control characters
+fn valid_filename(filename: &str) -> bool {
+    !filename.is_empty()
+        && filename.len() <= 255
+        && !matches!(filename, "." | "..")
+        && !filename
+            .chars()
+            .any(|c| c == '/' || c == '\\' || c.is_control())
+}
+
+#[derive(Debug, Serialize)]
+pub struct DownloadResponse {
+    pub url: String,
+    pub key: String,
+    pub content_type: String,
+    /// Seconds until `url` stops working
+    pub expires_in: u64,
+}
+
+/// `GET /uploads/{key}`: presigned GET URL for an object in the caller's namespace
+pub async fn download(
+    state: &AppState,
+    request: &ApiGatewayV2httpRequest,
+    user: AuthUser,
+) -> ApiGatewayV2httpResponse {
+    let path = request.raw_path.as_deref().unwrap_or("");
+    let key = match object_key(path.trim_start_matches("/uploads/")) {
+        Ok(key) => key,
+        Err(message) => return json_response(400, &ApiResponse::<()>::error(message)),
+    };
+
+    if !owns_key(&user.id, &key) {
+        return json_response(
+            403,
+            &ApiResponse::<()>::error("Object belongs to another user"),
+        );
+    }
+
+    let content_type = content_type_for(&key);
+    let presigning = PresigningConfig::expires_in(DOWNLOAD_URL_TTL).expect("valid presign TTL");
+    let presigned = state
+        .s3
+        .get_object()
+        .bucket(&state.config.storage_bucket)
+        .key(&key)
+        .response_content_type(content_type)
+        .presigned(presigning)
+        .await;
+
+    match presigned {
+        Ok(presigned) => json_response(
+            200,
+            &ApiResponse::success(DownloadResponse {
+                url: presigned.uri().to_string(),
+                key,
+                content_type: content_type.to_string(),
+                expires_in: DOWNLOAD_URL_TTL.as_secs(),
+            }),
+        ),
+        Err(e) => {
+            error!(error = %e, key = %key, "Failed to presign download");
+            json_response(
+                500,
+                &ApiResponse::<()>::error("Failed to create download URL"),
+            )
+        }
+    }
+}
+
+/// Check the key from the (already decoded) path, rejecting anything that
+/// could resolve outside its prefix: empty, `.` or `..` segments,
+/// backslashes and control characters
+fn object_key(key: &str) -> Result<String, &'static str> {
+    if key.is_empty() {
+        return Err("Missing object key");
+    }
+
+    let valid = key
+        .split('/')
+        .all(|segment| !matches!(segment, "" | "." | ".."))
+        && !key.chars().any(|c| c == '\\' || c.is_control());
+    if !valid {
+        return Err("Invalid object key");
+    }
+    Ok(key.to_string())
+}
+
+/// Uploads live under `{user_id}/`; the key must name an object inside it
+fn owns_key(user_id: &str, key: &str) -> bool {
+    key.strip_prefix(user_id)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .is_some_and(|name| !name.is_empty())
+}
+
+/// Content-Type S3 sends with the download, from the key's extension
+fn content_type_for(key: &str) -> &'static str {
+    let extension = key
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "zip" => "application/zip",
+        "mp4" => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_key_accepts_nested_keys() {
+        assert_eq!(
+            object_key("user-1/photos/my cat.png").unwrap(),
+            "user-1/photos/my cat.png"
+        );
+        // The router already decoded the path; a literal % stays as it is
+        assert_eq!(
+            object_key("user-1/100%25.txt").unwrap(),
+            "user-1/100%25.txt"
+        );
+    }
+
+    #[test]
+    fn test_object_key_rejects_namespace_escapes() {
+        for raw in [
+            "",
+            "user-1/../user-2/secret.txt",
+            "user-1/./a.txt",
+            "/user-1/a.txt",
+            "user-1//a.txt",
+            "user-1/a\\..\\b",
+            "user-1/a\0.txt",
+        ] {
+            assert!(object_key(raw).is_err(), "{raw:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn test_owns_key_requires_user_prefix() {
+        assert!(owns_key("user-1", "user-1/a.txt"));
+        assert!(owns_key("user-1", "user-1/nested/a.txt"));
+        assert!(!owns_key("user-1", "user-2/a.txt"));
+        // A shared prefix isn't the same namespace
+        assert!(!owns_key("user-1", "user-10/a.txt"));
+        assert!(!owns_key("user-1", "user-1"));
+        assert!(!owns_key("user-1", "user-1/"));
+    }
+
+    #[test]
+    fn test_content_type_for_extension() {
+        assert_eq!(content_type_for("u/photo.PNG"), "image/png");
+        assert_eq!(content_type_for("u/report.pdf"), "application/pdf");
+        assert_eq!(content_type_for("u/archive"), "application/octet-stream");
+        assert_eq!(content_type_for("u/data.bin"), "application/octet-stream");
+    }
+
+    fn upload_request(filename: &str, content_type: &str) -> UploadRequest {
+        UploadRequest {
+            filename: filename.to_string(),
+            content_type: content_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_upload_request_allows_configured_types() {
+        let config = AppConfig::default();
+
+        let mut request = upload_request("cat.png", " Image/PNG ");
+        assert!(request.validate(&config).is_ok());
+        assert_eq!(request.content_type, "image/png");
+
+        let errors = upload_request("setup.exe", "application/x-msdownload")
+            .validate(&config)
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "content_type");
+
+        let config = AppConfig {
+            allowed_upload_types: vec!["application/zip".to_string()],
+            ..AppConfig::default()
+        };
+        assert!(upload_request("a.zip", "application/zip")
+            .validate(&config)
+            .is_ok());
+        assert!(upload_request("cat.png", "image/png")
+            .validate(&config)
+            .is_err());
+    }
+
+    #[test]
+    fn test_valid_filename() {
+        assert!(valid_filename("report 2024.pdf"));
+        for name in ["", ".", "..", "a/b.txt", "a\\b.txt", "a\u{0}.txt"] {
+            assert!(!valid_filename(name), "{name:?} should be rejected");
+        }
+        assert!(!valid_filename(&"a".repeat(256)));
+    }
+
+    fn signed_policy(credentials: &Credentials) -> (BTreeMap<String, String>, Value) {
+        let policy = PostPolicy {
+            bucket: "storage",
+            key: "user-1/abc/cat.png",
+            content_type: "image/png",
+            max_bytes: 1024,
+            expires_in: Duration::from_secs(300),
+        };
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let fields = policy.sign(credentials, "us-east-1", now);
+        let document = BASE64_STANDARD.decode(&fields["policy"]).unwrap();
+        (fields, serde_json::from_slice(&document).unwrap())
+    }
+
+    #[test]
+    fn test_post_policy_caps_size_and_pins_fields() {
+        let credentials = Credentials::new("AKID", "secret", None, None, "tests");
+        let (fields, document) = signed_policy(&credentials);
+
+        let conditions = document["conditions"].as_array().unwrap();
+        assert!(conditions.contains(&json!(["content-length-range", 1, 1024])));
+        assert!(conditions.contains(&json!({"bucket": "storage"})));
+        assert!(conditions.contains(&json!({"key": "user-1/abc/cat.png"})));
+        assert!(conditions.contains(&json!({"Content-Type": "image/png"})));
+        assert_eq!(document["expiration"], "2023-11-14T22:18:20.000Z");
+
+        assert_eq!(
+            fields["x-amz-credential"],
+            "AKID/20231114/us-east-1/s3/aws4_request"
+        );
+        assert_eq!(fields["x-amz-date"], "20231114T221320Z");
+        assert_eq!(fields["x-amz-signature"].len(), 64);
+        assert!(!fields.contains_key("x-amz-security-token"));
+    }
+
+    #[test]
+    fn test_post_policy_includes_session_token() {
+        let credentials =
+            Credentials::new("AKID", "secret", Some("token".to_string()), None, "tests");
+        let (fields, document) = signed_policy(&credentials);
+
+        assert_eq!(fields["x-amz-security-token"], "token");
+        let conditions = document["conditions"].as_array().unwrap();
+        assert!(conditions.contains(&json!({"x-amz-security-token": "token"})));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{json_response, ApiResponse, FieldError};
+use aws_lambda_events::apigw::ApiGatewayV2httpResponse;
+use shared::config::AppConfig;
+
+/// A request body that checks its own fields. Implementations may normalize
+/// fields in place first (e.g. collapsing whitespace in names), so the value
+/// that passed validation is the one handlers use.
+pub trait Validate {
+    /// Every invalid field, or `Ok` when the request can be acted on
+    fn validate(&mut self, config: &AppConfig) -> Result<(), Vec<FieldError>>;
+}
+
+/// Validate a parsed request, turning failures into the shared 400
+/// `Validation failed` response with per-field errors
+#[allow(clippy::result_large_err)]
+pub fn validated<T: Validate>(
+    mut request: T,
+    config: &AppConfig,
+) -> Result<T, ApiGatewayV2httpResponse> {
+    match request.validate(config) {
+        Ok(()) => Ok(request),
+        Err(fields) => Err(json_response(
+            400,
+            &ApiResponse::<()>::validation_error(fields),
+        )),
+    }
+}
+
+/// `Ok` when no checks produced an error, otherwise all of them
+pub fn collect_errors(
+    errors: impl IntoIterator<Item = Option<FieldError>>,
+) -> Result<(), Vec<FieldError>> {
+    let errors: Vec<FieldError> = errors.into_iter().flatten().collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_lambda_events::encodings::Body;
+
+    struct Rename {
+        name: String,
+    }
+
+    impl Validate for Rename {
+        fn validate(&mut self, config: &AppConfig) -> Result<(), Vec<FieldError>> {
+            self.name = self.name.trim().to_string();
+            collect_errors([
+                (self.name.is_empty() || self.name.len() > config.name_max_len)
+                    .then(|| FieldError::new("name", "Name is required")),
+            ])
+        }
+    }
+
+    struct Tags {
+        tags: Vec<String>,
+    }
+
+    impl Validate for Tags {
+        fn validate(&mut self, _config: &AppConfig) -> Result<(), Vec<FieldError>> {
+            collect_errors(self.tags.iter().enumerate().map(|(index, tag)| {
+                tag.is_empty()
+                    .then(|| FieldError::new(format!("tags[{index}]"), "Tag must not be empty"))
+            }))
+        }
+    }
+
+    fn body_json(response: &ApiGatewayV2httpResponse) -> serde_json::Value {
+        match &response.body {
+            Some(Body::Text(text)) => serde_json::from_str(text).unwrap(),
+            other => panic!("unexpected body: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_validated_returns_normalized_request() {
+        let config = AppConfig::default();
+
+        let request = validated(
+            Rename {
+                name: "  Widget ".to_string(),
+            },
+            &config,
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(request.name, "Widget");
+
+        let tags = Tags {
+            tags: vec!["a".to_string()],
+        };
+        assert!(validated(tags, &config).is_ok());
+    }
+
+    #[test]
+    fn test_validation_failures_share_one_response_shape() {
+        let config = AppConfig::default();
+
+        let rename = validated(
+            Rename {
+                name: "   ".to_string(),
+            },
+            &config,
+        )
+        .err()
+        .unwrap();
+        let tags = validated(
+            Tags {
+                tags: vec!["a".to_string(), String::new(), String::new()],
+            },
+            &config,
+        )
+        .err()
+        .unwrap();
+
+        for (response, fields) in [(rename, vec!["name"]), (tags, vec!["tags[1]", "tags[2]"])] {
+            assert_eq!(response.status_code, 400);
+            let body = body_json(&response);
+            assert_eq!(body["success"], false);
+            assert_eq!(body["error"], "Validation failed");
+            let reported: Vec<_> = body["fields"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|f| f["field"].as_str().unwrap())
+                .collect();
+            assert_eq!(reported, fields);
+        }
+    }
+}
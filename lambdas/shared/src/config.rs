@@ -1,16 +1,414 @@
+use crate::ids::IdScheme;
+use crate::models::{
+    ItemLimits, DEFAULT_DESCRIPTION_MAX_LEN, DEFAULT_NAME_MAX_LEN, DEFAULT_TTL_ATTRIBUTE,
+};
+use crate::repository::TableSettings;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::env;
+use std::io::ErrorKind;
+use std::path::Path;
+use thiserror::Error;
 
 #[derive(Debug, Clone)]
 pub struct AppConfig {
     pub table_name: String,
     pub storage_bucket: String,
+    /// Largest request body (in decoded bytes) accepted before JSON parsing
+    pub max_body_bytes: usize,
+    /// Maximum item name length in characters (Unicode scalar values)
+    pub name_max_len: usize,
+    /// Maximum item description length in bytes
+    pub description_max_len: usize,
+    /// Page size used by list when the client doesn't send a limit
+    pub default_page_size: i32,
+    /// Largest page size list will return; bigger limits are clamped
+    pub max_page_size: i32,
+    /// Requests a single user may burst before being rate limited
+    pub rate_limit_burst: u32,
+    /// Sustained requests per second allowed per user
+    pub rate_limit_per_second: f64,
+    /// Seconds a browser may cache a single item GET; 0 sends `no-store`
+    pub item_cache_max_age: u32,
+    /// Origins allowed to call the API (comma-separated `ALLOWED_ORIGIN`); `*` allows any
+    pub allowed_origins: Vec<String>,
+    /// Seconds browsers may cache a preflight response
+    pub cors_max_age: u32,
+    /// Send `Access-Control-Allow-Credentials: true` for cookie-based flows
+    pub cors_allow_credentials: bool,
+    /// Maintenance mode: writes get 503 while reads keep working
+    pub read_only: bool,
+    /// Put parser detail in 400 bodies (`DEBUG_ERRORS`); off in production,
+    /// where clients get a generic message and the detail is only logged
+    pub debug_errors: bool,
+    /// Let callers without a token list and fetch items flagged `public`
+    /// (`PUBLIC_READS`); writes always need a token
+    pub public_reads: bool,
+    /// How new item ids are generated (`ID_SCHEME=uuid|ulid`)
+    pub id_scheme: IdScheme,
+    /// Milliseconds a handler may run before it is abandoned with a 504;
+    /// keep this under the Lambda timeout so clients get a clean error
+    pub handler_timeout_ms: u64,
+    /// Most items `GET /items/export` returns (`EXPORT_MAX_ITEMS`). Lambda
+    /// buffers the whole response and caps it at 6 MB, so exports stop here
+    /// and flag the truncation rather than fail
+    pub export_max_items: usize,
+    /// Content types `POST /uploads` accepts (`ALLOWED_UPLOAD_TYPES`, comma-separated)
+    pub allowed_upload_types: Vec<String>,
+    /// Largest upload the presigned POST policy allows (`MAX_UPLOAD_BYTES`)
+    pub max_upload_bytes: u64,
+    /// Attribute the table's TTL is configured on (`TTL_ATTRIBUTE`); item
+    /// expiry is written here so DynamoDB reaps what the API expires
+    pub ttl_attribute: String,
+}
+
+impl Default for AppConfig {
+    /// The built-in defaults, ignoring the environment
+    fn default() -> Self {
+        Self::from_lookup(|_| None)
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ConfigError {
+    #[error("CORS_ALLOW_CREDENTIALS requires ALLOWED_ORIGIN to list concrete origins, not *")]
+    WildcardOriginWithCredentials,
+    #[error("Failed to read config file {path}: {message}")]
+    Read { path: String, message: String },
+    #[error("Malformed config file {path}: {message}")]
+    Malformed { path: String, message: String },
 }
 
 impl AppConfig {
+    /// Configuration from environment variables alone
     pub fn from_env() -> Self {
+        Self::from_lookup(|name| env::var(name).ok())
+    }
+
+    /// Defaults from an optional JSON file, overridden by any environment
+    /// variables that are set. The file is an object keyed by the same names
+    /// as the environment (`{"TABLE_NAME": "items", "MAX_PAGE_SIZE": 50}`);
+    /// a path that doesn't exist is treated as an empty file.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        Self::load_with(path, |name| env::var(name).ok())
+    }
+
+    fn load_with(
+        path: Option<&Path>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let file = match path {
+            Some(path) => read_config_file(path)?,
+            None => HashMap::new(),
+        };
+        Ok(Self::from_lookup(|name| {
+            env(name).or_else(|| file.get(name).cloned())
+        }))
+    }
+
+    /// Build from a name -> raw value source; unparseable values fall back
+    /// to the defaults
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
         Self {
-            table_name: env::var("TABLE_NAME").unwrap_or_else(|_| "items".to_string()),
-            storage_bucket: env::var("STORAGE_BUCKET").unwrap_or_else(|_| "storage".to_string()),
+            table_name: lookup("TABLE_NAME").unwrap_or_else(|| "items".to_string()),
+            storage_bucket: lookup("STORAGE_BUCKET").unwrap_or_else(|| "storage".to_string()),
+            max_body_bytes: lookup("MAX_BODY_BYTES")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(64 * 1024),
+            name_max_len: lookup("NAME_MAX_LEN")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_NAME_MAX_LEN),
+            description_max_len: lookup("DESCRIPTION_MAX_LEN")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_DESCRIPTION_MAX_LEN),
+            default_page_size: lookup("DEFAULT_PAGE_SIZE")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(50),
+            max_page_size: lookup("MAX_PAGE_SIZE")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100),
+            rate_limit_burst: lookup("RATE_LIMIT_BURST")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(20),
+            rate_limit_per_second: lookup("RATE_LIMIT_PER_SECOND")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5.0),
+            item_cache_max_age: lookup("ITEM_CACHE_MAX_AGE")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            allowed_origins: parse_origins(lookup("ALLOWED_ORIGIN").as_deref()),
+            cors_max_age: lookup("CORS_MAX_AGE")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(600),
+            cors_allow_credentials: lookup("CORS_ALLOW_CREDENTIALS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            read_only: lookup("READ_ONLY")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            debug_errors: lookup("DEBUG_ERRORS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            public_reads: lookup("PUBLIC_READS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            id_scheme: IdScheme::parse(lookup("ID_SCHEME").as_deref()),
+            handler_timeout_ms: lookup("HANDLER_TIMEOUT_MS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(27_000),
+            export_max_items: lookup("EXPORT_MAX_ITEMS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5_000),
+            allowed_upload_types: parse_upload_types(lookup("ALLOWED_UPLOAD_TYPES").as_deref()),
+            max_upload_bytes: lookup("MAX_UPLOAD_BYTES")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10 * 1024 * 1024),
+            ttl_attribute: lookup("TTL_ATTRIBUTE")
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_TTL_ATTRIBUTE.to_string()),
+        }
+    }
+
+    /// How entities are laid out in the table, for `Repository::new`
+    pub fn table_settings(&self) -> TableSettings {
+        TableSettings {
+            ttl_attribute: self.ttl_attribute.clone(),
+            item_limits: self.item_limits(),
+        }
+    }
+
+    /// Field limits items are checked against again when they are written
+    pub fn item_limits(&self) -> ItemLimits {
+        ItemLimits {
+            name_max_len: self.name_max_len,
+            description_max_len: self.description_max_len,
+        }
+    }
+
+    /// Reject combinations browsers would refuse anyway; checked once at startup
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.cors_allow_credentials && self.allowed_origins.iter().any(|o| o == "*") {
+            return Err(ConfigError::WildcardOriginWithCredentials);
+        }
+        Ok(())
+    }
+}
+
+/// Flatten a JSON config file into the string values env vars would carry
+fn read_config_file(path: &Path) -> Result<HashMap<String, String>, ConfigError> {
+    let display = path.display().to_string();
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => {
+            return Err(ConfigError::Read {
+                path: display,
+                message: e.to_string(),
+            })
+        }
+    };
+    let malformed = |message: String| ConfigError::Malformed {
+        path: display.clone(),
+        message,
+    };
+
+    let Value::Object(entries) =
+        serde_json::from_str(&text).map_err(|e| malformed(e.to_string()))?
+    else {
+        return Err(malformed("expected a JSON object".to_string()));
+    };
+    entries
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(value) => value,
+                Value::Number(value) => value.to_string(),
+                Value::Bool(value) => value.to_string(),
+                // Lists such as ALLOWED_ORIGIN use the env var's comma form
+                Value::Array(values) => values
+                    .iter()
+                    .map(|v| v.as_str().map(String::from))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| malformed(format!("{name} must be a list of strings")))?
+                    .join(","),
+                _ => {
+                    return Err(malformed(format!(
+                        "{name} must be a string, number or boolean"
+                    )))
+                }
+            };
+            Ok((name, value))
+        })
+        .collect()
+}
+
+/// Upload content types allowed unless `ALLOWED_UPLOAD_TYPES` is set: images,
+/// PDFs and plain text, never executables
+const DEFAULT_UPLOAD_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "application/pdf",
+    "text/plain",
+    "text/csv",
+];
+
+/// Lowercased content types from a comma-separated list, or the defaults
+fn parse_upload_types(value: Option<&str>) -> Vec<String> {
+    let types: Vec<String> = value
+        .unwrap_or("")
+        .split(',')
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if types.is_empty() {
+        DEFAULT_UPLOAD_TYPES.iter().map(|t| t.to_string()).collect()
+    } else {
+        types
+    }
+}
+
+/// Split a comma-separated origin list, defaulting to `*` when unset or empty
+fn parse_origins(value: Option<&str>) -> Vec<String> {
+    let origins: Vec<String> = value
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(String::from)
+        .collect();
+    if origins.is_empty() {
+        vec!["*".to_string()]
+    } else {
+        origins
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_origins() {
+        assert_eq!(parse_origins(None), vec!["*"]);
+        assert_eq!(parse_origins(Some(" ")), vec!["*"]);
+        assert_eq!(
+            parse_origins(Some("https://a.example, https://b.example")),
+            vec!["https://a.example", "https://b.example"]
+        );
+    }
+
+    #[test]
+    fn test_parse_upload_types() {
+        assert_eq!(parse_upload_types(None).len(), DEFAULT_UPLOAD_TYPES.len());
+        assert_eq!(
+            parse_upload_types(Some(" Image/PNG, text/plain ,")),
+            vec!["image/png", "text/plain"]
+        );
+    }
+
+    #[test]
+    fn test_credentials_with_wildcard_origin_rejected() {
+        let config = AppConfig {
+            allowed_origins: vec!["*".to_string()],
+            cors_allow_credentials: true,
+            ..AppConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::WildcardOriginWithCredentials)
+        );
+
+        let config = AppConfig {
+            allowed_origins: vec!["https://app.example".to_string()],
+            ..config
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    /// Write `contents` to a file unique to this test, returning its path
+    fn config_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = env::temp_dir().join(format!("app-config-{}-{name}.json", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_load_from_file_only() {
+        let path = config_file(
+            "file-only",
+            r#"{"TABLE_NAME":"dev-items","MAX_PAGE_SIZE":25,"READ_ONLY":true,
+                "ALLOWED_ORIGIN":["http://localhost:5173","http://127.0.0.1:5173"]}"#,
+        );
+
+        let config = AppConfig::load_with(Some(&path), no_env).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.table_name, "dev-items");
+        assert_eq!(config.max_page_size, 25);
+        assert!(config.read_only);
+        assert_eq!(
+            config.allowed_origins,
+            vec!["http://localhost:5173", "http://127.0.0.1:5173"]
+        );
+        // Unset everywhere: the built-in default
+        assert_eq!(config.default_page_size, 50);
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let path = config_file(
+            "env-override",
+            r#"{"TABLE_NAME":"dev-items","MAX_PAGE_SIZE":25}"#,
+        );
+
+        let config = AppConfig::load_with(Some(&path), |name| {
+            (name == "TABLE_NAME").then(|| "from-env".to_string())
+        })
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.table_name, "from-env");
+        assert_eq!(config.max_page_size, 25);
+    }
+
+    #[test]
+    fn test_missing_file_uses_env_and_defaults() {
+        let path = env::temp_dir().join("app-config-does-not-exist.json");
+
+        let config = AppConfig::load_with(Some(&path), |name| {
+            (name == "STORAGE_BUCKET").then(|| "bucket".to_string())
+        })
+        .unwrap();
+
+        assert_eq!(config.storage_bucket, "bucket");
+        assert_eq!(config.table_name, "items");
+        assert_eq!(config.ttl_attribute, "ttl");
+        assert!(!config.debug_errors);
+        assert!(AppConfig::load_with(None, no_env).is_ok());
+    }
+
+    #[test]
+    fn test_malformed_file_is_an_error() {
+        for (name, contents) in [
+            ("not-json", "TABLE_NAME=items"),
+            ("not-object", r#"["items"]"#),
+            ("nested", r#"{"TABLE_NAME":{"name":"items"}}"#),
+            ("bad-list", r#"{"ALLOWED_ORIGIN":[1,2]}"#),
+        ] {
+            let path = config_file(name, contents);
+            let result = AppConfig::load_with(Some(&path), no_env);
+            std::fs::remove_file(&path).unwrap();
+
+            assert!(
+                matches!(result, Err(ConfigError::Malformed { .. })),
+                "{name}: {result:?}"
+            );
         }
     }
 }
@@ -0,0 +1,136 @@
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// How new item ids are generated (`ID_SCHEME`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdScheme {
+    /// Random v4 UUIDs; the default, as existing ids are UUIDs
+    #[default]
+    Uuid,
+    /// ULIDs, which sort lexicographically in creation order
+    Ulid,
+}
+
+impl IdScheme {
+    /// `ulid` (any case) selects ULIDs; anything else keeps UUIDs
+    pub fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some(value) if value.trim().eq_ignore_ascii_case("ulid") => IdScheme::Ulid,
+            _ => IdScheme::Uuid,
+        }
+    }
+
+    pub fn generate(self) -> String {
+        match self {
+            IdScheme::Uuid => Uuid::new_v4().to_string(),
+            IdScheme::Ulid => new_ulid(),
+        }
+    }
+}
+
+/// Crockford base32, the ULID alphabet
+const ULID_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+const ULID_RANDOM_BITS: u32 = 80;
+
+/// Last (timestamp ms, random) pair handed out, so ids from one process stay
+/// strictly increasing even within a millisecond
+static LAST_ULID: Mutex<Option<(u64, u128)>> = Mutex::new(None);
+
+/// A 26-character ULID: 48-bit millisecond timestamp then 80 random bits
+pub fn new_ulid() -> String {
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    let random = Uuid::new_v4().as_u128() & ((1 << ULID_RANDOM_BITS) - 1);
+
+    let mut last = LAST_ULID.lock().unwrap_or_else(|e| e.into_inner());
+    let (ms, random) = next_ulid_parts(*last, now_ms, random);
+    *last = Some((ms, random));
+    encode_ulid(ms, random)
+}
+
+/// Fresh parts for a later millisecond; otherwise increment the previous
+/// random part (carrying into the timestamp if it overflows) so the new id
+/// sorts after the last one even if the clock stalled or stepped back
+fn next_ulid_parts(last: Option<(u64, u128)>, now_ms: u64, random: u128) -> (u64, u128) {
+    match last {
+        Some((last_ms, last_random)) if now_ms <= last_ms => {
+            let next = last_random + 1;
+            if next >> ULID_RANDOM_BITS == 0 {
+                (last_ms, next)
+            } else {
+                (last_ms + 1, 0)
+            }
+        }
+        _ => (now_ms, random),
+    }
+}
+
+fn encode_ulid(ms: u64, random: u128) -> String {
+    let value = (u128::from(ms) << ULID_RANDOM_BITS) | random;
+    (0..26)
+        .rev()
+        .map(|i| ULID_ALPHABET[((value >> (5 * i)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_scheme_parse() {
+        assert_eq!(IdScheme::parse(None), IdScheme::Uuid);
+        assert_eq!(IdScheme::parse(Some("uuid")), IdScheme::Uuid);
+        assert_eq!(IdScheme::parse(Some("other")), IdScheme::Uuid);
+        assert_eq!(IdScheme::parse(Some(" ULID ")), IdScheme::Ulid);
+    }
+
+    #[test]
+    fn test_id_scheme_selects_generator() {
+        let uuid = IdScheme::Uuid.generate();
+        assert!(Uuid::parse_str(&uuid).is_ok());
+
+        let ulid = IdScheme::Ulid.generate();
+        assert_eq!(ulid.len(), 26);
+        assert!(ulid.bytes().all(|b| ULID_ALPHABET.contains(&b)));
+    }
+
+    #[test]
+    fn test_encode_ulid_known_values() {
+        assert_eq!(encode_ulid(0, 0), "00000000000000000000000000");
+        assert_eq!(encode_ulid(0, 1), "00000000000000000000000001");
+        // Timestamp occupies the first 10 characters
+        assert_eq!(&encode_ulid(1, 0)[..10], "0000000001");
+        assert_eq!(
+            encode_ulid((1 << 48) - 1, (1 << ULID_RANDOM_BITS) - 1),
+            "7ZZZZZZZZZZZZZZZZZZZZZZZZZ"
+        );
+    }
+
+    #[test]
+    fn test_ulids_in_sequence_sort_in_creation_order() {
+        let ids: Vec<String> = (0..1000).map(|_| new_ulid()).collect();
+
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(ids, sorted);
+        sorted.dedup();
+        assert_eq!(sorted.len(), ids.len());
+    }
+
+    #[test]
+    fn test_next_ulid_parts_is_monotonic() {
+        // A later millisecond takes the fresh random part
+        assert_eq!(next_ulid_parts(Some((5, 9)), 6, 3), (6, 3));
+        // Same millisecond or a clock step back increments instead
+        assert_eq!(next_ulid_parts(Some((5, 9)), 5, 3), (5, 10));
+        assert_eq!(next_ulid_parts(Some((5, 9)), 4, 3), (5, 10));
+        // Overflowing the random bits carries into the timestamp
+        let max = (1 << ULID_RANDOM_BITS) - 1;
+        assert_eq!(next_ulid_parts(Some((5, max)), 5, 3), (6, 0));
+        assert!(encode_ulid(6, 0) > encode_ulid(5, max));
+    }
+}
@@ -1,2 +1,6 @@
 pub mod config;
+pub mod ids;
 pub mod models;
+pub mod repository;
+pub mod retry;
+pub mod text;
@@ -1,6 +1,9 @@
+use crate::repository::{DynamoEntity, TableSettings};
 use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -9,6 +12,8 @@ pub enum ModelError {
     MissingAttribute(String),
     #[error("Invalid attribute type for {0}")]
     InvalidType(String),
+    #[error("{field} exceeds the maximum length of {max}")]
+    TooLong { field: String, max: usize },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,16 +24,292 @@ pub struct Item {
     pub description: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// Id of the user who created the item ("unknown" for legacy items)
+    pub created_by: String,
+    /// Epoch seconds after which the item expires and DynamoDB TTL deletes it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// User-facing identifier, unique per owner (see `SlugClaim`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    /// RFC3339 time the item was soft-deleted; such items are kept, but
+    /// `GET` and `PATCH` answer 410 Gone and lists and exports skip them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    /// Free-form string key/value pairs, stored as a DynamoDB map
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+    /// Visible to anonymous readers when public reads are enabled
+    #[serde(default)]
+    pub public: bool,
+}
+
+/// Placeholder owner for items written before `created_by` existed
+pub const UNKNOWN_CREATOR: &str = "unknown";
+
+/// Attribute the table's TTL is configured on unless `TTL_ATTRIBUTE` says
+/// otherwise (see infra/data.tf)
+pub const DEFAULT_TTL_ATTRIBUTE: &str = "ttl";
+
+/// Longest item name, in characters, unless `NAME_MAX_LEN` says otherwise
+pub const DEFAULT_NAME_MAX_LEN: usize = 256;
+
+/// Longest item description, in bytes, unless `DESCRIPTION_MAX_LEN` says otherwise
+pub const DEFAULT_DESCRIPTION_MAX_LEN: usize = 4096;
+
+/// Field limits every stored item must meet. Request validation enforces
+/// the same ones; checking them again on write covers paths that skip it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItemLimits {
+    /// Characters (Unicode scalar values)
+    pub name_max_len: usize,
+    /// Bytes
+    pub description_max_len: usize,
+}
+
+impl Default for ItemLimits {
+    fn default() -> Self {
+        Self {
+            name_max_len: DEFAULT_NAME_MAX_LEN,
+            description_max_len: DEFAULT_DESCRIPTION_MAX_LEN,
+        }
+    }
 }
 
 impl Item {
-    pub fn from_dynamo(attrs: &HashMap<String, AttributeValue>) -> Result<Self, ModelError> {
+    /// Check the name and description against `limits`
+    pub fn check_limits(&self, limits: &ItemLimits) -> Result<(), ModelError> {
+        if self.name.chars().count() > limits.name_max_len {
+            return Err(ModelError::TooLong {
+                field: "name".to_string(),
+                max: limits.name_max_len,
+            });
+        }
+        if self
+            .description
+            .as_ref()
+            .is_some_and(|d| d.len() > limits.description_max_len)
+        {
+            return Err(ModelError::TooLong {
+                field: "description".to_string(),
+                max: limits.description_max_len,
+            });
+        }
+        Ok(())
+    }
+
+    /// `gsi2pk` of the items owned by `user_id`
+    pub fn gsi2pk_for(user_id: &str) -> String {
+        format!("USER#{user_id}")
+    }
+
+    /// `gsi2sk` for a name, or for a name prefix when querying with
+    /// `begins_with`; lowercased so prefix lookups are case-insensitive
+    pub fn gsi2sk_for(name: &str) -> String {
+        format!("NAME#{}", name.to_lowercase())
+    }
+
+    /// `gsi4pk` of every user's public items
+    pub fn public_gsi4pk() -> String {
+        "PUBLIC".to_string()
+    }
+
+    /// `gsi4pk` of the private items owned by `user_id`
+    pub fn private_gsi4pk_for(user_id: &str) -> String {
+        format!("PRIVATE#{user_id}")
+    }
+
+    /// Visibility partition the item is listed under in gsi4, or `None`
+    /// once it is soft-deleted and should drop out of lists
+    fn gsi4pk(&self) -> Option<String> {
+        match (&self.deleted_at, self.public) {
+            (Some(_), _) => None,
+            (None, true) => Some(Self::public_gsi4pk()),
+            (None, false) => Some(Self::private_gsi4pk_for(&self.created_by)),
+        }
+    }
+
+    pub fn created_at_utc(&self) -> Option<DateTime<Utc>> {
+        parse_timestamp(&self.created_at)
+    }
+
+    pub fn updated_at_utc(&self) -> Option<DateTime<Utc>> {
+        parse_timestamp(&self.updated_at)
+    }
+}
+
+impl DynamoEntity for Item {
+    const ENTITY_TYPE: &'static str = "ITEM";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    fn check_invariants(&self, settings: &TableSettings) -> Result<(), ModelError> {
+        self.check_limits(&settings.item_limits)
+    }
+
+    fn to_dynamo(&self, settings: &TableSettings) -> HashMap<String, AttributeValue> {
+        let mut attrs = HashMap::from([
+            ("pk".to_string(), AttributeValue::S(self.pk())),
+            ("sk".to_string(), AttributeValue::S(self.sk())),
+            ("id".to_string(), AttributeValue::S(self.id.clone())),
+            ("name".to_string(), AttributeValue::S(self.name.clone())),
+            (
+                "description".to_string(),
+                self.description
+                    .as_ref()
+                    .map(|d| AttributeValue::S(d.clone()))
+                    .unwrap_or(AttributeValue::Null(true)),
+            ),
+            (
+                "created_at".to_string(),
+                AttributeValue::S(self.created_at.clone()),
+            ),
+            (
+                "updated_at".to_string(),
+                AttributeValue::S(self.updated_at.clone()),
+            ),
+            (
+                "created_by".to_string(),
+                AttributeValue::S(self.created_by.clone()),
+            ),
+            ("public".to_string(), AttributeValue::Bool(self.public)),
+            ("gsi1pk".to_string(), AttributeValue::S(self.pk())),
+            (
+                "gsi1sk".to_string(),
+                AttributeValue::S(self.created_at.clone()),
+            ),
+            (
+                "gsi2pk".to_string(),
+                AttributeValue::S(Self::gsi2pk_for(&self.created_by)),
+            ),
+            (
+                "gsi2sk".to_string(),
+                AttributeValue::S(Self::gsi2sk_for(&self.name)),
+            ),
+            ("gsi3pk".to_string(), AttributeValue::S(self.pk())),
+            (
+                "gsi3sk".to_string(),
+                AttributeValue::S(self.updated_at.clone()),
+            ),
+        ]);
+        if let Some(gsi4pk) = self.gsi4pk() {
+            attrs.insert("gsi4pk".to_string(), AttributeValue::S(gsi4pk));
+            attrs.insert("gsi4sk".to_string(), AttributeValue::S(self.sk()));
+        }
+        if let Some(slug) = &self.slug {
+            attrs.insert("slug".to_string(), AttributeValue::S(slug.clone()));
+        }
+        if let Some(deleted_at) = &self.deleted_at {
+            attrs.insert(
+                "deleted_at".to_string(),
+                AttributeValue::S(deleted_at.clone()),
+            );
+        }
+        if !self.metadata.is_empty() {
+            attrs.insert(
+                "metadata".to_string(),
+                AttributeValue::M(
+                    self.metadata
+                        .iter()
+                        .map(|(key, value)| (key.clone(), AttributeValue::S(value.clone())))
+                        .collect(),
+                ),
+            );
+        }
+        if let Some(expires_at) = self.expires_at {
+            attrs.insert(
+                settings.ttl_attribute.clone(),
+                AttributeValue::N(expires_at.to_string()),
+            );
+        }
+        attrs
+    }
+
+    fn from_dynamo(
+        attrs: &HashMap<String, AttributeValue>,
+        settings: &TableSettings,
+    ) -> Result<Self, ModelError> {
         Ok(Self {
             id: get_string(attrs, "id")?,
             name: get_string(attrs, "name")?,
             description: get_optional_string(attrs, "description"),
-            created_at: get_string(attrs, "created_at")?,
-            updated_at: get_string(attrs, "updated_at")?,
+            created_at: get_timestamp(attrs, "created_at")?,
+            updated_at: get_timestamp(attrs, "updated_at")?,
+            created_by: get_optional_string(attrs, "created_by")
+                .unwrap_or_else(|| UNKNOWN_CREATOR.to_string()),
+            expires_at: get_optional_number(attrs, &settings.ttl_attribute)?,
+            slug: get_optional_string(attrs, "slug"),
+            deleted_at: get_optional_string(attrs, "deleted_at"),
+            metadata: get_string_map(attrs, "metadata")?,
+            // Items written before the flag existed are private
+            public: get_optional_bool(attrs, "public")?.unwrap_or(false),
+        })
+    }
+}
+
+/// Marker row reserving a slug for one owner. Written with a conditional put
+/// before the item that uses the slug, which makes slugs unique per user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlugClaim {
+    /// `{owner}#{slug}`, so the sort key is `SLUG#{owner}#{slug}`
+    pub id: String,
+    pub owner: String,
+    pub slug: String,
+    /// Item the slug belongs to
+    pub item_id: String,
+}
+
+impl SlugClaim {
+    pub fn new(owner: &str, slug: &str, item_id: &str) -> Self {
+        Self {
+            id: Self::id_for(owner, slug),
+            owner: owner.to_string(),
+            slug: slug.to_string(),
+            item_id: item_id.to_string(),
+        }
+    }
+
+    pub fn id_for(owner: &str, slug: &str) -> String {
+        format!("{owner}#{slug}")
+    }
+}
+
+impl DynamoEntity for SlugClaim {
+    const ENTITY_TYPE: &'static str = "SLUG";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn to_dynamo(&self, _settings: &TableSettings) -> HashMap<String, AttributeValue> {
+        HashMap::from([
+            ("pk".to_string(), AttributeValue::S(self.pk())),
+            ("sk".to_string(), AttributeValue::S(self.sk())),
+            ("id".to_string(), AttributeValue::S(self.id.clone())),
+            ("owner".to_string(), AttributeValue::S(self.owner.clone())),
+            ("slug".to_string(), AttributeValue::S(self.slug.clone())),
+            (
+                "item_id".to_string(),
+                AttributeValue::S(self.item_id.clone()),
+            ),
+        ])
+    }
+
+    fn from_dynamo(
+        attrs: &HashMap<String, AttributeValue>,
+        _settings: &TableSettings,
+    ) -> Result<Self, ModelError> {
+        Ok(Self {
+            id: get_string(attrs, "id")?,
+            owner: get_string(attrs, "owner")?,
+            slug: get_string(attrs, "slug")?,
+            item_id: get_string(attrs, "item_id")?,
         })
     }
 }
@@ -47,3 +328,431 @@ fn get_optional_string(attrs: &HashMap<String, AttributeValue>, key: &str) -> Op
         .and_then(|v| v.as_s().ok())
         .map(|s| s.to_string())
 }
+
+/// Parse an RFC3339 timestamp into UTC
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Read a required string attribute that must be an RFC3339 timestamp
+pub fn get_timestamp(
+    attrs: &HashMap<String, AttributeValue>,
+    key: &str,
+) -> Result<String, ModelError> {
+    let value = get_string(attrs, key)?;
+    parse_timestamp(&value).ok_or_else(|| ModelError::InvalidType(key.to_string()))?;
+    Ok(value)
+}
+
+/// Parse a required DynamoDB number (`N`) attribute
+pub fn get_number<T: FromStr>(
+    attrs: &HashMap<String, AttributeValue>,
+    key: &str,
+) -> Result<T, ModelError> {
+    get_optional_number(attrs, key)?.ok_or_else(|| ModelError::MissingAttribute(key.to_string()))
+}
+
+/// Parse an optional DynamoDB number (`N`) attribute; present but malformed is an error
+pub fn get_optional_number<T: FromStr>(
+    attrs: &HashMap<String, AttributeValue>,
+    key: &str,
+) -> Result<Option<T>, ModelError> {
+    match attrs.get(key) {
+        None | Some(AttributeValue::Null(_)) => Ok(None),
+        Some(value) => value
+            .as_n()
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .map(Some)
+            .ok_or_else(|| ModelError::InvalidType(key.to_string())),
+    }
+}
+
+/// Parse an optional DynamoDB map (`M`) of string values; absent is empty,
+/// while a non-map or a non-string value is an error
+pub fn get_string_map(
+    attrs: &HashMap<String, AttributeValue>,
+    key: &str,
+) -> Result<HashMap<String, String>, ModelError> {
+    match attrs.get(key) {
+        None | Some(AttributeValue::Null(_)) => Ok(HashMap::new()),
+        Some(AttributeValue::M(map)) => map
+            .iter()
+            .map(|(name, value)| match value {
+                AttributeValue::S(value) => Ok((name.clone(), value.clone())),
+                _ => Err(ModelError::InvalidType(format!("{key}.{name}"))),
+            })
+            .collect(),
+        Some(_) => Err(ModelError::InvalidType(key.to_string())),
+    }
+}
+
+/// Parse a required DynamoDB boolean (`BOOL`) attribute
+pub fn get_bool(attrs: &HashMap<String, AttributeValue>, key: &str) -> Result<bool, ModelError> {
+    match attrs.get(key) {
+        None => Err(ModelError::MissingAttribute(key.to_string())),
+        Some(value) => value
+            .as_bool()
+            .copied()
+            .map_err(|_| ModelError::InvalidType(key.to_string())),
+    }
+}
+
+/// Parse an optional DynamoDB boolean (`BOOL`) attribute
+pub fn get_optional_bool(
+    attrs: &HashMap<String, AttributeValue>,
+    key: &str,
+) -> Result<Option<bool>, ModelError> {
+    match attrs.get(key) {
+        None | Some(AttributeValue::Null(_)) => Ok(None),
+        Some(_) => get_bool(attrs, key).map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(key: &str, value: AttributeValue) -> HashMap<String, AttributeValue> {
+        HashMap::from([(key.to_string(), value)])
+    }
+
+    #[test]
+    fn test_get_number_valid() {
+        let attrs = attrs("version", AttributeValue::N("42".to_string()));
+        assert_eq!(get_number::<u64>(&attrs, "version").unwrap(), 42);
+        assert_eq!(
+            get_optional_number::<i64>(&attrs, "version").unwrap(),
+            Some(42)
+        );
+        assert_eq!(get_optional_number::<i64>(&attrs, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_number_invalid() {
+        let not_numeric = attrs("version", AttributeValue::N("abc".to_string()));
+        assert!(matches!(
+            get_number::<u64>(&not_numeric, "version"),
+            Err(ModelError::InvalidType(_))
+        ));
+
+        let wrong_type = attrs("version", AttributeValue::S("42".to_string()));
+        assert!(matches!(
+            get_optional_number::<u64>(&wrong_type, "version"),
+            Err(ModelError::InvalidType(_))
+        ));
+
+        assert!(matches!(
+            get_number::<u64>(&HashMap::new(), "version"),
+            Err(ModelError::MissingAttribute(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_dynamo_tolerates_missing_created_by() {
+        let attrs = HashMap::from([
+            ("id".to_string(), AttributeValue::S("abc".to_string())),
+            ("name".to_string(), AttributeValue::S("Legacy".to_string())),
+            (
+                "created_at".to_string(),
+                AttributeValue::S("2024-01-01T00:00:00+00:00".to_string()),
+            ),
+            (
+                "updated_at".to_string(),
+                AttributeValue::S("2024-01-01T00:00:00+00:00".to_string()),
+            ),
+        ]);
+
+        let item = Item::from_dynamo(&attrs, &TableSettings::default()).unwrap();
+        assert_eq!(item.created_by, UNKNOWN_CREATOR);
+    }
+
+    #[test]
+    fn test_get_bool_missing_vs_present() {
+        let present = attrs("archived", AttributeValue::Bool(true));
+        assert!(get_bool(&present, "archived").unwrap());
+
+        assert!(matches!(
+            get_bool(&HashMap::new(), "archived"),
+            Err(ModelError::MissingAttribute(_))
+        ));
+
+        let wrong_type = attrs("archived", AttributeValue::S("true".to_string()));
+        assert!(matches!(
+            get_bool(&wrong_type, "archived"),
+            Err(ModelError::InvalidType(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_timestamp_accepts_rfc3339() {
+        let attrs = attrs(
+            "created_at",
+            AttributeValue::S("2024-03-01T12:30:00+02:00".to_string()),
+        );
+
+        assert_eq!(
+            get_timestamp(&attrs, "created_at").unwrap(),
+            "2024-03-01T12:30:00+02:00"
+        );
+        let parsed = parse_timestamp("2024-03-01T12:30:00+02:00").unwrap();
+        assert_eq!(parsed.to_rfc3339(), "2024-03-01T10:30:00+00:00");
+    }
+
+    #[test]
+    fn test_get_timestamp_rejects_garbage() {
+        let attrs = attrs("created_at", AttributeValue::S("yesterday".to_string()));
+
+        assert!(matches!(
+            get_timestamp(&attrs, "created_at"),
+            Err(ModelError::InvalidType(_))
+        ));
+    }
+
+    #[test]
+    fn test_to_dynamo_writes_gsi2_keys() {
+        let item = Item {
+            id: "abc".to_string(),
+            name: "Blue Widget".to_string(),
+            description: None,
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+            created_by: "user-1".to_string(),
+            expires_at: None,
+            slug: None,
+            deleted_at: None,
+            metadata: HashMap::new(),
+            public: false,
+        };
+
+        let attrs = item.to_dynamo(&TableSettings::default());
+
+        assert_eq!(attrs["gsi2pk"].as_s().unwrap(), "USER#user-1");
+        assert_eq!(attrs["gsi2sk"].as_s().unwrap(), "NAME#blue widget");
+        assert!(attrs["gsi2sk"]
+            .as_s()
+            .unwrap()
+            .starts_with(&Item::gsi2sk_for("BLUE")));
+    }
+
+    #[test]
+    fn test_to_dynamo_lists_live_items_by_visibility_in_gsi4() {
+        let mut item = Item {
+            id: "abc".to_string(),
+            name: "Widget".to_string(),
+            description: None,
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+            created_by: "user-1".to_string(),
+            expires_at: None,
+            slug: None,
+            deleted_at: None,
+            metadata: HashMap::new(),
+            public: false,
+        };
+        let settings = TableSettings::default();
+
+        let attrs = item.to_dynamo(&settings);
+        assert_eq!(attrs["gsi4pk"].as_s().unwrap(), "PRIVATE#user-1");
+        assert_eq!(attrs["gsi4sk"], attrs["sk"]);
+
+        item.public = true;
+        let attrs = item.to_dynamo(&settings);
+        assert_eq!(attrs["gsi4pk"].as_s().unwrap(), "PUBLIC");
+
+        item.deleted_at = Some("2024-06-01T00:00:00+00:00".to_string());
+        let attrs = item.to_dynamo(&settings);
+        assert!(!attrs.contains_key("gsi4pk"));
+        assert!(!attrs.contains_key("gsi4sk"));
+    }
+
+    #[test]
+    fn test_metadata_round_trips_as_a_map() {
+        let item = Item {
+            id: "abc".to_string(),
+            name: "Widget".to_string(),
+            description: None,
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+            created_by: "user-1".to_string(),
+            expires_at: None,
+            slug: None,
+            deleted_at: None,
+            metadata: HashMap::from([
+                ("color".to_string(), "blue".to_string()),
+                ("sku".to_string(), "W-100".to_string()),
+            ]),
+            public: false,
+        };
+
+        let attrs = item.to_dynamo(&TableSettings::default());
+        let map = attrs["metadata"].as_m().unwrap();
+        assert_eq!(map["color"].as_s().unwrap(), "blue");
+
+        let parsed = Item::from_dynamo(&attrs, &TableSettings::default()).unwrap();
+        assert_eq!(parsed.metadata, item.metadata);
+
+        // Items without metadata don't write the attribute and read back empty
+        let plain = Item {
+            metadata: HashMap::new(),
+            ..item
+        };
+        let attrs = plain.to_dynamo(&TableSettings::default());
+        assert!(!attrs.contains_key("metadata"));
+        assert!(Item::from_dynamo(&attrs, &TableSettings::default())
+            .unwrap()
+            .metadata
+            .is_empty());
+    }
+
+    #[test]
+    fn test_public_flag_round_trips_and_defaults_to_private() {
+        let item = Item {
+            id: "abc".to_string(),
+            name: "Widget".to_string(),
+            description: None,
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+            created_by: "user-1".to_string(),
+            expires_at: None,
+            slug: None,
+            deleted_at: None,
+            metadata: HashMap::new(),
+            public: true,
+        };
+
+        let mut attrs = item.to_dynamo(&TableSettings::default());
+        assert_eq!(attrs["public"].as_bool().unwrap(), &true);
+        assert!(
+            Item::from_dynamo(&attrs, &TableSettings::default())
+                .unwrap()
+                .public
+        );
+
+        // Items written before the flag existed
+        attrs.remove("public");
+        assert!(
+            !Item::from_dynamo(&attrs, &TableSettings::default())
+                .unwrap()
+                .public
+        );
+
+        attrs.insert("public".to_string(), AttributeValue::S("yes".to_string()));
+        assert!(matches!(
+            Item::from_dynamo(&attrs, &TableSettings::default()),
+            Err(ModelError::InvalidType(field)) if field == "public"
+        ));
+    }
+
+    #[test]
+    fn test_to_dynamo_writes_ttl_under_configured_attribute() {
+        let item = Item {
+            id: "abc".to_string(),
+            name: "Widget".to_string(),
+            description: None,
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+            created_by: "user-1".to_string(),
+            expires_at: Some(1_700_000_000),
+            slug: None,
+            deleted_at: None,
+            metadata: HashMap::new(),
+            public: false,
+        };
+        assert_eq!(
+            item.to_dynamo(&TableSettings::default())[DEFAULT_TTL_ATTRIBUTE]
+                .as_n()
+                .unwrap(),
+            "1700000000"
+        );
+
+        let settings = TableSettings {
+            ttl_attribute: "expireAt".to_string(),
+            ..TableSettings::default()
+        };
+        let attrs = item.to_dynamo(&settings);
+
+        assert_eq!(attrs["expireAt"].as_n().unwrap(), "1700000000");
+        assert!(!attrs.contains_key(DEFAULT_TTL_ATTRIBUTE));
+        assert_eq!(
+            Item::from_dynamo(&attrs, &settings).unwrap().expires_at,
+            Some(1_700_000_000)
+        );
+    }
+
+    #[test]
+    fn test_over_limit_description_is_rejected_before_write() {
+        let item = Item {
+            id: "abc".to_string(),
+            name: "Widget".to_string(),
+            description: Some("x".repeat(DEFAULT_DESCRIPTION_MAX_LEN)),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+            created_by: "user-1".to_string(),
+            expires_at: None,
+            slug: None,
+            deleted_at: None,
+            metadata: HashMap::new(),
+            public: false,
+        };
+        assert!(item.check_invariants(&TableSettings::default()).is_ok());
+
+        let oversized = Item {
+            description: Some("x".repeat(DEFAULT_DESCRIPTION_MAX_LEN + 1)),
+            ..item.clone()
+        };
+        assert!(matches!(
+            oversized.check_invariants(&TableSettings::default()),
+            Err(ModelError::TooLong { field, max })
+                if field == "description" && max == DEFAULT_DESCRIPTION_MAX_LEN
+        ));
+
+        // Configured limits apply: bytes for descriptions, characters for names
+        let limits = ItemLimits {
+            name_max_len: 3,
+            description_max_len: 10,
+        };
+        let accented = Item {
+            name: "Été".to_string(),
+            description: Some("é".repeat(5)),
+            ..item
+        };
+        assert!(accented.check_limits(&limits).is_ok());
+        let longer = Item {
+            description: Some("é".repeat(6)),
+            ..accented.clone()
+        };
+        assert!(longer.check_limits(&limits).is_err());
+        let renamed = Item {
+            name: "Étés".to_string(),
+            ..accented
+        };
+        assert!(matches!(
+            renamed.check_limits(&limits),
+            Err(ModelError::TooLong { field, .. }) if field == "name"
+        ));
+    }
+
+    #[test]
+    fn test_get_string_map_rejects_non_string_values() {
+        let nested = attrs(
+            "metadata",
+            AttributeValue::M(HashMap::from([(
+                "count".to_string(),
+                AttributeValue::N("3".to_string()),
+            )])),
+        );
+        assert!(matches!(
+            get_string_map(&nested, "metadata"),
+            Err(ModelError::InvalidType(field)) if field == "metadata.count"
+        ));
+
+        let wrong_type = attrs("metadata", AttributeValue::S("{}".to_string()));
+        assert!(matches!(
+            get_string_map(&wrong_type, "metadata"),
+            Err(ModelError::InvalidType(_))
+        ));
+    }
+}
@@ -0,0 +1,1686 @@
+use crate::models::{ItemLimits, ModelError, DEFAULT_TTL_ATTRIBUTE};
+use crate::retry::retry_dynamo;
+use aws_sdk_dynamodb::error::BuildError;
+use aws_sdk_dynamodb::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_dynamodb::operation::get_item::builders::GetItemFluentBuilder;
+use aws_sdk_dynamodb::operation::query::builders::QueryFluentBuilder;
+use aws_sdk_dynamodb::operation::query::QueryOutput;
+use aws_sdk_dynamodb::types::{
+    AttributeValue, DeleteRequest, PutRequest, ReturnValue, Select, WriteRequest,
+};
+use aws_sdk_dynamodb::Client;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Maximum number of writes DynamoDB accepts in a single BatchWriteItem
+pub const MAX_BATCH_WRITE: usize = 25;
+
+/// Global secondary index keyed on `gsi1pk` / `gsi1sk`
+const GSI1: &str = "gsi1";
+
+/// Global secondary index keyed on `gsi2pk` / `gsi2sk`
+const GSI2: &str = "gsi2";
+
+/// Global secondary index keyed on `gsi3pk` / `gsi3sk`
+const GSI3: &str = "gsi3";
+
+/// Global secondary index keyed on `gsi4pk` / `gsi4sk`
+const GSI4: &str = "gsi4";
+
+/// How many times unprocessed batch writes are resubmitted
+const UNPROCESSED_RETRY_ROUNDS: u32 = 2;
+
+/// Most DynamoDB requests one query makes while filling a page
+const MAX_QUERY_ROUND_TRIPS: usize = 5;
+
+#[derive(Debug, Error)]
+pub enum RepositoryError {
+    #[error("Conditional check failed")]
+    ConditionFailed,
+    #[error("Item exceeds the DynamoDB item size limit")]
+    ItemTooLarge,
+    #[error("DynamoDB error: {message}")]
+    Dynamo {
+        code: Option<String>,
+        message: String,
+    },
+    #[error(transparent)]
+    Model(#[from] ModelError),
+}
+
+impl<E: ProvideErrorMetadata, R> From<SdkError<E, R>> for RepositoryError {
+    fn from(err: SdkError<E, R>) -> Self {
+        if err.code() == Some("ConditionalCheckFailedException") {
+            return RepositoryError::ConditionFailed;
+        }
+
+        if err.code() == Some("ValidationException")
+            && err.message().is_some_and(is_item_size_message)
+        {
+            return RepositoryError::ItemTooLarge;
+        }
+
+        let code = err.code().map(String::from);
+        let message = err
+            .message()
+            .map(String::from)
+            .or_else(|| code.clone())
+            .unwrap_or_else(|| "request failed".to_string());
+        RepositoryError::Dynamo { code, message }
+    }
+}
+
+/// DynamoDB reports the 400KB limit as "Item size has exceeded ..." or
+/// "Item size to update has exceeded ..."
+fn is_item_size_message(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    message.contains("item size") && message.contains("exceeded")
+}
+
+impl From<BuildError> for RepositoryError {
+    fn from(err: BuildError) -> Self {
+        RepositoryError::Dynamo {
+            code: None,
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Per-deployment details of how entities are stored in the table, taken
+/// from `AppConfig` and handed to every conversion to and from attributes
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableSettings {
+    /// Attribute the table's TTL is configured on
+    pub ttl_attribute: String,
+    /// Field limits items are checked against before every write
+    pub item_limits: ItemLimits,
+}
+
+impl Default for TableSettings {
+    fn default() -> Self {
+        Self {
+            ttl_attribute: DEFAULT_TTL_ATTRIBUTE.to_string(),
+            item_limits: ItemLimits::default(),
+        }
+    }
+}
+
+/// An entity stored in the single DynamoDB table
+pub trait DynamoEntity: Sized {
+    /// Type tag used as the partition key and sort key prefix
+    const ENTITY_TYPE: &'static str;
+
+    /// Partition key for the entity with the given id
+    fn pk_for(_id: &str) -> String {
+        Self::ENTITY_TYPE.to_string()
+    }
+
+    /// Sort key for the entity with the given id
+    fn sk_for(id: &str) -> String {
+        format!("{}#{id}", Self::ENTITY_TYPE)
+    }
+
+    fn id(&self) -> &str;
+
+    /// Whether the entity has passed its TTL. DynamoDB reaps expired items
+    /// lazily (up to days later), so reads filter them out themselves.
+    fn is_expired(&self, _now: u64) -> bool {
+        false
+    }
+
+    /// Reject an entity that breaks a rule request validation is meant to
+    /// guarantee; checked by `stored_attributes` before every write
+    fn check_invariants(&self, _settings: &TableSettings) -> Result<(), ModelError> {
+        Ok(())
+    }
+
+    fn to_dynamo(&self, settings: &TableSettings) -> HashMap<String, AttributeValue>;
+    fn from_dynamo(
+        attrs: &HashMap<String, AttributeValue>,
+        settings: &TableSettings,
+    ) -> Result<Self, ModelError>;
+
+    fn pk(&self) -> String {
+        Self::pk_for(self.id())
+    }
+
+    fn sk(&self) -> String {
+        Self::sk_for(self.id())
+    }
+
+    /// Primary key attributes for the entity with the given id
+    fn key(id: &str) -> HashMap<String, AttributeValue> {
+        HashMap::from([
+            ("pk".to_string(), AttributeValue::S(Self::pk_for(id))),
+            ("sk".to_string(), AttributeValue::S(Self::sk_for(id))),
+        ])
+    }
+}
+
+/// What a stored entity must look like for `delete_if` to remove it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DeleteCondition<'a> {
+    /// `(attribute, value)` the stored entity must match, e.g. its owner
+    pub equals: Option<(&'a str, &'a str)>,
+    /// `(attribute, bound)` the stored attribute must sort before (for
+    /// RFC3339 timestamps, be earlier than)
+    pub before: Option<(&'a str, &'a str)>,
+}
+
+impl DeleteCondition<'_> {
+    /// Condition expression for DeleteItem, `None` when unconditional. A
+    /// missing item deletes nothing, so it is let through to come back as `None`.
+    fn expression(&self) -> Option<String> {
+        let clauses: Vec<&str> = [
+            self.equals.map(|_| "#eq = :eq"),
+            self.before.map(|_| "#before < :before"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!clauses.is_empty())
+            .then(|| format!("attribute_not_exists(sk) OR ({})", clauses.join(" AND ")))
+    }
+
+    /// Whether stored attributes satisfy the condition
+    pub fn holds(&self, attrs: &HashMap<String, AttributeValue>) -> bool {
+        let value = |attribute: &str| match attrs.get(attribute) {
+            Some(AttributeValue::S(value)) => Some(value.as_str()),
+            _ => None,
+        };
+        self.equals
+            .is_none_or(|(attribute, expected)| value(attribute) == Some(expected))
+            && self
+                .before
+                .is_none_or(|(attribute, bound)| value(attribute).is_some_and(|v| v < bound))
+    }
+}
+
+/// Filter keeping the entities where any of the attributes equals its
+/// value. DynamoDB applies it after reading, so filtered-out entities still
+/// cost reads, but `fill_page` keeps reading until the page is full.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchAny(pub Vec<(String, AttributeValue)>);
+
+impl MatchAny {
+    /// Whether stored attributes pass the filter
+    pub fn holds(&self, attrs: &HashMap<String, AttributeValue>) -> bool {
+        self.0.is_empty()
+            || self
+                .0
+                .iter()
+                .any(|(name, value)| attrs.get(name) == Some(value))
+    }
+}
+
+/// One page of query results. Paged queries take the `last_evaluated_key`
+/// of one page as the `start_key` of the next.
+#[derive(Debug)]
+pub struct QueryPage<T> {
+    pub items: Vec<T>,
+    pub last_evaluated_key: Option<HashMap<String, AttributeValue>>,
+    pub scanned_count: usize,
+}
+
+/// Typed access to entities of one kind in the table
+pub struct Repository<T> {
+    client: Client,
+    table_name: String,
+    settings: TableSettings,
+    _entity: PhantomData<T>,
+}
+
+impl<T: DynamoEntity> Repository<T> {
+    pub fn new(client: Client, table_name: impl Into<String>, settings: TableSettings) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+            settings,
+            _entity: PhantomData,
+        }
+    }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    pub fn settings(&self) -> &TableSettings {
+        &self.settings
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<T>, RepositoryError> {
+        self.get_with_consistency(id, false).await
+    }
+
+    /// Get an entity, optionally with a strongly consistent read.
+    /// Consistent reads cost twice the read capacity of eventually consistent ones.
+    pub async fn get_with_consistency(
+        &self,
+        id: &str,
+        consistent_read: bool,
+    ) -> Result<Option<T>, RepositoryError> {
+        let output = retry_dynamo(|| self.get_request(id, consistent_read).send()).await?;
+
+        match output.item {
+            Some(item) => Ok(parse_live(&item, now_secs(), &self.settings)?),
+            None => Ok(None),
+        }
+    }
+
+    fn get_request(&self, id: &str, consistent_read: bool) -> GetItemFluentBuilder {
+        self.client
+            .get_item()
+            .table_name(&self.table_name)
+            .set_key(Some(T::key(id)))
+            .consistent_read(consistent_read)
+    }
+
+    pub async fn put(&self, entity: &T) -> Result<(), RepositoryError> {
+        let item = stored_attributes(entity, &self.settings)?;
+        retry_dynamo(|| {
+            self.client
+                .put_item()
+                .table_name(&self.table_name)
+                .set_item(Some(item.clone()))
+                .send()
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Put an entity only if no entity with the same key exists yet.
+    /// Returns `RepositoryError::ConditionFailed` on a key collision.
+    pub async fn put_new(&self, entity: &T) -> Result<(), RepositoryError> {
+        let item = stored_attributes(entity, &self.settings)?;
+        retry_dynamo(|| {
+            self.client
+                .put_item()
+                .table_name(&self.table_name)
+                .set_item(Some(item.clone()))
+                .condition_expression("attribute_not_exists(sk)")
+                .send()
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Put an entity only if the stored copy's `attribute` still equals `expected`
+    /// (optimistic concurrency). Returns `RepositoryError::ConditionFailed` otherwise.
+    pub async fn put_if_match(
+        &self,
+        entity: &T,
+        attribute: &str,
+        expected: &str,
+    ) -> Result<(), RepositoryError> {
+        let item = stored_attributes(entity, &self.settings)?;
+        retry_dynamo(|| {
+            self.client
+                .put_item()
+                .table_name(&self.table_name)
+                .set_item(Some(item.clone()))
+                .condition_expression("#attr = :expected")
+                .expression_attribute_names("#attr", attribute)
+                .expression_attribute_values(":expected", AttributeValue::S(expected.to_string()))
+                .send()
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Write up to `MAX_BATCH_WRITE` entities in one BatchWriteItem, resubmitting
+    /// unprocessed writes a couple of times. Returns ids that were still unprocessed.
+    pub async fn batch_put(&self, entities: &[T]) -> Result<Vec<String>, RepositoryError> {
+        let writes = entities
+            .iter()
+            .map(|entity| {
+                let put = PutRequest::builder()
+                    .set_item(Some(stored_attributes(entity, &self.settings)?))
+                    .build()?;
+                Ok(WriteRequest::builder().put_request(put).build())
+            })
+            .collect::<Result<Vec<_>, RepositoryError>>()?;
+
+        let unprocessed =
+            write_with_retries(writes, UNPROCESSED_RETRY_ROUNDS, |writes| async move {
+                let output = retry_dynamo(|| {
+                    self.client
+                        .batch_write_item()
+                        .request_items(&self.table_name, writes.clone())
+                        .send()
+                })
+                .await?;
+                Ok::<_, RepositoryError>(
+                    output
+                        .unprocessed_items
+                        .and_then(|mut items| items.remove(&self.table_name))
+                        .unwrap_or_default(),
+                )
+            })
+            .await?;
+
+        Ok(unprocessed
+            .iter()
+            .filter_map(|write| write.put_request.as_ref())
+            .filter_map(|put| T::from_dynamo(&put.item, &self.settings).ok())
+            .map(|entity| entity.id().to_string())
+            .collect())
+    }
+
+    /// Delete an entity, returning what was deleted; `None` when nothing
+    /// (or only an expired entity) was stored under the id
+    pub async fn delete(&self, id: &str) -> Result<Option<T>, RepositoryError> {
+        let output = retry_dynamo(|| {
+            self.client
+                .delete_item()
+                .table_name(&self.table_name)
+                .set_key(Some(T::key(id)))
+                .return_values(ReturnValue::AllOld)
+                .send()
+        })
+        .await?;
+
+        match output.attributes {
+            Some(attrs) if !attrs.is_empty() => Ok(parse_live(&attrs, now_secs(), &self.settings)?),
+            _ => Ok(None),
+        }
+    }
+
+    /// Delete an entity only if it satisfies `condition`. Returns what was
+    /// deleted, `None` when nothing was stored under the id, and
+    /// `RepositoryError::ConditionFailed` when the stored entity doesn't match.
+    pub async fn delete_if(
+        &self,
+        id: &str,
+        condition: DeleteCondition<'_>,
+    ) -> Result<Option<T>, RepositoryError> {
+        let output = retry_dynamo(|| {
+            let mut request = self
+                .client
+                .delete_item()
+                .table_name(&self.table_name)
+                .set_key(Some(T::key(id)))
+                .set_condition_expression(condition.expression())
+                .return_values(ReturnValue::AllOld);
+            if let Some((attribute, value)) = condition.equals {
+                request = request
+                    .expression_attribute_names("#eq", attribute)
+                    .expression_attribute_values(":eq", AttributeValue::S(value.to_string()));
+            }
+            if let Some((attribute, bound)) = condition.before {
+                request = request
+                    .expression_attribute_names("#before", attribute)
+                    .expression_attribute_values(":before", AttributeValue::S(bound.to_string()));
+            }
+            request.send()
+        })
+        .await?;
+
+        match output.attributes {
+            Some(attrs) if !attrs.is_empty() => Ok(parse_live(&attrs, now_secs(), &self.settings)?),
+            _ => Ok(None),
+        }
+    }
+
+    /// Delete up to `MAX_BATCH_WRITE` entities in one BatchWriteItem, resubmitting
+    /// unprocessed deletes a couple of times. Returns ids that were still unprocessed.
+    pub async fn batch_delete(&self, ids: &[String]) -> Result<Vec<String>, RepositoryError> {
+        let writes = ids
+            .iter()
+            .map(|id| {
+                let delete = DeleteRequest::builder().set_key(Some(T::key(id))).build()?;
+                Ok(WriteRequest::builder().delete_request(delete).build())
+            })
+            .collect::<Result<Vec<_>, RepositoryError>>()?;
+
+        let unprocessed =
+            write_with_retries(writes, UNPROCESSED_RETRY_ROUNDS, |writes| async move {
+                let output = retry_dynamo(|| {
+                    self.client
+                        .batch_write_item()
+                        .request_items(&self.table_name, writes.clone())
+                        .send()
+                })
+                .await?;
+                Ok::<_, RepositoryError>(
+                    output
+                        .unprocessed_items
+                        .and_then(|mut items| items.remove(&self.table_name))
+                        .unwrap_or_default(),
+                )
+            })
+            .await?;
+
+        Ok(unprocessed_delete_ids::<T>(&unprocessed))
+    }
+
+    /// Query one page of entities in a partition; unparseable items are skipped
+    pub async fn query(
+        &self,
+        pk: &str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> Result<QueryPage<T>, RepositoryError> {
+        query_pages(
+            || self.partition_query(pk),
+            limit,
+            start_key,
+            |output| self.page(output),
+        )
+        .await
+    }
+
+    /// Query everything under `gsi2pk` in the gsi2 index, following the
+    /// cursor until the results run out or `max_items` were collected, in
+    /// which case `last_evaluated_key` is set
+    pub async fn query_gsi2_all(
+        &self,
+        gsi2pk: &str,
+        max_items: usize,
+    ) -> Result<QueryPage<T>, RepositoryError> {
+        let limit = i32::try_from(max_items).unwrap_or(i32::MAX);
+        fill_page_within(limit, None, usize::MAX, |start_key, remaining| {
+            let request = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(GSI2)
+                .key_condition_expression("gsi2pk = :pk")
+                .expression_attribute_values(":pk", AttributeValue::S(gsi2pk.to_string()))
+                .set_exclusive_start_key(start_key)
+                .limit(remaining);
+            async move {
+                let output = retry_dynamo(|| request.clone().send()).await?;
+                Ok(self.page(output))
+            }
+        })
+        .await
+    }
+
+    /// Query one page of a partition, returning only `attributes` of each item
+    pub async fn query_projected(
+        &self,
+        pk: &str,
+        attributes: &[&str],
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> Result<QueryPage<HashMap<String, AttributeValue>>, RepositoryError> {
+        query_pages(
+            || with_projection(self.partition_query(pk), attributes, &self.settings),
+            limit,
+            start_key,
+            |output| projected_page(output, attributes, &self.settings),
+        )
+        .await
+    }
+
+    fn partition_query(&self, pk: &str) -> QueryFluentBuilder {
+        self.client
+            .query()
+            .table_name(&self.table_name)
+            .key_condition_expression("pk = :pk")
+            .expression_attribute_values(":pk", AttributeValue::S(pk.to_string()))
+    }
+
+    /// Query one page of the gsi3 index for entities whose `gsi3sk` sorts
+    /// strictly after `after`
+    pub async fn query_gsi3_after(
+        &self,
+        gsi3pk: &str,
+        after: &str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+        filter: Option<&MatchAny>,
+    ) -> Result<QueryPage<T>, RepositoryError> {
+        query_pages(
+            || self.gsi3_after_query(gsi3pk, after, filter),
+            limit,
+            start_key,
+            |output| self.page(output),
+        )
+        .await
+    }
+
+    /// Like `query_gsi3_after`, returning only `attributes` of each item
+    pub async fn query_gsi3_after_projected(
+        &self,
+        gsi3pk: &str,
+        after: &str,
+        attributes: &[&str],
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+        filter: Option<&MatchAny>,
+    ) -> Result<QueryPage<HashMap<String, AttributeValue>>, RepositoryError> {
+        query_pages(
+            || {
+                with_projection(
+                    self.gsi3_after_query(gsi3pk, after, filter),
+                    attributes,
+                    &self.settings,
+                )
+            },
+            limit,
+            start_key,
+            |output| projected_page(output, attributes, &self.settings),
+        )
+        .await
+    }
+
+    fn gsi3_after_query(
+        &self,
+        gsi3pk: &str,
+        after: &str,
+        filter: Option<&MatchAny>,
+    ) -> QueryFluentBuilder {
+        let query = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .index_name(GSI3)
+            .key_condition_expression("gsi3pk = :pk AND gsi3sk > :after")
+            .expression_attribute_values(":pk", AttributeValue::S(gsi3pk.to_string()))
+            .expression_attribute_values(":after", AttributeValue::S(after.to_string()));
+        with_filter(query, filter)
+    }
+
+    /// Query one page of the gsi1 index for entities whose `gsi1sk`
+    /// (`created_at`) is between `after` and `before` inclusive
+    pub async fn query_gsi1_created_between(
+        &self,
+        gsi1pk: &str,
+        after: &str,
+        before: &str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+        filter: Option<&MatchAny>,
+    ) -> Result<QueryPage<T>, RepositoryError> {
+        query_pages(
+            || self.gsi1_between_query(gsi1pk, after, before, filter),
+            limit,
+            start_key,
+            |output| self.page(output),
+        )
+        .await
+    }
+
+    fn gsi1_between_query(
+        &self,
+        gsi1pk: &str,
+        after: &str,
+        before: &str,
+        filter: Option<&MatchAny>,
+    ) -> QueryFluentBuilder {
+        let query = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .index_name(GSI1)
+            .key_condition_expression("gsi1pk = :pk AND gsi1sk BETWEEN :after AND :before")
+            .expression_attribute_values(":pk", AttributeValue::S(gsi1pk.to_string()))
+            .expression_attribute_values(":after", AttributeValue::S(after.to_string()))
+            .expression_attribute_values(":before", AttributeValue::S(before.to_string()));
+        with_filter(query, filter)
+    }
+
+    /// Query one page of the gsi4 index: the entities under `gsi4pk`, in
+    /// `gsi4sk` order
+    pub async fn query_gsi4(
+        &self,
+        gsi4pk: &str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> Result<QueryPage<T>, RepositoryError> {
+        let query = || {
+            self.client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(GSI4)
+                .key_condition_expression("gsi4pk = :pk")
+                .expression_attribute_values(":pk", AttributeValue::S(gsi4pk.to_string()))
+        };
+        query_pages(query, limit, start_key, |output| self.page(output)).await
+    }
+
+    /// Query one page of the gsi2 index for entities whose `gsi2sk` begins
+    /// with `prefix`, in `gsi2sk` order
+    pub async fn query_gsi2_prefix(
+        &self,
+        gsi2pk: &str,
+        prefix: &str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> Result<QueryPage<T>, RepositoryError> {
+        let query = || {
+            self.client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(GSI2)
+                .key_condition_expression("gsi2pk = :pk AND begins_with(gsi2sk, :prefix)")
+                .expression_attribute_values(":pk", AttributeValue::S(gsi2pk.to_string()))
+                .expression_attribute_values(":prefix", AttributeValue::S(prefix.to_string()))
+        };
+        query_pages(query, limit, start_key, |output| self.page(output)).await
+    }
+
+    fn page(&self, output: QueryOutput) -> QueryPage<T> {
+        let now = now_secs();
+        QueryPage {
+            items: output
+                .items
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|item| parse_live(item, now, &self.settings).ok().flatten())
+                .collect(),
+            last_evaluated_key: output.last_evaluated_key,
+            scanned_count: output.scanned_count.max(0) as usize,
+        }
+    }
+
+    /// Count the entities under `gsi2pk` in the gsi2 index, following
+    /// pagination to the end. Only that key range is read, so the cost grows
+    /// with the count rather than with the whole table.
+    pub async fn count_gsi2(&self, gsi2pk: &str) -> Result<usize, RepositoryError> {
+        sum_pages(|start_key| async move {
+            let output = retry_dynamo(|| {
+                self.client
+                    .query()
+                    .table_name(&self.table_name)
+                    .index_name(GSI2)
+                    .key_condition_expression("gsi2pk = :pk")
+                    .expression_attribute_values(":pk", AttributeValue::S(gsi2pk.to_string()))
+                    .select(Select::Count)
+                    .set_exclusive_start_key(start_key.clone())
+                    .send()
+            })
+            .await?;
+            Ok((output.count.max(0) as usize, output.last_evaluated_key))
+        })
+        .await
+    }
+}
+
+/// Attributes to write for `entity` once it has passed its invariants.
+/// Every `EntityStore` writes through this, so no store can skip the check.
+pub fn stored_attributes<T: DynamoEntity>(
+    entity: &T,
+    settings: &TableSettings,
+) -> Result<HashMap<String, AttributeValue>, ModelError> {
+    entity.check_invariants(settings)?;
+    Ok(entity.to_dynamo(settings))
+}
+
+/// The data operations route handlers need, so handlers can run against
+/// `Repository` in production and an in-memory fake in tests.
+/// Methods mirror the ones on `Repository`.
+pub trait EntityStore<T>: Send + Sync {
+    fn get<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<T>, RepositoryError>> {
+        self.get_with_consistency(id, false)
+    }
+
+    fn get_with_consistency<'a>(
+        &'a self,
+        id: &'a str,
+        consistent_read: bool,
+    ) -> BoxFuture<'a, Result<Option<T>, RepositoryError>>;
+
+    fn put<'a>(&'a self, entity: &'a T) -> BoxFuture<'a, Result<(), RepositoryError>>;
+
+    fn put_new<'a>(&'a self, entity: &'a T) -> BoxFuture<'a, Result<(), RepositoryError>>;
+
+    fn put_if_match<'a>(
+        &'a self,
+        entity: &'a T,
+        attribute: &'a str,
+        expected: &'a str,
+    ) -> BoxFuture<'a, Result<(), RepositoryError>>;
+
+    fn batch_put<'a>(
+        &'a self,
+        entities: &'a [T],
+    ) -> BoxFuture<'a, Result<Vec<String>, RepositoryError>>;
+
+    fn delete<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<T>, RepositoryError>>;
+
+    fn delete_if<'a>(
+        &'a self,
+        id: &'a str,
+        condition: DeleteCondition<'a>,
+    ) -> BoxFuture<'a, Result<Option<T>, RepositoryError>>;
+
+    fn batch_delete<'a>(
+        &'a self,
+        ids: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<String>, RepositoryError>>;
+
+    fn query<'a>(
+        &'a self,
+        pk: &'a str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>>;
+
+    fn query_gsi2_all<'a>(
+        &'a self,
+        gsi2pk: &'a str,
+        max_items: usize,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>>;
+
+    fn query_gsi3_after<'a>(
+        &'a self,
+        gsi3pk: &'a str,
+        after: &'a str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+        filter: Option<&'a MatchAny>,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>>;
+
+    fn query_gsi1_created_between<'a>(
+        &'a self,
+        gsi1pk: &'a str,
+        after: &'a str,
+        before: &'a str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+        filter: Option<&'a MatchAny>,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>>;
+
+    fn query_gsi2_prefix<'a>(
+        &'a self,
+        gsi2pk: &'a str,
+        prefix: &'a str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>>;
+
+    fn query_gsi4<'a>(
+        &'a self,
+        gsi4pk: &'a str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>>;
+
+    fn query_projected<'a>(
+        &'a self,
+        pk: &'a str,
+        attributes: &'a [&'a str],
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> BoxFuture<'a, Result<QueryPage<HashMap<String, AttributeValue>>, RepositoryError>>;
+
+    fn query_gsi3_after_projected<'a>(
+        &'a self,
+        gsi3pk: &'a str,
+        after: &'a str,
+        attributes: &'a [&'a str],
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+        filter: Option<&'a MatchAny>,
+    ) -> BoxFuture<'a, Result<QueryPage<HashMap<String, AttributeValue>>, RepositoryError>>;
+
+    fn count_gsi2<'a>(&'a self, gsi2pk: &'a str) -> BoxFuture<'a, Result<usize, RepositoryError>>;
+}
+
+impl<T: DynamoEntity + Send + Sync> EntityStore<T> for Repository<T> {
+    fn get_with_consistency<'a>(
+        &'a self,
+        id: &'a str,
+        consistent_read: bool,
+    ) -> BoxFuture<'a, Result<Option<T>, RepositoryError>> {
+        Box::pin(Repository::get_with_consistency(self, id, consistent_read))
+    }
+
+    fn put<'a>(&'a self, entity: &'a T) -> BoxFuture<'a, Result<(), RepositoryError>> {
+        Box::pin(Repository::put(self, entity))
+    }
+
+    fn put_new<'a>(&'a self, entity: &'a T) -> BoxFuture<'a, Result<(), RepositoryError>> {
+        Box::pin(Repository::put_new(self, entity))
+    }
+
+    fn put_if_match<'a>(
+        &'a self,
+        entity: &'a T,
+        attribute: &'a str,
+        expected: &'a str,
+    ) -> BoxFuture<'a, Result<(), RepositoryError>> {
+        Box::pin(Repository::put_if_match(self, entity, attribute, expected))
+    }
+
+    fn batch_put<'a>(
+        &'a self,
+        entities: &'a [T],
+    ) -> BoxFuture<'a, Result<Vec<String>, RepositoryError>> {
+        Box::pin(Repository::batch_put(self, entities))
+    }
+
+    fn delete<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<T>, RepositoryError>> {
+        Box::pin(Repository::delete(self, id))
+    }
+
+    fn delete_if<'a>(
+        &'a self,
+        id: &'a str,
+        condition: DeleteCondition<'a>,
+    ) -> BoxFuture<'a, Result<Option<T>, RepositoryError>> {
+        Box::pin(Repository::delete_if(self, id, condition))
+    }
+
+    fn batch_delete<'a>(
+        &'a self,
+        ids: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<String>, RepositoryError>> {
+        Box::pin(Repository::batch_delete(self, ids))
+    }
+
+    fn query<'a>(
+        &'a self,
+        pk: &'a str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>> {
+        Box::pin(Repository::query(self, pk, limit, start_key))
+    }
+
+    fn query_gsi2_all<'a>(
+        &'a self,
+        gsi2pk: &'a str,
+        max_items: usize,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>> {
+        Box::pin(Repository::query_gsi2_all(self, gsi2pk, max_items))
+    }
+
+    fn query_gsi3_after<'a>(
+        &'a self,
+        gsi3pk: &'a str,
+        after: &'a str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+        filter: Option<&'a MatchAny>,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>> {
+        Box::pin(Repository::query_gsi3_after(
+            self, gsi3pk, after, limit, start_key, filter,
+        ))
+    }
+
+    fn query_gsi1_created_between<'a>(
+        &'a self,
+        gsi1pk: &'a str,
+        after: &'a str,
+        before: &'a str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+        filter: Option<&'a MatchAny>,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>> {
+        Box::pin(Repository::query_gsi1_created_between(
+            self, gsi1pk, after, before, limit, start_key, filter,
+        ))
+    }
+
+    fn query_gsi2_prefix<'a>(
+        &'a self,
+        gsi2pk: &'a str,
+        prefix: &'a str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>> {
+        Box::pin(Repository::query_gsi2_prefix(
+            self, gsi2pk, prefix, limit, start_key,
+        ))
+    }
+
+    fn query_gsi4<'a>(
+        &'a self,
+        gsi4pk: &'a str,
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> BoxFuture<'a, Result<QueryPage<T>, RepositoryError>> {
+        Box::pin(Repository::query_gsi4(self, gsi4pk, limit, start_key))
+    }
+
+    fn query_projected<'a>(
+        &'a self,
+        pk: &'a str,
+        attributes: &'a [&'a str],
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> BoxFuture<'a, Result<QueryPage<HashMap<String, AttributeValue>>, RepositoryError>> {
+        Box::pin(Repository::query_projected(
+            self, pk, attributes, limit, start_key,
+        ))
+    }
+
+    fn query_gsi3_after_projected<'a>(
+        &'a self,
+        gsi3pk: &'a str,
+        after: &'a str,
+        attributes: &'a [&'a str],
+        limit: i32,
+        start_key: Option<HashMap<String, AttributeValue>>,
+        filter: Option<&'a MatchAny>,
+    ) -> BoxFuture<'a, Result<QueryPage<HashMap<String, AttributeValue>>, RepositoryError>> {
+        Box::pin(Repository::query_gsi3_after_projected(
+            self, gsi3pk, after, attributes, limit, start_key, filter,
+        ))
+    }
+
+    fn count_gsi2<'a>(&'a self, gsi2pk: &'a str) -> BoxFuture<'a, Result<usize, RepositoryError>> {
+        Box::pin(Repository::count_gsi2(self, gsi2pk))
+    }
+}
+
+/// Entity ids of delete requests DynamoDB returned as unprocessed
+fn unprocessed_delete_ids<T: DynamoEntity>(writes: &[WriteRequest]) -> Vec<String> {
+    let prefix = T::sk_for("");
+    writes
+        .iter()
+        .filter_map(|write| write.delete_request.as_ref())
+        .filter_map(|delete| delete.key.get("sk"))
+        .filter_map(|sk| sk.as_s().ok())
+        .filter_map(|sk| sk.strip_prefix(&prefix))
+        .map(String::from)
+        .collect()
+}
+
+/// Project `attributes` plus the TTL attribute, so expired items can still be
+/// filtered out. Every name goes through a placeholder because several
+/// attribute names (`name`, for one) are DynamoDB reserved words.
+fn with_projection(
+    builder: QueryFluentBuilder,
+    attributes: &[&str],
+    settings: &TableSettings,
+) -> QueryFluentBuilder {
+    let ttl = settings.ttl_attribute.as_str();
+    let mut names: Vec<&str> = attributes.to_vec();
+    if !names.contains(&ttl) {
+        names.push(ttl);
+    }
+    let expression = (0..names.len())
+        .map(|i| format!("#p{i}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    names.iter().enumerate().fold(
+        builder.projection_expression(expression),
+        |builder, (i, name)| builder.expression_attribute_names(format!("#p{i}"), *name),
+    )
+}
+
+/// Add `filter` to a query as a FilterExpression; no filter, or an empty
+/// one, keeps everything
+fn with_filter(query: QueryFluentBuilder, filter: Option<&MatchAny>) -> QueryFluentBuilder {
+    let Some(MatchAny(matches)) = filter.filter(|filter| !filter.0.is_empty()) else {
+        return query;
+    };
+    let expression = (0..matches.len())
+        .map(|i| format!("#f{i} = :f{i}"))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    matches.iter().enumerate().fold(
+        query.filter_expression(expression),
+        |query, (i, (name, value))| {
+            query
+                .expression_attribute_names(format!("#f{i}"), name)
+                .expression_attribute_values(format!(":f{i}"), value.clone())
+        },
+    )
+}
+
+/// Drop expired items from a projected query, and the TTL attribute itself
+/// unless it was asked for
+fn projected_page(
+    output: QueryOutput,
+    attributes: &[&str],
+    settings: &TableSettings,
+) -> QueryPage<HashMap<String, AttributeValue>> {
+    let now = now_secs();
+    let ttl = settings.ttl_attribute.as_str();
+    let keep_ttl = attributes.contains(&ttl);
+    QueryPage {
+        items: output
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|item| !attrs_expired(item, now, ttl))
+            .map(|mut item| {
+                if !keep_ttl {
+                    item.remove(ttl);
+                }
+                item
+            })
+            .collect(),
+        last_evaluated_key: output.last_evaluated_key,
+        scanned_count: output.scanned_count.max(0) as usize,
+    }
+}
+
+/// Whether raw attributes carry a TTL at or before `now`
+fn attrs_expired(attrs: &HashMap<String, AttributeValue>, now: u64, ttl_attribute: &str) -> bool {
+    attrs
+        .get(ttl_attribute)
+        .and_then(|ttl| ttl.as_n().ok())
+        .and_then(|ttl| ttl.parse::<u64>().ok())
+        .is_some_and(|ttl| ttl <= now)
+}
+
+/// Parse an entity, treating one past its TTL as absent
+fn parse_live<T: DynamoEntity>(
+    attrs: &HashMap<String, AttributeValue>,
+    now: u64,
+    settings: &TableSettings,
+) -> Result<Option<T>, ModelError> {
+    let entity = T::from_dynamo(attrs, settings)?;
+    Ok((!entity.is_expired(now)).then_some(entity))
+}
+
+fn now_secs() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+/// Run `query` until it yields `limit` items; see `fill_page`
+async fn query_pages<U>(
+    query: impl Fn() -> QueryFluentBuilder,
+    limit: i32,
+    start_key: Option<HashMap<String, AttributeValue>>,
+    convert: impl Fn(QueryOutput) -> QueryPage<U>,
+) -> Result<QueryPage<U>, RepositoryError> {
+    let convert = &convert;
+    fill_page(limit, start_key, |start_key, remaining| {
+        let request = query().set_exclusive_start_key(start_key).limit(remaining);
+        async move {
+            let output = retry_dynamo(|| request.clone().send()).await?;
+            Ok(convert(output))
+        }
+    })
+    .await
+}
+
+/// Fetch pages until `limit` items are collected, the results run out, or
+/// `MAX_QUERY_ROUND_TRIPS` requests were made. One DynamoDB page can come back
+/// short because of its 1 MB cap or because expired and unparseable items were
+/// dropped. The first request starts at `start_key` (a previous page's
+/// `last_evaluated_key`, or `None` for the first page); `fetch` gets the
+/// start key and how many items are still wanted.
+async fn fill_page<U, E, F, Fut>(
+    limit: i32,
+    start_key: Option<HashMap<String, AttributeValue>>,
+    fetch: F,
+) -> Result<QueryPage<U>, E>
+where
+    F: FnMut(Option<HashMap<String, AttributeValue>>, i32) -> Fut,
+    Fut: Future<Output = Result<QueryPage<U>, E>>,
+{
+    fill_page_within(limit, start_key, MAX_QUERY_ROUND_TRIPS, fetch).await
+}
+
+/// `fill_page` with an explicit cap on requests
+async fn fill_page_within<U, E, F, Fut>(
+    limit: i32,
+    start_key: Option<HashMap<String, AttributeValue>>,
+    max_round_trips: usize,
+    mut fetch: F,
+) -> Result<QueryPage<U>, E>
+where
+    F: FnMut(Option<HashMap<String, AttributeValue>>, i32) -> Fut,
+    Fut: Future<Output = Result<QueryPage<U>, E>>,
+{
+    let mut items = Vec::new();
+    let mut scanned_count = 0;
+    let mut start_key = start_key;
+    for _ in 0..max_round_trips {
+        let remaining = limit - items.len() as i32;
+        let page = fetch(start_key.take(), remaining).await?;
+        items.extend(page.items);
+        scanned_count += page.scanned_count;
+        start_key = page.last_evaluated_key.filter(|key| !key.is_empty());
+        if start_key.is_none() || items.len() as i32 >= limit {
+            break;
+        }
+    }
+    Ok(QueryPage {
+        items,
+        last_evaluated_key: start_key,
+        scanned_count,
+    })
+}
+
+/// Add up per-page counts, passing each page's last evaluated key as the
+/// next page's start key until there are no more pages
+async fn sum_pages<E, F, Fut>(mut fetch: F) -> Result<usize, E>
+where
+    F: FnMut(Option<HashMap<String, AttributeValue>>) -> Fut,
+    Fut: Future<Output = Result<(usize, Option<HashMap<String, AttributeValue>>), E>>,
+{
+    let mut total = 0;
+    let mut start_key = None;
+    loop {
+        let (count, last_key) = fetch(start_key).await?;
+        total += count;
+        match last_key {
+            Some(key) if !key.is_empty() => start_key = Some(key),
+            _ => return Ok(total),
+        }
+    }
+}
+
+/// Send batch writes, resubmitting whatever comes back unprocessed for up to
+/// `retry_rounds` extra rounds. Returns the writes that were never processed.
+async fn write_with_retries<E, F, Fut>(
+    mut writes: Vec<WriteRequest>,
+    retry_rounds: u32,
+    mut send: F,
+) -> Result<Vec<WriteRequest>, E>
+where
+    F: FnMut(Vec<WriteRequest>) -> Fut,
+    Fut: Future<Output = Result<Vec<WriteRequest>, E>>,
+{
+    for round in 0..=retry_rounds {
+        if writes.is_empty() {
+            break;
+        }
+        if round > 0 {
+            tokio::time::sleep(Duration::from_millis(50 * 2u64.pow(round - 1))).await;
+        }
+        writes = send(writes).await?;
+    }
+    Ok(writes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Item;
+
+    fn sample_item() -> Item {
+        Item {
+            id: "abc".to_string(),
+            name: "Sample".to_string(),
+            description: Some("A sample".to_string()),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-02T00:00:00+00:00".to_string(),
+            created_by: "user-1".to_string(),
+            expires_at: None,
+            slug: None,
+            deleted_at: None,
+            metadata: HashMap::new(),
+            public: false,
+        }
+    }
+
+    #[test]
+    fn test_item_keys() {
+        let item = sample_item();
+        assert_eq!(item.pk(), "ITEM");
+        assert_eq!(item.sk(), "ITEM#abc");
+
+        let key = Item::key("abc");
+        assert_eq!(key["pk"].as_s().unwrap(), "ITEM");
+        assert_eq!(key["sk"].as_s().unwrap(), "ITEM#abc");
+    }
+
+    #[test]
+    fn test_item_dynamo_round_trip() {
+        let item = sample_item();
+        let attrs = item.to_dynamo(&TableSettings::default());
+
+        assert_eq!(attrs["sk"].as_s().unwrap(), "ITEM#abc");
+        assert_eq!(attrs["gsi1sk"].as_s().unwrap(), &item.created_at);
+        assert_eq!(attrs["gsi3sk"].as_s().unwrap(), &item.updated_at);
+
+        let parsed = Item::from_dynamo(&attrs, &TableSettings::default()).unwrap();
+        assert_eq!(parsed.id, item.id);
+        assert_eq!(parsed.name, item.name);
+        assert_eq!(parsed.description, item.description);
+        assert_eq!(parsed.updated_at, item.updated_at);
+        assert_eq!(parsed.created_by, item.created_by);
+    }
+
+    #[test]
+    fn test_missing_description_stored_as_null() {
+        let item = Item {
+            description: None,
+            ..sample_item()
+        };
+        let attrs = item.to_dynamo(&TableSettings::default());
+
+        assert!(attrs["description"].is_null());
+        assert_eq!(
+            Item::from_dynamo(&attrs, &TableSettings::default())
+                .unwrap()
+                .description,
+            None
+        );
+    }
+
+    fn test_repository() -> Repository<Item> {
+        let config = aws_sdk_dynamodb::Config::builder()
+            .behavior_version(aws_sdk_dynamodb::config::BehaviorVersion::latest())
+            .build();
+        Repository::new(Client::from_conf(config), "items", TableSettings::default())
+    }
+
+    #[test]
+    fn test_get_request_consistent_read() {
+        let repo = test_repository();
+
+        let request = repo.get_request("abc", true);
+        assert_eq!(request.get_consistent_read(), &Some(true));
+        assert_eq!(request.get_table_name().as_deref(), Some("items"));
+
+        let request = repo.get_request("abc", false);
+        assert_eq!(request.get_consistent_read(), &Some(false));
+    }
+
+    #[test]
+    fn test_created_range_is_a_key_condition() {
+        let request = test_repository().gsi1_between_query(
+            "ITEM",
+            "2024-06-01T00:00:00+00:00",
+            "2024-06-30T00:00:00+00:00",
+            None,
+        );
+
+        assert_eq!(request.get_index_name().as_deref(), Some("gsi1"));
+        assert_eq!(
+            request.get_key_condition_expression().as_deref(),
+            Some("gsi1pk = :pk AND gsi1sk BETWEEN :after AND :before")
+        );
+        // A filter would apply after Limit and leave pages short
+        assert_eq!(request.get_filter_expression(), &None);
+    }
+
+    #[test]
+    fn test_match_any_filters_on_any_listed_attribute() {
+        let filter = MatchAny(vec![
+            ("public".to_string(), AttributeValue::Bool(true)),
+            (
+                "created_by".to_string(),
+                AttributeValue::S("user-1".to_string()),
+            ),
+        ]);
+        let request =
+            test_repository().gsi3_after_query("ITEM", "2024-06-01T00:00:00+00:00", Some(&filter));
+
+        assert_eq!(
+            request.get_filter_expression().as_deref(),
+            Some("#f0 = :f0 OR #f1 = :f1")
+        );
+        let names = request.get_expression_attribute_names().clone().unwrap();
+        assert_eq!(names["#f1"], "created_by");
+
+        let item = |created_by: &str, public: bool| {
+            HashMap::from([
+                (
+                    "created_by".to_string(),
+                    AttributeValue::S(created_by.to_string()),
+                ),
+                ("public".to_string(), AttributeValue::Bool(public)),
+            ])
+        };
+        assert!(filter.holds(&item("user-2", true)));
+        assert!(filter.holds(&item("user-1", false)));
+        assert!(!filter.holds(&item("user-2", false)));
+    }
+
+    fn put_write(item: &Item) -> WriteRequest {
+        let put = PutRequest::builder()
+            .set_item(Some(item.to_dynamo(&TableSettings::default())))
+            .build()
+            .unwrap();
+        WriteRequest::builder().put_request(put).build()
+    }
+
+    #[tokio::test]
+    async fn test_unprocessed_writes_are_resubmitted() {
+        let writes = vec![put_write(&sample_item()), put_write(&sample_item())];
+        let mut sent = Vec::new();
+
+        let unprocessed = write_with_retries(writes, 2, |writes| {
+            sent.push(writes.len());
+            // First round leaves one write unprocessed, second succeeds
+            let remaining = if sent.len() == 1 {
+                writes[..1].to_vec()
+            } else {
+                Vec::new()
+            };
+            async move { Ok::<_, ()>(remaining) }
+        })
+        .await
+        .unwrap();
+
+        assert!(unprocessed.is_empty());
+        assert_eq!(sent, vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_unprocessed_writes_reported_after_retries() {
+        let writes = vec![put_write(&sample_item())];
+        let mut rounds = 0;
+
+        let unprocessed = write_with_retries(writes, 2, |writes| {
+            rounds += 1;
+            async move { Ok::<_, ()>(writes) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(rounds, 3);
+        assert_eq!(unprocessed.len(), 1);
+    }
+
+    #[test]
+    fn test_conditional_check_failure_maps_to_condition_failed() {
+        use aws_sdk_dynamodb::error::ErrorMetadata;
+        use aws_sdk_dynamodb::operation::put_item::PutItemError;
+        use aws_sdk_dynamodb::types::error::ConditionalCheckFailedException;
+
+        let err = PutItemError::ConditionalCheckFailedException(
+            ConditionalCheckFailedException::builder()
+                .message("The conditional request failed")
+                .meta(
+                    ErrorMetadata::builder()
+                        .code("ConditionalCheckFailedException")
+                        .build(),
+                )
+                .build(),
+        );
+        let sdk_err: SdkError<PutItemError, ()> = SdkError::service_error(err, ());
+
+        assert!(matches!(
+            RepositoryError::from(sdk_err),
+            RepositoryError::ConditionFailed
+        ));
+    }
+
+    #[test]
+    fn test_item_size_validation_maps_to_item_too_large() {
+        use aws_sdk_dynamodb::error::ErrorMetadata;
+        use aws_sdk_dynamodb::operation::put_item::PutItemError;
+
+        let service_error = |message: &str| {
+            let err = PutItemError::generic(
+                ErrorMetadata::builder()
+                    .code("ValidationException")
+                    .message(message)
+                    .build(),
+            );
+            let sdk_err: SdkError<PutItemError, ()> = SdkError::service_error(err, ());
+            RepositoryError::from(sdk_err)
+        };
+
+        assert!(matches!(
+            service_error("Item size has exceeded the maximum allowed size"),
+            RepositoryError::ItemTooLarge
+        ));
+        assert!(matches!(
+            service_error("One or more parameter values were invalid"),
+            RepositoryError::Dynamo { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sum_pages_aggregates_counts() {
+        let page_key = |n: &str| {
+            Some(HashMap::from([(
+                "sk".to_string(),
+                AttributeValue::S(n.to_string()),
+            )]))
+        };
+        let pages = std::sync::Mutex::new(vec![(3, page_key("a")), (5, page_key("b")), (2, None)]);
+        let seen = std::sync::Mutex::new(Vec::new());
+
+        let total = sum_pages(|start_key| {
+            seen.lock().unwrap().push(start_key);
+            let page = pages.lock().unwrap().remove(0);
+            async move { Ok::<_, ()>(page) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(total, 10);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![None, page_key("a"), page_key("b")]
+        );
+    }
+
+    fn query_page(items: &[&str], last_key: Option<&str>) -> QueryPage<String> {
+        QueryPage {
+            items: items.iter().map(|s| s.to_string()).collect(),
+            last_evaluated_key: last_key
+                .map(|k| HashMap::from([("sk".to_string(), AttributeValue::S(k.to_string()))])),
+            scanned_count: items.len() + 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fill_page_follows_last_evaluated_key() {
+        let pages = std::sync::Mutex::new(vec![
+            query_page(&["a", "b"], Some("b")),
+            query_page(&["c", "d"], None),
+        ]);
+        let requests = std::sync::Mutex::new(Vec::new());
+
+        let page = fill_page(5, None, |start_key, remaining| {
+            requests.lock().unwrap().push((start_key, remaining));
+            let page = pages.lock().unwrap().remove(0);
+            async move { Ok::<_, ()>(page) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(page.items, vec!["a", "b", "c", "d"]);
+        assert!(page.last_evaluated_key.is_none());
+        assert_eq!(page.scanned_count, 6);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![
+                (None, 5),
+                (query_page(&[], Some("b")).last_evaluated_key, 3)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fill_page_stops_at_limit_and_keeps_cursor() {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+
+        let page = fill_page(2, None, |_, _| {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async { Ok::<_, ()>(query_page(&["a", "b"], Some("b"))) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(page.items.len(), 2);
+        assert!(page.last_evaluated_key.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_fill_page_bounds_round_trips() {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+
+        let page = fill_page(100, None, |_, _| {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async { Ok::<_, ()>(query_page(&[], Some("x"))) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            calls.load(std::sync::atomic::Ordering::SeqCst),
+            MAX_QUERY_ROUND_TRIPS
+        );
+        assert!(page.items.is_empty());
+        assert!(page.last_evaluated_key.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_fill_page_within_can_page_past_the_default_cap() {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+
+        let page = fill_page_within(100, None, usize::MAX, |_, _| {
+            let call = calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let last_key = (call < 2 * MAX_QUERY_ROUND_TRIPS).then_some("x");
+            async move { Ok::<_, ()>(query_page(&["a"], last_key)) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(page.items.len(), 2 * MAX_QUERY_ROUND_TRIPS + 1);
+        assert!(page.last_evaluated_key.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sum_pages_propagates_errors() {
+        let result = sum_pages(|_| async { Err::<(usize, _), _>("throttled") }).await;
+
+        assert_eq!(result, Err("throttled"));
+    }
+
+    #[test]
+    fn test_item_ttl_round_trip() {
+        let mut item = sample_item();
+        item.expires_at = Some(1_900_000_000);
+
+        let attrs = item.to_dynamo(&TableSettings::default());
+        assert_eq!(attrs["ttl"].as_n().unwrap(), "1900000000");
+        assert_eq!(
+            Item::from_dynamo(&attrs, &TableSettings::default())
+                .unwrap()
+                .expires_at,
+            Some(1_900_000_000)
+        );
+
+        let attrs = sample_item().to_dynamo(&TableSettings::default());
+        assert!(!attrs.contains_key("ttl"));
+        assert_eq!(
+            Item::from_dynamo(&attrs, &TableSettings::default())
+                .unwrap()
+                .expires_at,
+            None
+        );
+    }
+
+    #[test]
+    fn test_expired_item_filtered_from_reads() {
+        let mut item = sample_item();
+        item.expires_at = Some(1_000);
+        let attrs = item.to_dynamo(&TableSettings::default());
+
+        assert!(parse_live::<Item>(&attrs, 999, &TableSettings::default())
+            .unwrap()
+            .is_some());
+        assert!(parse_live::<Item>(&attrs, 1_000, &TableSettings::default())
+            .unwrap()
+            .is_none());
+        assert!(parse_live::<Item>(
+            &sample_item().to_dynamo(&TableSettings::default()),
+            u64::MAX,
+            &TableSettings::default()
+        )
+        .unwrap()
+        .is_some());
+    }
+
+    #[test]
+    fn test_unprocessed_delete_ids_reported() {
+        let delete = DeleteRequest::builder()
+            .set_key(Some(Item::key("b")))
+            .build()
+            .unwrap();
+        let writes = vec![WriteRequest::builder().delete_request(delete).build()];
+
+        assert_eq!(
+            unprocessed_delete_ids::<Item>(&writes),
+            vec!["b".to_string()]
+        );
+        assert!(unprocessed_delete_ids::<Item>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_projection_uses_placeholders_and_adds_ttl() {
+        let client = Client::from_conf(
+            aws_sdk_dynamodb::Config::builder()
+                .behavior_version(aws_sdk_dynamodb::config::BehaviorVersion::latest())
+                .build(),
+        );
+        let settings = TableSettings {
+            ttl_attribute: "expireAt".to_string(),
+            ..TableSettings::default()
+        };
+        let builder = with_projection(client.query(), &["id", "name"], &settings);
+
+        assert_eq!(
+            builder.get_projection_expression().as_deref(),
+            Some("#p0, #p1, #p2")
+        );
+        let names = builder.get_expression_attribute_names().clone().unwrap();
+        assert_eq!(names["#p1"], "name");
+        assert_eq!(names["#p2"], "expireAt");
+    }
+
+    #[test]
+    fn test_projected_page_drops_expired_items_and_ttl() {
+        let projected = |id: &str, ttl: Option<&str>| {
+            let mut attrs = HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]);
+            if let Some(ttl) = ttl {
+                attrs.insert("expireAt".to_string(), AttributeValue::N(ttl.to_string()));
+            }
+            attrs
+        };
+        let output = QueryOutput::builder()
+            .items(projected("live", Some("99999999999")))
+            .items(projected("expired", Some("1")))
+            .items(projected("forever", None))
+            .build();
+
+        let settings = TableSettings {
+            ttl_attribute: "expireAt".to_string(),
+            ..TableSettings::default()
+        };
+        let page = projected_page(output, &["id"], &settings);
+
+        let ids: Vec<_> = page
+            .items
+            .iter()
+            .map(|item| item["id"].as_s().unwrap().as_str())
+            .collect();
+        assert_eq!(ids, vec!["live", "forever"]);
+        assert!(page.items.iter().all(|item| !item.contains_key("expireAt")));
+    }
+
+    #[test]
+    fn test_delete_condition_expression_and_check() {
+        let attrs = HashMap::from([
+            ("created_by".to_string(), AttributeValue::S("user-1".into())),
+            (
+                "updated_at".to_string(),
+                AttributeValue::S("2024-01-02T00:00:00Z".into()),
+            ),
+        ]);
+        let owned = DeleteCondition {
+            equals: Some(("created_by", "user-1")),
+            ..DeleteCondition::default()
+        };
+        let both = DeleteCondition {
+            before: Some(("updated_at", "2024-01-01T00:00:00Z")),
+            ..owned
+        };
+
+        assert_eq!(DeleteCondition::default().expression(), None);
+        assert_eq!(
+            both.expression().as_deref(),
+            Some("attribute_not_exists(sk) OR (#eq = :eq AND #before < :before)")
+        );
+        assert!(owned.holds(&attrs));
+        assert!(!both.holds(&attrs));
+        let someone_else = DeleteCondition {
+            equals: Some(("created_by", "user-2")),
+            ..DeleteCondition::default()
+        };
+        assert!(!someone_else.holds(&attrs));
+    }
+}
@@ -0,0 +1,152 @@
+use aws_sdk_dynamodb::error::{ProvideErrorMetadata, SdkError};
+use std::future::Future;
+use std::time::Duration;
+
+/// DynamoDB error codes that indicate a transient condition worth retrying
+const RETRYABLE_CODES: &[&str] = &[
+    "ProvisionedThroughputExceededException",
+    "ThrottlingException",
+    "RequestLimitExceeded",
+    "InternalServerError",
+    "ServiceUnavailable",
+];
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with "full jitter" for the given (zero-based) retry
+    fn delay(&self, retry: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        exp.mul_f64(f64::from(nanos % 1000) / 1000.0)
+    }
+}
+
+/// Run `op`, retrying with backoff while `is_retryable` says the error is transient
+pub async fn with_retry<T, E, F, Fut>(
+    policy: &RetryPolicy,
+    is_retryable: impl Fn(&E) -> bool,
+    mut op: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.max_attempts && is_retryable(&e) => {
+                tokio::time::sleep(policy.delay(attempt - 1)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Whether a DynamoDB SDK error is transient (throttling, timeouts, 5xx)
+pub fn is_retryable_sdk_error<E: ProvideErrorMetadata, R>(err: &SdkError<E, R>) -> bool {
+    match err {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => {
+            true
+        }
+        SdkError::ServiceError(service_err) => service_err
+            .err()
+            .code()
+            .is_some_and(|code| RETRYABLE_CODES.contains(&code)),
+        _ => false,
+    }
+}
+
+/// Retry a DynamoDB call with the default policy
+pub async fn retry_dynamo<T, E, R, F, Fut>(op: F) -> Result<T, SdkError<E, R>>
+where
+    E: ProvideErrorMetadata,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SdkError<E, R>>>,
+{
+    with_retry(&RetryPolicy::default(), is_retryable_sdk_error, op).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Throttled,
+        Invalid,
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success() {
+        let calls = Cell::new(0);
+        let result = with_retry(
+            &fast_policy(),
+            |e| *e == TestError::Throttled,
+            || {
+                calls.set(calls.get() + 1);
+                let attempt = calls.get();
+                async move {
+                    if attempt < 3 {
+                        Err(TestError::Throttled)
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(result, Ok(3));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_returns_immediately() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = with_retry(
+            &fast_policy(),
+            |e| *e == TestError::Throttled,
+            || {
+                calls.set(calls.get() + 1);
+                async { Err(TestError::Invalid) }
+            },
+        )
+        .await;
+
+        assert_eq!(result, Err(TestError::Invalid));
+        assert_eq!(calls.get(), 1);
+    }
+}
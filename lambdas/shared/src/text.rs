@@ -0,0 +1,113 @@
+/// Cut `value` to at most `max_chars` Unicode scalar values without splitting
+/// a user-perceived character: the cut backs off over combining marks,
+/// variation selectors, emoji modifiers, ZWJ sequences and flag pairs.
+/// This approximates extended grapheme clusters for the cases users type,
+/// without pulling in the full segmentation tables.
+pub fn truncate_graphemes(value: &str, max_chars: usize) -> &str {
+    let Some((mut end, _)) = value.char_indices().nth(max_chars) else {
+        return value;
+    };
+    while end > 0 && !is_cluster_boundary(value, end) {
+        end = value[..end]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(index, _)| index);
+    }
+    &value[..end]
+}
+
+/// Whether a cut between `value[..index]` and `value[index..]` keeps every
+/// cluster whole; `index` must be a char boundary inside the string
+fn is_cluster_boundary(value: &str, index: usize) -> bool {
+    let (Some(prev), Some(next)) = (
+        value[..index].chars().next_back(),
+        value[index..].chars().next(),
+    ) else {
+        return true;
+    };
+    if is_extender(next) || prev == ZERO_WIDTH_JOINER {
+        return false;
+    }
+    if is_regional_indicator(prev) && is_regional_indicator(next) {
+        // Flags are pairs; an odd run before the cut would split one
+        let run = value[..index]
+            .chars()
+            .rev()
+            .take_while(|c| is_regional_indicator(*c))
+            .count();
+        return run % 2 == 0;
+    }
+    true
+}
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// Characters that attach to the one before them
+fn is_extender(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'       // combining diacritical marks
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{FE00}'..='\u{FE0F}'     // variation selectors
+        | '\u{E0100}'..='\u{E01EF}'
+        | '\u{1F3FB}'..='\u{1F3FF}'   // emoji skin-tone modifiers
+        | '\u{E0020}'..='\u{E007F}'   // emoji tag sequences
+        | ZERO_WIDTH_JOINER
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_short_and_ascii() {
+        assert_eq!(truncate_graphemes("widget", 10), "widget");
+        assert_eq!(truncate_graphemes("widget", 6), "widget");
+        assert_eq!(truncate_graphemes("widget", 3), "wid");
+        assert_eq!(truncate_graphemes("widget", 0), "");
+    }
+
+    #[test]
+    fn test_truncate_counts_chars_not_bytes() {
+        assert_eq!(truncate_graphemes("héllo wörld", 7), "héllo w");
+        assert_eq!(truncate_graphemes("😀😀😀", 2), "😀😀");
+    }
+
+    #[test]
+    fn test_truncate_keeps_combining_marks_with_base() {
+        // "e" + combining acute: cutting after 2 chars would orphan the accent's base
+        let value = "ae\u{0301}b";
+        assert_eq!(truncate_graphemes(value, 2), "a");
+        assert_eq!(truncate_graphemes(value, 3), "ae\u{0301}");
+    }
+
+    #[test]
+    fn test_truncate_never_splits_emoji_sequences() {
+        // 👍🏽: thumbs up + skin tone
+        assert_eq!(truncate_graphemes("x\u{1F44D}\u{1F3FD}", 2), "x");
+        // 👩‍💻: woman + ZWJ + laptop
+        let coder = "\u{1F469}\u{200D}\u{1F4BB}";
+        assert_eq!(truncate_graphemes(&format!("a{coder}"), 2), "a");
+        assert_eq!(truncate_graphemes(&format!("a{coder}"), 3), "a");
+        assert_eq!(
+            truncate_graphemes(&format!("a{coder}b"), 4),
+            format!("a{coder}")
+        );
+    }
+
+    #[test]
+    fn test_truncate_keeps_flag_pairs_whole() {
+        // 🇫🇷🇩🇪
+        let flags = "\u{1F1EB}\u{1F1F7}\u{1F1E9}\u{1F1EA}";
+        assert_eq!(truncate_graphemes(flags, 3), "\u{1F1EB}\u{1F1F7}");
+        assert_eq!(truncate_graphemes(flags, 1), "");
+        assert_eq!(truncate_graphemes(flags, 4), flags);
+    }
+}
@@ -47,7 +47,8 @@ class MainActivity : ComponentActivity() {
         initialize(ApiConfig(
             apiUrl = API_URL,
             cognitoDomain = COGNITO_DOMAIN,
-            cognitoClientId = COGNITO_CLIENT_ID
+            cognitoClientId = COGNITO_CLIENT_ID,
+            allowedRedirectUris = listOf(REDIRECT_URI)
         ))
         
         // Check if already authenticated
@@ -24,7 +24,8 @@ class AuthManager: NSObject, ObservableObject {
         let config = ApiConfig(
             apiUrl: apiUrl,
             cognitoDomain: cognitoDomain,
-            cognitoClientId: cognitoClientId
+            cognitoClientId: cognitoClientId,
+            allowedRedirectUris: [redirectUri]
         )
         initialize(config: config)
         
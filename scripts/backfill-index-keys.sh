@@ -0,0 +1,39 @@
+#!/usr/bin/env bash
+# Backfill item index keys after the gsi3 index was added.
+#
+# gsi1sk holds created_at (created ranges) and gsi3pk/gsi3sk hold updated_at
+# (modified_since). Items written before gsi3 existed have no gsi3 keys, so
+# modified_since never returns them, and items written while gsi1sk briefly
+# held updated_at sort wrongly in created ranges. This rewrites the keys from
+# each item's own timestamps. Items that are already right are skipped, so
+# it is safe to re-run.
+#
+# Run it after `terraform apply` has created gsi3 and the new API is deployed:
+#   scripts/backfill-index-keys.sh <table-name>
+set -euo pipefail
+
+table="${1:?usage: $0 <table-name>}"
+
+# The CLI follows LastEvaluatedKey itself and prints every page's items
+aws dynamodb query \
+  --table-name "$table" \
+  --key-condition-expression "pk = :pk" \
+  --expression-attribute-values '{":pk":{"S":"ITEM"}}' \
+  --projection-expression "pk, sk, created_at, updated_at, gsi1sk, gsi3sk" \
+  --output json |
+  jq -c '.Items[] | select(.gsi1sk.S != .created_at.S or .gsi3sk.S != .updated_at.S)' |
+  while read -r item; do
+    key=$(jq -c '{pk, sk}' <<<"$item")
+    values=$(jq -c '{":pk": .pk, ":created": .created_at, ":updated": .updated_at}' <<<"$item")
+    # An item updated since it was read already has the right keys
+    if aws dynamodb update-item \
+      --table-name "$table" \
+      --key "$key" \
+      --update-expression "SET gsi1sk = :created, gsi3pk = :pk, gsi3sk = :updated" \
+      --condition-expression "updated_at = :updated" \
+      --expression-attribute-values "$values"; then
+      echo "updated $(jq -r '.sk.S' <<<"$item")"
+    else
+      echo "skipped $(jq -r '.sk.S' <<<"$item")" >&2
+    fi
+  done
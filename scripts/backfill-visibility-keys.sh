@@ -0,0 +1,43 @@
+#!/usr/bin/env bash
+# Backfill item visibility keys after the gsi4 index was added.
+#
+# gsi4pk/gsi4sk list live items by who may see them: PUBLIC for public
+# items, PRIVATE#<owner> for private ones, each in table order. GET /items
+# reads those partitions for anonymous and signed-in callers, so items
+# written before gsi4 existed aren't listed until they have the keys. This
+# sets them from each item's own public and created_by. Soft-deleted items
+# and items that are already right are skipped, so it is safe to re-run.
+#
+# Run it after `terraform apply` has created gsi4 and the new API is deployed:
+#   scripts/backfill-visibility-keys.sh <table-name>
+set -euo pipefail
+
+table="${1:?usage: $0 <table-name>}"
+
+# The CLI follows LastEvaluatedKey itself and prints every page's items
+aws dynamodb query \
+  --table-name "$table" \
+  --key-condition-expression "pk = :pk" \
+  --expression-attribute-values '{":pk":{"S":"ITEM"}}' \
+  --projection-expression "pk, sk, created_by, #public, deleted_at, updated_at, gsi4pk, gsi4sk" \
+  --expression-attribute-names '{"#public":"public"}' \
+  --output json |
+  jq -c '.Items[]
+    | select(.deleted_at == null)
+    | .want = (if .public.BOOL == true then "PUBLIC" else "PRIVATE#\(.created_by.S // "unknown")" end)
+    | select(.gsi4pk.S != .want or .gsi4sk.S != .sk.S)' |
+  while read -r item; do
+    key=$(jq -c '{pk, sk}' <<<"$item")
+    values=$(jq -c '{":gsi4pk": {S: .want}, ":sk": .sk, ":updated": .updated_at}' <<<"$item")
+    # An item updated since it was read already has the right keys
+    if aws dynamodb update-item \
+      --table-name "$table" \
+      --key "$key" \
+      --update-expression "SET gsi4pk = :gsi4pk, gsi4sk = :sk" \
+      --condition-expression "updated_at = :updated" \
+      --expression-attribute-values "$values"; then
+      echo "updated $(jq -r '.sk.S' <<<"$item")"
+    else
+      echo "skipped $(jq -r '.sk.S' <<<"$item")" >&2
+    fi
+  done